@@ -1,17 +1,56 @@
+use crate::math_objects::basic_shapes::Rectangle as MathRectangle;
+use crate::math_objects::{
+    resample_polyline, FunctionGraph, Line, MathCircle, MathPath, MorphOutline, ParametricCurve,
+    Position2D, RevealProgress, Style as MathStyle, ToMathPath,
+};
+use bevy::platform::collections::{HashMap, HashSet};
 use bevy::prelude::*;
 
+pub mod rate_functions;
+pub mod timeline;
+pub mod updaters;
+
+pub use rate_functions::*;
+pub use timeline::*;
+pub use updaters::*;
+
+/// 变形动画中轮廓重新采样的点数
+const MORPH_SAMPLES: usize = 128;
+
 pub struct AnimationPlugin;
 
 impl Plugin for AnimationPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<MathAnimation>()
-            .add_systems(Update, update_animations);
+            .register_type::<AnimationType>()
+            .register_type::<RateFunction>()
+            .register_type::<ValueTracker>()
+            .init_resource::<Timeline>()
+            .add_systems(
+                Update,
+                (
+                    advance_animations,
+                    drive_timeline,
+                    evaluate_animations,
+                    apply_custom_updaters,
+                    apply_updaters,
+                )
+                    .chain(),
+            );
     }
 }
 
 /// 数学动画组件
+///
+/// 可以直接挂在被动画的数学对象上，也可以作为独立实体通过 target 指向对象，
+/// 后者允许同一对象在时间轴上先后播放多个动画。
 #[derive(Component, Reflect, Clone)]
 pub struct MathAnimation {
+    /// 被动画的对象，None 表示动画所在的实体本身
+    pub target: Option<Entity>,
+    pub animation_type: AnimationType,
+    /// 把时间进度映射为动画进度的速率函数
+    pub rate_function: RateFunction,
     pub duration: f32,
     pub elapsed: f32,
     pub is_playing: bool,
@@ -21,6 +60,9 @@ pub struct MathAnimation {
 impl Default for MathAnimation {
     fn default() -> Self {
         Self {
+            target: None,
+            animation_type: AnimationType::Fade { from: 0.0, to: 1.0 },
+            rate_function: RateFunction::Smooth,
             duration: 1.0,
             elapsed: 0.0,
             is_playing: false,
@@ -29,29 +71,505 @@ impl Default for MathAnimation {
     }
 }
 
+impl MathAnimation {
+    /// 创建未开始播放的动画
+    pub fn new(animation_type: AnimationType, duration: f32) -> Self {
+        Self {
+            animation_type,
+            duration,
+            ..default()
+        }
+    }
+
+    /// 开始或继续播放，已播放完的动画从头重播
+    pub fn play(&mut self) {
+        if self.elapsed >= self.duration {
+            self.elapsed = 0.0;
+        }
+        self.is_playing = true;
+    }
+
+    /// 暂停播放，对象停留在当前状态
+    pub fn pause(&mut self) {
+        self.is_playing = false;
+    }
+}
+
+/// 创建作用于 target 的独立动画实体
+pub fn create_animation(
+    commands: &mut Commands,
+    target: Entity,
+    animation: MathAnimation,
+) -> Entity {
+    commands
+        .spawn(MathAnimation {
+            target: Some(target),
+            ..animation
+        })
+        .id()
+}
+
 /// 动画类型枚举
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Reflect)]
 pub enum AnimationType {
-    Transform,
-    Fade,
+    /// 相对起始状态平移 shift、缩放 scale 倍并旋转 rotation 弧度
+    Transform {
+        shift: Vec2,
+        scale: f32,
+        rotation: f32,
+    },
+    /// 透明度从 from 变化到 to
+    Fade { from: f32, to: f32 },
+    /// 逐步描出轮廓，填充同步显现
     Draw,
+    /// 前半段描出轮廓，后半段显现填充
     Write,
-    Morph,
+    /// 轮廓变形为 target（以 Position2D 为原点），style 不为 None 时颜色、线宽和透明度同步过渡
+    Morph {
+        #[reflect(ignore)]
+        target: MathPath,
+        style: Option<MathStyle>,
+    },
+    /// ValueTracker 的值变化到 to
+    SetValue { to: f32 },
 }
 
-/// 更新动画的系统
-fn update_animations(mut query: Query<&mut MathAnimation>, time: Res<Time>) {
-    for mut animation in query.iter_mut() {
-        if animation.is_playing {
-            animation.elapsed += time.delta_secs();
+impl AnimationType {
+    /// 界面上显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            AnimationType::Transform { .. } => "变换",
+            AnimationType::Fade { .. } => "淡入淡出",
+            AnimationType::Draw => "描绘",
+            AnimationType::Write => "书写",
+            AnimationType::Morph { .. } => "变形",
+            AnimationType::SetValue { .. } => "数值变化",
+        }
+    }
 
-            if animation.elapsed >= animation.duration {
-                if animation.loop_animation {
-                    animation.elapsed = 0.0;
-                } else {
-                    animation.is_playing = false;
+    fn channel(&self) -> AnimationChannel {
+        match self {
+            AnimationType::Transform { .. } => AnimationChannel::Transform,
+            AnimationType::Fade { .. } => AnimationChannel::Opacity,
+            AnimationType::Draw | AnimationType::Write => AnimationChannel::Reveal,
+            AnimationType::Morph { .. } => AnimationChannel::Outline,
+            AnimationType::SetValue { .. } => AnimationChannel::Value,
+        }
+    }
+
+    /// 以 progress（已经过速率函数处理）把动画应用到 state 上
+    fn apply(&self, progress: f32, state: &mut AnimatedState, shape: impl FnOnce() -> MathPath) {
+        match self {
+            AnimationType::Transform {
+                shift,
+                scale,
+                rotation,
+            } => {
+                state.position += *shift * progress;
+                state.scale *= 1.0 + (scale - 1.0) * progress;
+                state.rotation += rotation * progress;
+            }
+            AnimationType::Fade { from, to } => {
+                // 回弹类速率函数可能超出 [0, 1]，透明度需要截断
+                if let Some(style) = &mut state.style {
+                    style.opacity = (from + (to - from) * progress).clamp(0.0, 1.0);
                 }
             }
+            AnimationType::Draw => {
+                state.reveal = Some(RevealProgress {
+                    stroke: progress,
+                    fill: progress,
+                });
+            }
+            AnimationType::Write => {
+                state.reveal = Some(RevealProgress {
+                    stroke: (progress * 2.0).min(1.0),
+                    fill: (progress * 2.0 - 1.0).max(0.0),
+                });
+            }
+            AnimationType::Morph { target, style } => {
+                // 两侧都按弧长重新采样为相同点数后逐点插值，
+                // 已经变形过的对象从当前轮廓继续变形
+                let source = match &state.outline {
+                    Some(points) => resample_polyline(points, MORPH_SAMPLES),
+                    None => shape().resample(MORPH_SAMPLES),
+                };
+                let target = target.resample(MORPH_SAMPLES);
+                state.outline = Some(
+                    source
+                        .iter()
+                        .zip(&target)
+                        .map(|(from, to)| match (from.is_finite(), to.is_finite()) {
+                            (true, true) => from.lerp(*to, progress),
+                            // 断点在变形开始时只属于原轮廓，结束时只属于目标轮廓
+                            (true, false) if progress <= 0.0 => *from,
+                            (false, true) if progress >= 1.0 => *to,
+                            _ => Vec2::NAN,
+                        })
+                        .collect(),
+                );
+                if let (Some(from), Some(to)) = (&mut state.style, style) {
+                    *from = from.interpolate(to, progress);
+                }
+            }
+            AnimationType::SetValue { to } => {
+                // 从前一个动画结束时的值继续变化
+                state.value += (to - state.value) * progress;
+            }
+        }
+    }
+}
+
+/// 动画作用的对象属性通道，同一通道上的动画按开始时间依次叠加
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AnimationChannel {
+    Transform,
+    Opacity,
+    Reveal,
+    Outline,
+    Value,
+}
+
+/// 动画可修改的对象状态
+#[derive(Clone, Debug)]
+pub struct AnimatedState {
+    pub position: Vec2,
+    pub scale: Vec3,
+    /// 绕 Z 轴的旋转角（弧度）
+    pub rotation: f32,
+    /// 对象的样式，ValueTracker 等没有样式的对象为 None
+    pub style: Option<MathStyle>,
+    pub reveal: Option<RevealProgress>,
+    /// 变形后的轮廓，None 表示绘制对象自身的形状
+    pub outline: Option<Vec<Vec2>>,
+    /// ValueTracker 的值
+    pub value: f32,
+}
+
+/// 对象在任何动画作用之前的状态
+///
+/// 每次求值都从这里出发重新叠加全部动画，因此对象状态只取决于时间，
+/// 与播放、拖动还是倒放无关。对象上的动画全部移除后恢复到该状态并移除该组件。
+#[derive(Component, Clone, Debug)]
+pub struct AnimationBase(pub AnimatedState);
+
+/// 被动画修改的对象组件，ValueTracker 等非图形对象只有其中一部分
+type AnimatedObject<'a> = (
+    Option<&'a mut Position2D>,
+    Option<&'a mut Transform>,
+    Option<&'a mut MathStyle>,
+    Option<&'a mut RevealProgress>,
+    Option<&'a mut MorphOutline>,
+    Option<&'a mut ValueTracker>,
+    Option<&'a AnimationBase>,
+);
+
+/// 用于获取变形起始轮廓的形状组件
+type ShapeSource<'a> = (
+    Option<&'a MathCircle>,
+    Option<&'a Line>,
+    Option<&'a MathRectangle>,
+    Option<&'a FunctionGraph>,
+    Option<&'a ParametricCurve>,
+);
+
+/// 对象当前形状的路径（以 Position2D 为原点）
+fn shape_path((circle, line, rectangle, graph, curve): ShapeSource) -> MathPath {
+    circle
+        .map(ToMathPath::to_path)
+        .or_else(|| line.map(ToMathPath::to_path))
+        .or_else(|| rectangle.map(ToMathPath::to_path))
+        .or_else(|| graph.map(ToMathPath::to_path))
+        .or_else(|| curve.map(ToMathPath::to_path))
+        .unwrap_or_default()
+}
+
+/// 某一时刻作用于对象的动画
+struct ScheduledAnimation<'a> {
+    start: f32,
+    entity: Entity,
+    animation: &'a MathAnimation,
+    /// 经速率函数处理后的进度，尚未开始时为 None
+    progress: Option<f32>,
+}
+
+/// 推进独立播放的动画（由时间轴控制的动画除外）
+fn advance_animations(
+    mut query: Query<(Entity, &mut MathAnimation)>,
+    timeline: Res<Timeline>,
+    time: Res<Time>,
+) {
+    for (entity, mut animation) in query.iter_mut() {
+        if !animation.is_playing || timeline.contains(entity) {
+            continue;
+        }
+
+        animation.elapsed = (animation.elapsed + time.delta_secs()).min(animation.duration);
+        if animation.elapsed >= animation.duration {
+            if animation.loop_animation {
+                animation.elapsed = 0.0;
+            } else {
+                animation.is_playing = false;
+            }
+        }
+    }
+}
+
+/// 根据时间求出所有被动画对象的状态
+///
+/// 每个对象从 AnimationBase 出发，按开始时间依次叠加已经开始的动画；
+/// 某个通道上还没有任何动画开始时，由该通道上的第一个动画决定初始值
+/// （例如描绘动画开始前对象不可见）。
+pub fn evaluate_animations(
+    mut commands: Commands,
+    animations: Query<(Entity, Ref<MathAnimation>)>,
+    mut removed: RemovedComponents<MathAnimation>,
+    mut objects: Query<AnimatedObject>,
+    shapes: Query<ShapeSource>,
+    bases: Query<Entity, With<AnimationBase>>,
+    timeline: Res<Timeline>,
+) {
+    let removed_any = removed.read().count() > 0;
+    let changed = animations
+        .iter()
+        .any(|(_, animation)| animation.is_changed());
+    if !timeline.is_changed() && !changed && !removed_any {
+        return;
+    }
+
+    // 按目标对象分组
+    let mut by_target: HashMap<Entity, Vec<ScheduledAnimation>> = HashMap::new();
+    for (entity, animation) in animations.iter() {
+        let (start, started, elapsed) = match timeline.start_of(entity) {
+            Some(start) => (start, timeline.playhead >= start, timeline.playhead - start),
+            None => (
+                0.0,
+                animation.is_playing || animation.elapsed > 0.0,
+                animation.elapsed,
+            ),
+        };
+        let progress = started.then(|| {
+            let time = if animation.duration <= 0.0 {
+                1.0
+            } else {
+                (elapsed / animation.duration).clamp(0.0, 1.0)
+            };
+            animation.rate_function.apply(time)
+        });
+        by_target
+            .entry(animation.target.unwrap_or(entity))
+            .or_default()
+            .push(ScheduledAnimation {
+                start,
+                entity,
+                animation: animation.into_inner(),
+                progress,
+            });
+    }
+
+    // 动画全部移除的对象恢复到动画之前的状态，下次添加动画时重新记录
+    for entity in bases.iter() {
+        if !by_target.contains_key(&entity) {
+            commands.entity(entity).remove::<AnimationBase>();
+            by_target.insert(entity, Vec::new());
+        }
+    }
+
+    for (target, mut target_animations) in by_target {
+        let Ok((position, transform, style, reveal, outline, tracker, base)) =
+            objects.get_mut(target)
+        else {
+            continue;
+        };
+
+        let base = match base {
+            Some(base) => base.0.clone(),
+            None => {
+                let state = AnimatedState {
+                    position: position
+                        .as_ref()
+                        .map_or(Vec2::ZERO, |position| Vec2::new(position.x, position.y)),
+                    scale: transform
+                        .as_ref()
+                        .map_or(Vec3::ONE, |transform| transform.scale),
+                    rotation: transform.as_ref().map_or(0.0, |transform| {
+                        transform.rotation.to_euler(EulerRot::ZYX).0
+                    }),
+                    style: style.as_deref().cloned(),
+                    reveal: reveal.as_deref().cloned(),
+                    outline: outline.as_ref().map(|outline| outline.points.clone()),
+                    value: tracker.as_ref().map_or(0.0, |tracker| tracker.value),
+                };
+                commands.entity(target).insert(AnimationBase(state.clone()));
+                state
+            }
+        };
+
+        target_animations.sort_by(|a, b| a.start.total_cmp(&b.start).then(a.entity.cmp(&b.entity)));
+        let mut state = base;
+        let mut channels = HashSet::new();
+        for ScheduledAnimation {
+            animation,
+            progress,
+            ..
+        } in target_animations
+        {
+            let first_on_channel = channels.insert(animation.animation_type.channel());
+            let progress = match progress {
+                Some(progress) => progress,
+                None if first_on_channel => animation.rate_function.apply(0.0),
+                None => continue,
+            };
+            animation.animation_type.apply(progress, &mut state, || {
+                shapes.get(target).map(shape_path).unwrap_or_default()
+            });
+        }
+
+        if let Some(mut position) = position {
+            position.x = state.position.x;
+            position.y = state.position.y;
+        }
+        if let Some(mut transform) = transform {
+            transform.scale = state.scale;
+            transform.rotation = Quat::from_rotation_z(state.rotation);
+        }
+        if let (Some(mut style), Some(value)) = (style, state.style) {
+            style.set_if_neq(value);
+        }
+        if let Some(mut tracker) = tracker {
+            tracker.value = state.value;
+        }
+
+        match (state.reveal, reveal) {
+            (Some(value), Some(mut reveal)) => *reveal = value,
+            (Some(value), None) => {
+                commands.entity(target).insert(value);
+            }
+            (None, Some(_)) => {
+                commands.entity(target).remove::<RevealProgress>();
+            }
+            (None, None) => {}
+        }
+        match (state.outline, outline) {
+            (Some(points), Some(mut outline)) => outline.points = points,
+            (Some(points), None) => {
+                commands.entity(target).insert(MorphOutline { points });
+            }
+            (None, Some(_)) => {
+                commands.entity(target).remove::<MorphOutline>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 播放头处对象的位置、缩放、样式和描绘进度
+    type Snapshot = (Vec2, Vec3, MathStyle, (f32, f32));
+
+    /// 一个对象先平移，再同时淡出和描绘的时间轴
+    fn setup() -> (World, Schedule, Entity) {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        let object = world
+            .spawn((
+                Position2D::from(Vec2::new(1.0, -1.0)),
+                Transform::default(),
+                MathStyle::default(),
+            ))
+            .id();
+        let mut spawn = |animation_type: AnimationType, rate_function: RateFunction| {
+            world
+                .spawn(MathAnimation {
+                    target: Some(object),
+                    rate_function,
+                    ..MathAnimation::new(animation_type, 1.0)
+                })
+                .id()
+        };
+        let shift = spawn(
+            AnimationType::Transform {
+                shift: Vec2::new(2.0, 1.0),
+                scale: 2.0,
+                rotation: 0.5,
+            },
+            RateFunction::Smooth,
+        );
+        let fade = spawn(
+            AnimationType::Fade { from: 1.0, to: 0.2 },
+            RateFunction::Linear,
+        );
+        let draw = spawn(AnimationType::Draw, RateFunction::EaseInOutQuad);
+        let mut timeline = Timeline::default();
+        timeline.push(TimelineBlock::Play(shift));
+        timeline.push(TimelineBlock::Group(vec![
+            TimelineBlock::Play(fade),
+            TimelineBlock::Play(draw),
+        ]));
+        world.insert_resource(timeline);
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems((drive_timeline, evaluate_animations).chain());
+        // 第一帧展开时间轴
+        schedule.run(&mut world);
+        (world, schedule, object)
+    }
+
+    fn frame(world: &mut World, schedule: &mut Schedule, seconds: f32) {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        schedule.run(world);
+    }
+
+    fn snapshot(world: &World, object: Entity) -> Snapshot {
+        let position = world.get::<Position2D>(object).unwrap();
+        let reveal = world.get::<RevealProgress>(object).unwrap();
+        (
+            Vec2::new(position.x, position.y),
+            world.get::<Transform>(object).unwrap().scale,
+            world.get::<MathStyle>(object).unwrap().clone(),
+            (reveal.stroke, reveal.fill),
+        )
+    }
+
+    #[test]
+    fn state_depends_only_on_playhead() {
+        // 正放到 1.5 秒
+        let (mut world, mut schedule, object) = setup();
+        world.resource_mut::<Timeline>().play_with_speed(1.0);
+        for _ in 0..6 {
+            frame(&mut world, &mut schedule, 0.25);
         }
+        assert_eq!(world.resource::<Timeline>().playhead, 1.5);
+        let played = snapshot(&world, object);
+
+        // 直接拖动到 1.5 秒
+        let (mut world, mut schedule, object) = setup();
+        world.resource_mut::<Timeline>().seek(1.5);
+        frame(&mut world, &mut schedule, 0.0);
+        let seeked = snapshot(&world, object);
+
+        // 先拖到结尾，再倒放到 1.5 秒
+        let (mut world, mut schedule, object) = setup();
+        world.resource_mut::<Timeline>().seek(2.0);
+        frame(&mut world, &mut schedule, 0.0);
+        world.resource_mut::<Timeline>().play_with_speed(-1.0);
+        frame(&mut world, &mut schedule, 0.25);
+        frame(&mut world, &mut schedule, 0.25);
+        assert_eq!(world.resource::<Timeline>().playhead, 1.5);
+        let reversed = snapshot(&world, object);
+
+        assert_eq!(played, seeked);
+        assert_eq!(played, reversed);
+        // 平移已完成，淡出和描绘进行到一半
+        assert_eq!(played.0, Vec2::new(3.0, 0.0));
+        assert_eq!(played.1, Vec3::splat(2.0));
+        assert!((played.2.opacity - 0.6).abs() < 1e-5);
+        assert_eq!(played.3, (0.5, 0.5));
     }
 }
@@ -0,0 +1,307 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 速率函数：把线性时间进度映射为动画进度
+///
+/// 输入与输出都以 0~1 为主，Back 类和 Wiggle 会短暂超出该范围。
+#[derive(Debug, Clone, Copy, PartialEq, Default, Reflect, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RateFunction {
+    Linear,
+    /// Manim 默认的平滑曲线（归一化 sigmoid）
+    #[default]
+    Smooth,
+    EaseInQuad,
+    EaseOutQuad,
+    EaseInOutQuad,
+    EaseInCubic,
+    EaseOutCubic,
+    EaseInOutCubic,
+    EaseInExpo,
+    EaseOutExpo,
+    EaseInOutExpo,
+    /// 前半段平滑到达终点，后半段平滑返回起点
+    ThereAndBack,
+    /// 在起点附近来回摆动 wiggles 次后回到起点
+    Wiggle {
+        wiggles: f32,
+    },
+    /// 起步时先反向回拉
+    EaseInBack,
+    /// 冲过终点后回弹
+    EaseOutBack,
+    /// CSS 风格的三次贝塞尔曲线，控制点为 (x1, y1)、(x2, y2)
+    CubicBezier {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+    },
+}
+
+/// Back 缓动的回拉强度
+const BACK_OVERSHOOT: f32 = 1.70158;
+
+impl RateFunction {
+    /// 界面上可选的速率函数
+    pub const PRESETS: [RateFunction; 16] = [
+        RateFunction::Linear,
+        RateFunction::Smooth,
+        RateFunction::EaseInQuad,
+        RateFunction::EaseOutQuad,
+        RateFunction::EaseInOutQuad,
+        RateFunction::EaseInCubic,
+        RateFunction::EaseOutCubic,
+        RateFunction::EaseInOutCubic,
+        RateFunction::EaseInExpo,
+        RateFunction::EaseOutExpo,
+        RateFunction::EaseInOutExpo,
+        RateFunction::ThereAndBack,
+        RateFunction::Wiggle { wiggles: 2.0 },
+        RateFunction::EaseInBack,
+        RateFunction::EaseOutBack,
+        RateFunction::CubicBezier {
+            x1: 0.25,
+            y1: 0.1,
+            x2: 0.25,
+            y2: 1.0,
+        },
+    ];
+
+    /// 界面上显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            RateFunction::Linear => "线性",
+            RateFunction::Smooth => "平滑",
+            RateFunction::EaseInQuad => "二次缓入",
+            RateFunction::EaseOutQuad => "二次缓出",
+            RateFunction::EaseInOutQuad => "二次缓入缓出",
+            RateFunction::EaseInCubic => "三次缓入",
+            RateFunction::EaseOutCubic => "三次缓出",
+            RateFunction::EaseInOutCubic => "三次缓入缓出",
+            RateFunction::EaseInExpo => "指数缓入",
+            RateFunction::EaseOutExpo => "指数缓出",
+            RateFunction::EaseInOutExpo => "指数缓入缓出",
+            RateFunction::ThereAndBack => "往返",
+            RateFunction::Wiggle { .. } => "摆动",
+            RateFunction::EaseInBack => "回拉缓入",
+            RateFunction::EaseOutBack => "回弹缓出",
+            RateFunction::CubicBezier { .. } => "贝塞尔曲线",
+        }
+    }
+
+    /// 计算时间进度 t（0~1）对应的动画进度
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            RateFunction::Linear => t,
+            RateFunction::Smooth => smooth(t),
+            RateFunction::EaseInQuad => t * t,
+            RateFunction::EaseOutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            RateFunction::EaseInOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) * 0.5
+                }
+            }
+            RateFunction::EaseInCubic => t.powi(3),
+            RateFunction::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            RateFunction::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) * 0.5
+                }
+            }
+            RateFunction::EaseInExpo => {
+                if t <= 0.0 {
+                    0.0
+                } else {
+                    2f32.powf(10.0 * t - 10.0)
+                }
+            }
+            RateFunction::EaseOutExpo => {
+                if t >= 1.0 {
+                    1.0
+                } else {
+                    1.0 - 2f32.powf(-10.0 * t)
+                }
+            }
+            RateFunction::EaseInOutExpo => {
+                if t <= 0.0 {
+                    0.0
+                } else if t >= 1.0 {
+                    1.0
+                } else if t < 0.5 {
+                    2f32.powf(20.0 * t - 10.0) * 0.5
+                } else {
+                    (2.0 - 2f32.powf(-20.0 * t + 10.0)) * 0.5
+                }
+            }
+            RateFunction::ThereAndBack => there_and_back(t),
+            RateFunction::Wiggle { wiggles } => {
+                there_and_back(t) * (wiggles * std::f32::consts::PI * t).sin()
+            }
+            RateFunction::EaseInBack => (BACK_OVERSHOOT + 1.0) * t.powi(3) - BACK_OVERSHOOT * t * t,
+            RateFunction::EaseOutBack => {
+                1.0 + (BACK_OVERSHOOT + 1.0) * (t - 1.0).powi(3)
+                    + BACK_OVERSHOOT * (t - 1.0).powi(2)
+            }
+            RateFunction::CubicBezier { x1, y1, x2, y2 } => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+}
+
+/// Manim 的 smooth：把 sigmoid 归一化到 [0, 1]
+fn smooth(t: f32) -> f32 {
+    const INFLECTION: f32 = 10.0;
+    let sigmoid = |x: f32| 1.0 / (1.0 + (-x).exp());
+    let error = sigmoid(-INFLECTION * 0.5);
+    ((sigmoid(INFLECTION * (t - 0.5)) - error) / (1.0 - 2.0 * error)).clamp(0.0, 1.0)
+}
+
+fn there_and_back(t: f32) -> f32 {
+    if t < 0.5 {
+        smooth(2.0 * t)
+    } else {
+        smooth(2.0 - 2.0 * t)
+    }
+}
+
+/// 三次贝塞尔缓动：先由 x = t 解出曲线参数 s，再返回 y(s)
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, t: f32) -> f32 {
+    // 端点固定为 (0, 0) 和 (1, 1) 的一维贝塞尔分量
+    let bezier = |a: f32, b: f32, s: f32| {
+        let inverse = 1.0 - s;
+        3.0 * inverse * inverse * s * a + 3.0 * inverse * s * s * b + s * s * s
+    };
+    let derivative = |a: f32, b: f32, s: f32| {
+        let inverse = 1.0 - s;
+        3.0 * inverse * inverse * a + 6.0 * inverse * s * (b - a) + 3.0 * s * s * (1.0 - b)
+    };
+
+    // 牛顿迭代，斜率过小时退回二分法
+    let mut s = t;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, s) - t;
+        if error.abs() < 1e-5 {
+            return bezier(y1, y2, s);
+        }
+        let slope = derivative(x1, x2, s);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        s = (s - error / slope).clamp(0.0, 1.0);
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = t;
+    for _ in 0..32 {
+        let x = bezier(x1, x2, s);
+        if (x - t).abs() < 1e-5 {
+            break;
+        }
+        if x < t {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) * 0.5;
+    }
+    bezier(y1, y2, s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASE: RateFunction = RateFunction::CubicBezier {
+        x1: 0.25,
+        y1: 0.1,
+        x2: 0.25,
+        y2: 1.0,
+    };
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn presets_start_at_zero_and_end_at_one() {
+        for rate in RateFunction::PRESETS {
+            if matches!(
+                rate,
+                RateFunction::ThereAndBack | RateFunction::Wiggle { .. }
+            ) {
+                continue;
+            }
+            assert!(
+                close(rate.apply(0.0), 0.0),
+                "{:?}(0) = {}",
+                rate,
+                rate.apply(0.0)
+            );
+            assert!(
+                close(rate.apply(1.0), 1.0),
+                "{:?}(1) = {}",
+                rate,
+                rate.apply(1.0)
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_return_to_start() {
+        for rate in [
+            RateFunction::ThereAndBack,
+            RateFunction::Wiggle { wiggles: 2.0 },
+        ] {
+            assert!(close(rate.apply(0.0), 0.0));
+            assert!(close(rate.apply(1.0), 0.0));
+        }
+        assert!(close(RateFunction::ThereAndBack.apply(0.5), 1.0));
+    }
+
+    #[test]
+    fn smooth_is_symmetric() {
+        assert!(close(RateFunction::Smooth.apply(0.5), 0.5));
+        assert!(close(
+            RateFunction::Smooth.apply(0.2) + RateFunction::Smooth.apply(0.8),
+            1.0
+        ));
+        // 超出范围的时间先截断
+        assert_eq!(RateFunction::Smooth.apply(-1.0), 0.0);
+        assert_eq!(RateFunction::Smooth.apply(2.0), 1.0);
+    }
+
+    #[test]
+    fn cubic_bezier_converges_and_is_monotonic() {
+        // 曲线上的点 (x(s), y(s)) 反解后应回到 y(s)
+        let bezier = |a: f32, b: f32, s: f32| {
+            3.0 * (1.0 - s).powi(2) * s * a + 3.0 * (1.0 - s) * s * s * b + s.powi(3)
+        };
+        for i in 0..=20 {
+            let s = i as f32 / 20.0;
+            let (x, y) = (bezier(0.25, 0.25, s), bezier(0.1, 1.0, s));
+            assert!(close(EASE.apply(x), y), "s = {s}: {} != {y}", EASE.apply(x));
+        }
+
+        let values: Vec<f32> = (0..=200).map(|i| EASE.apply(i as f32 / 200.0)).collect();
+        assert!(values.windows(2).all(|pair| pair[1] >= pair[0] - 1e-6));
+    }
+
+    #[test]
+    fn cubic_bezier_with_flat_ends_is_symmetric() {
+        // 两端 x 方向斜率为零，靠近端点时牛顿迭代退回二分法
+        let rate = RateFunction::CubicBezier {
+            x1: 0.0,
+            y1: 0.0,
+            x2: 1.0,
+            y2: 1.0,
+        };
+        assert!(close(rate.apply(0.5), 0.5));
+        assert!(close(rate.apply(0.25) + rate.apply(0.75), 1.0));
+        assert!(close(rate.apply(1e-4) + rate.apply(1.0 - 1e-4), 1.0));
+    }
+}
@@ -0,0 +1,334 @@
+use super::MathAnimation;
+use bevy::prelude::*;
+
+/// 时间轴中的一个块
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimelineBlock {
+    /// 播放单个动画（MathAnimation 所在的实体）
+    Play(Entity),
+    /// 等待若干秒
+    Wait(f32),
+    /// 依次播放
+    Sequence(Vec<TimelineBlock>),
+    /// 同时开始播放，全部结束后才进入下一块
+    Group(Vec<TimelineBlock>),
+    /// 依次错开 lag 秒开始播放
+    LaggedStart {
+        lag: f32,
+        blocks: Vec<TimelineBlock>,
+    },
+}
+
+impl TimelineBlock {
+    /// 从 start 开始展开为动画条目，返回该块的结束时间
+    fn schedule(
+        &self,
+        start: f32,
+        duration_of: &impl Fn(Entity) -> Option<f32>,
+        entries: &mut Vec<TimelineEntry>,
+    ) -> f32 {
+        match self {
+            TimelineBlock::Play(animation) => match duration_of(*animation) {
+                Some(duration) => {
+                    entries.push(TimelineEntry {
+                        animation: *animation,
+                        start,
+                        duration,
+                    });
+                    start + duration
+                }
+                // 动画实体已被删除
+                None => start,
+            },
+            TimelineBlock::Wait(duration) => start + duration.max(0.0),
+            TimelineBlock::Sequence(blocks) => blocks.iter().fold(start, |time, block| {
+                block.schedule(time, duration_of, entries)
+            }),
+            TimelineBlock::Group(blocks) => blocks.iter().fold(start, |end, block| {
+                end.max(block.schedule(start, duration_of, entries))
+            }),
+            TimelineBlock::LaggedStart { lag, blocks } => {
+                blocks.iter().enumerate().fold(start, |end, (i, block)| {
+                    let block_start = start + lag.max(0.0) * i as f32;
+                    end.max(block.schedule(block_start, duration_of, entries))
+                })
+            }
+        }
+    }
+}
+
+/// 展开后单个动画在时间轴上的位置
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimelineEntry {
+    pub animation: Entity,
+    pub start: f32,
+    pub duration: f32,
+}
+
+/// 动画时间轴资源：按顺序播放 blocks，并由全局播放头驱动其中的动画
+#[derive(Resource)]
+pub struct Timeline {
+    /// 顶层块，依次播放
+    pub blocks: Vec<TimelineBlock>,
+    /// 播放头位置（秒）
+    pub playhead: f32,
+    pub is_playing: bool,
+    /// 播放速度倍率，负数表示倒放
+    pub speed: f32,
+    /// 由 blocks 展开得到的动画条目，由 drive_timeline 每帧更新
+    entries: Vec<TimelineEntry>,
+    duration: f32,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            blocks: Vec::new(),
+            playhead: 0.0,
+            is_playing: false,
+            speed: 1.0,
+            entries: Vec::new(),
+            duration: 0.0,
+        }
+    }
+}
+
+impl Timeline {
+    /// 在时间轴末尾追加一个块
+    pub fn push(&mut self, block: TimelineBlock) {
+        self.blocks.push(block);
+    }
+
+    /// 清空时间轴并回到开头
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+        self.entries.clear();
+        self.duration = 0.0;
+        self.playhead = 0.0;
+        self.is_playing = false;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// 时间轴总时长（秒）
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    /// 展开后的动画条目
+    pub fn entries(&self) -> &[TimelineEntry] {
+        &self.entries
+    }
+
+    /// 动画是否由时间轴控制
+    pub fn contains(&self, animation: Entity) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.animation == animation)
+    }
+
+    /// 动画在时间轴上的开始时间
+    pub fn start_of(&self, animation: Entity) -> Option<f32> {
+        self.entries
+            .iter()
+            .find(|entry| entry.animation == animation)
+            .map(|entry| entry.start)
+    }
+
+    /// 播放或暂停，已播放到尽头时从另一端重新播放
+    pub fn toggle_playing(&mut self) {
+        if self.is_playing {
+            self.is_playing = false;
+        } else {
+            if self.speed >= 0.0 && self.playhead >= self.duration {
+                self.playhead = 0.0;
+            } else if self.speed < 0.0 && self.playhead <= 0.0 {
+                self.playhead = self.duration;
+            }
+            self.is_playing = true;
+        }
+    }
+
+    /// 按 speed 的方向开始播放（正数正放，负数倒放）
+    pub fn play_with_speed(&mut self, speed: f32) {
+        self.speed = speed;
+        self.is_playing = false;
+        self.toggle_playing();
+    }
+
+    /// 移动播放头
+    pub fn seek(&mut self, time: f32) {
+        self.playhead = time.clamp(0.0, self.duration);
+    }
+
+    /// 按各动画的时长展开 blocks，返回动画条目和总时长
+    fn schedule(&self, duration_of: &impl Fn(Entity) -> Option<f32>) -> (Vec<TimelineEntry>, f32) {
+        let mut entries = Vec::new();
+        let duration = self.blocks.iter().fold(0.0, |time, block| {
+            block.schedule(time, duration_of, &mut entries)
+        });
+        (entries, duration)
+    }
+}
+
+/// 展开时间轴并推进播放头
+///
+/// 对象状态由 evaluate_animations 根据播放头直接求出，这里只负责时间。
+pub fn drive_timeline(
+    mut timeline: ResMut<Timeline>,
+    animations: Query<&MathAnimation>,
+    time: Res<Time>,
+) {
+    // 每帧重新展开，动画时长修改或动画被删除后时间轴随之更新
+    let (entries, duration) = timeline.schedule(&|entity| {
+        animations
+            .get(entity)
+            .ok()
+            .map(|animation| animation.duration)
+    });
+    if entries != timeline.entries {
+        timeline.entries = entries;
+    }
+    if timeline.duration != duration {
+        timeline.duration = duration;
+        timeline.playhead = timeline.playhead.min(duration);
+    }
+
+    if timeline.is_playing {
+        let duration = timeline.duration;
+        let playhead = timeline.playhead + time.delta_secs() * timeline.speed;
+        timeline.playhead = playhead.clamp(0.0, duration);
+        if playhead <= 0.0 || playhead >= duration {
+            timeline.is_playing = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 实体 n 的动画时长为 n 秒
+    fn animation(n: u32) -> Entity {
+        Entity::from_raw(n)
+    }
+
+    fn duration_of(entity: Entity) -> Option<f32> {
+        Some(entity.index() as f32)
+    }
+
+    /// 展开后的时间轴，与 drive_timeline 的结果一致
+    fn timeline(blocks: Vec<TimelineBlock>) -> Timeline {
+        let mut timeline = Timeline {
+            blocks,
+            ..default()
+        };
+        (timeline.entries, timeline.duration) = timeline.schedule(&duration_of);
+        timeline
+    }
+
+    fn starts(timeline: &Timeline) -> Vec<(u32, f32)> {
+        timeline
+            .entries()
+            .iter()
+            .map(|entry| (entry.animation.index(), entry.start))
+            .collect()
+    }
+
+    #[test]
+    fn blocks_expand_to_start_times() {
+        let timeline = timeline(vec![
+            TimelineBlock::Play(animation(1)),
+            TimelineBlock::Wait(0.5),
+            TimelineBlock::Group(vec![
+                TimelineBlock::Play(animation(2)),
+                TimelineBlock::Sequence(vec![
+                    TimelineBlock::Play(animation(1)),
+                    TimelineBlock::Play(animation(3)),
+                ]),
+            ]),
+            TimelineBlock::LaggedStart {
+                lag: 0.25,
+                blocks: vec![
+                    TimelineBlock::Play(animation(2)),
+                    TimelineBlock::Play(animation(1)),
+                    TimelineBlock::Play(animation(1)),
+                ],
+            },
+        ]);
+        assert_eq!(
+            starts(&timeline),
+            [
+                (1, 0.0),
+                (2, 1.5),
+                (1, 1.5),
+                (3, 2.5),
+                (2, 5.5),
+                (1, 5.75),
+                (1, 6.0)
+            ]
+        );
+        // 同时播放的块等最长的子块结束，错开播放的块等最晚结束的子块
+        assert_eq!(timeline.duration(), 7.5);
+        assert_eq!(timeline.start_of(animation(3)), Some(2.5));
+        assert!(!timeline.contains(animation(4)));
+    }
+
+    #[test]
+    fn empty_and_negative_blocks_take_no_time() {
+        let timeline = timeline(vec![
+            TimelineBlock::Wait(-1.0),
+            TimelineBlock::Group(Vec::new()),
+            TimelineBlock::LaggedStart {
+                lag: -1.0,
+                blocks: vec![
+                    TimelineBlock::Play(animation(1)),
+                    TimelineBlock::Play(animation(2)),
+                ],
+            },
+        ]);
+        assert_eq!(starts(&timeline), [(1, 0.0), (2, 0.0)]);
+        assert_eq!(timeline.duration(), 2.0);
+    }
+
+    #[test]
+    fn deleted_animations_are_skipped() {
+        let mut timeline = Timeline::default();
+        timeline.push(TimelineBlock::Play(animation(2)));
+        timeline.push(TimelineBlock::Play(animation(3)));
+        let (entries, duration) =
+            timeline.schedule(&|entity| (entity != animation(2)).then(|| entity.index() as f32));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].start, 0.0);
+        assert_eq!(duration, 3.0);
+    }
+
+    #[test]
+    fn seek_clamps_to_timeline() {
+        let mut timeline = timeline(vec![TimelineBlock::Play(animation(2))]);
+        timeline.seek(1.5);
+        assert_eq!(timeline.playhead, 1.5);
+        timeline.seek(5.0);
+        assert_eq!(timeline.playhead, 2.0);
+        timeline.seek(-1.0);
+        assert_eq!(timeline.playhead, 0.0);
+    }
+
+    #[test]
+    fn toggle_playing_restarts_from_the_far_end() {
+        let mut timeline = timeline(vec![TimelineBlock::Play(animation(2))]);
+        timeline.seek(2.0);
+        timeline.toggle_playing();
+        assert!(timeline.is_playing);
+        assert_eq!(timeline.playhead, 0.0);
+        timeline.toggle_playing();
+        assert!(!timeline.is_playing);
+
+        // 倒放到起点后从结尾重新倒放
+        timeline.play_with_speed(-1.0);
+        assert!(timeline.is_playing);
+        assert_eq!(timeline.playhead, 2.0);
+    }
+}
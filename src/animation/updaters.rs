@@ -0,0 +1,333 @@
+use crate::math_objects::{create_circle, create_line, FunctionGraph, Line, Position2D, Style};
+use crate::render::CoordinateSystem;
+use bevy::prelude::*;
+use std::fmt;
+use std::sync::Arc;
+
+/// 数值追踪器：保存一个可被 SetValue 动画驱动的数值
+///
+/// 追踪器本身不绘制，依赖它的对象通过 Updater 每帧根据该值重建。
+#[derive(Component, Reflect, Clone, Debug, Default)]
+pub struct ValueTracker {
+    pub value: f32,
+}
+
+/// 创建数值追踪器实体
+pub fn create_value_tracker(commands: &mut Commands, value: f32) -> Entity {
+    commands.spawn(ValueTracker { value }).id()
+}
+
+/// 自定义更新函数，参数为所在对象；可通过 `entity.world()` 读取追踪器等其他实体
+#[derive(Clone)]
+pub struct UpdaterFn(pub Arc<dyn Fn(&mut EntityWorldMut) + Send + Sync>);
+
+impl fmt::Debug for UpdaterFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UpdaterFn(..)")
+    }
+}
+
+/// 更新器：每帧根据追踪器的值重建所在的对象
+#[derive(Component, Clone, Debug)]
+pub enum Updater {
+    /// 移动到函数图形上 x = 追踪值 的点
+    PointOnGraph { graph: Entity, tracker: Entity },
+    /// 函数图形在 x = 追踪值 处的切线，length 为数学长度
+    TangentLine {
+        graph: Entity,
+        tracker: Entity,
+        length: f32,
+    },
+    /// 显示追踪值的文本标签，跟随 anchor 对象并偏移 offset 像素
+    ValueLabel {
+        tracker: Entity,
+        prefix: String,
+        decimals: usize,
+        anchor: Option<Entity>,
+        offset: Vec2,
+    },
+    /// 任意更新函数，在内置更新器之前执行，数值标签可以跟随它移动的对象
+    Custom(UpdaterFn),
+}
+
+impl Updater {
+    /// 由闭包创建自定义更新器
+    pub fn custom(update: impl Fn(&mut EntityWorldMut) + Send + Sync + 'static) -> Self {
+        Self::Custom(UpdaterFn(Arc::new(update)))
+    }
+
+    /// 移动到 path(追踪值)（数学坐标），path 可以捕获任意状态
+    pub fn follow(tracker: Entity, path: impl Fn(f32) -> Vec2 + Send + Sync + 'static) -> Self {
+        Self::custom(move |entity| {
+            let Some(value) = entity
+                .world()
+                .get::<ValueTracker>(tracker)
+                .map(|tracker| tracker.value)
+            else {
+                return;
+            };
+            if let Some(mut position) = entity.get_mut::<Position2D>() {
+                let point = path(value);
+                position.x = point.x;
+                position.y = point.y;
+            }
+        })
+    }
+}
+
+/// 在函数图形上创建追踪点：追踪器的值为 x 坐标，并添加跟随的圆点、切线和数值标签
+///
+/// 返回追踪器实体和依赖它的对象实体。
+pub fn create_graph_tracker(
+    commands: &mut Commands,
+    graph: Entity,
+    x: f32,
+    style: Style,
+) -> (Entity, Vec<Entity>) {
+    let tracker = create_value_tracker(commands, x);
+
+    let dot = create_circle(
+        commands,
+        Vec2::ZERO,
+        0.08,
+        Style {
+            fill_color: Some(style.stroke_color),
+            ..style.clone()
+        },
+    );
+    commands
+        .entity(dot)
+        .insert(Updater::PointOnGraph { graph, tracker });
+
+    let tangent = create_line(commands, Vec2::ZERO, Vec2::X, style);
+    commands.entity(tangent).insert(Updater::TangentLine {
+        graph,
+        tracker,
+        length: 3.0,
+    });
+
+    let label = create_value_label(
+        commands,
+        tracker,
+        "x = ".to_string(),
+        2,
+        Some(dot),
+        Vec2::new(0.0, 24.0),
+    );
+
+    (tracker, vec![dot, tangent, label])
+}
+
+/// 创建显示追踪值的文本标签，anchor 为跟随的对象，offset 为屏幕像素偏移
+pub fn create_value_label(
+    commands: &mut Commands,
+    tracker: Entity,
+    prefix: String,
+    decimals: usize,
+    anchor: Option<Entity>,
+    offset: Vec2,
+) -> Entity {
+    commands
+        .spawn((
+            Text2d::new(""),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Transform::from_xyz(0.0, 0.0, 1.0),
+            Visibility::Inherited,
+            Updater::ValueLabel {
+                tracker,
+                prefix,
+                decimals,
+                anchor,
+                offset,
+            },
+        ))
+        .id()
+}
+
+/// 函数图形在 x 处的点和单位切向量（数学坐标，已应用图形的变换）
+///
+/// 函数在 x 处无定义时返回 None。
+pub fn graph_point_and_tangent(
+    graph: &FunctionGraph,
+    position: &Position2D,
+    transform: &Transform,
+    x: f32,
+) -> Option<(Vec2, Vec2)> {
+    let y = graph.evaluate(x)?;
+    // 中心差分求斜率
+    let h = 1e-3 * x.abs().max(1.0);
+    let slope = (graph.evaluate(x + h)? - graph.evaluate(x - h)?) / (2.0 * h);
+    if !y.is_finite() || !slope.is_finite() {
+        return None;
+    }
+
+    let to_world = |local: Vec2| {
+        (transform.rotation * (local * transform.scale.truncate()).extend(0.0)).truncate()
+    };
+    let point = Vec2::new(position.x, position.y) + to_world(Vec2::new(x, y));
+    let tangent = to_world(Vec2::new(1.0, slope)).normalize_or_zero();
+    Some((point, tangent))
+}
+
+/// 执行自定义更新器，需要独占 World，在 apply_updaters 之前运行
+pub fn apply_custom_updaters(world: &mut World) {
+    let updaters: Vec<(Entity, UpdaterFn)> = world
+        .query::<(Entity, &Updater)>()
+        .iter(world)
+        .filter_map(|(entity, updater)| match updater {
+            Updater::Custom(update) => Some((entity, update.clone())),
+            _ => None,
+        })
+        .collect();
+    for (entity, update) in updaters {
+        if let Ok(mut entity) = world.get_entity_mut(entity) {
+            (update.0)(&mut entity);
+        }
+    }
+}
+
+/// 被更新器重建的对象组件
+type UpdatedObject<'a> = (
+    Option<&'a mut Position2D>,
+    Option<&'a mut Line>,
+    Option<(&'a mut Text2d, &'a mut Transform)>,
+);
+
+/// 依次执行所有更新器，标签最后更新以便跟随本帧移动过的对象
+pub fn apply_updaters(
+    updaters: Query<(Entity, &Updater)>,
+    trackers: Query<&ValueTracker>,
+    graphs: Query<(&FunctionGraph, &Position2D, &Transform), Without<Updater>>,
+    anchors: Query<&Position2D, Without<Updater>>,
+    mut objects: Query<UpdatedObject, With<Updater>>,
+    coordinate_system: Res<CoordinateSystem>,
+) {
+    let value_of = |tracker: Entity| trackers.get(tracker).ok().map(|tracker| tracker.value);
+    let graph_point = |graph: Entity, x: f32| {
+        let (graph, position, transform) = graphs.get(graph).ok()?;
+        graph_point_and_tangent(graph, position, transform, x)
+    };
+
+    for (entity, updater) in updaters.iter() {
+        let Ok((position, line, _)) = objects.get_mut(entity) else {
+            continue;
+        };
+        // 更新后的中心点和直线端点
+        let (center, endpoints) = match updater {
+            Updater::PointOnGraph { graph, tracker } => {
+                let Some((point, _)) = value_of(*tracker).and_then(|x| graph_point(*graph, x))
+                else {
+                    continue;
+                };
+                (point, None)
+            }
+            Updater::TangentLine {
+                graph,
+                tracker,
+                length,
+            } => {
+                let Some((point, tangent)) =
+                    value_of(*tracker).and_then(|x| graph_point(*graph, x))
+                else {
+                    continue;
+                };
+                let half = tangent * length * 0.5;
+                (point, Some((point - half, point + half)))
+            }
+            Updater::ValueLabel { .. } | Updater::Custom(_) => continue,
+        };
+
+        // 直线以中点（Position2D）为原点绘制，两者需同时更新
+        if let (Some(mut line), Some((start, end))) = (line, endpoints) {
+            line.start = start;
+            line.end = end;
+        }
+        if let Some(mut position) = position {
+            position.x = center.x;
+            position.y = center.y;
+        }
+    }
+
+    for (entity, updater) in updaters.iter() {
+        let Updater::ValueLabel {
+            tracker,
+            prefix,
+            decimals,
+            anchor,
+            offset,
+        } = updater
+        else {
+            continue;
+        };
+        let Some(value) = value_of(*tracker) else {
+            continue;
+        };
+        let anchor = anchor.and_then(|anchor| {
+            let position = match objects.get(anchor) {
+                Ok((Some(position), _, _)) => position,
+                _ => anchors.get(anchor).ok()?,
+            };
+            Some(Vec2::new(position.x, position.y))
+        });
+        let Ok((_, _, Some((mut text, mut transform)))) = objects.get_mut(entity) else {
+            continue;
+        };
+
+        let content = format!("{}{:.*}", prefix, *decimals, value);
+        if text.0 != content {
+            text.0 = content;
+        }
+        if let Some(anchor) = anchor {
+            transform.translation =
+                (coordinate_system.to_screen(anchor) + *offset).extend(transform.translation.z);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_updaters_read_trackers_and_capture_state() {
+        let mut world = World::new();
+        let tracker = world.spawn(ValueTracker { value: 2.0 }).id();
+        let scale = 3.0;
+        let follower = world
+            .spawn((
+                Position2D::from(Vec2::ZERO),
+                Updater::follow(tracker, move |t| Vec2::new(t * scale, t * t)),
+            ))
+            .id();
+        let counter = world
+            .spawn((
+                ValueTracker::default(),
+                Updater::custom(|entity| {
+                    if let Some(mut tracker) = entity.get_mut::<ValueTracker>() {
+                        tracker.value += 1.0;
+                    }
+                }),
+            ))
+            .id();
+
+        apply_custom_updaters(&mut world);
+        let position = world.get::<Position2D>(follower).unwrap();
+        assert_eq!(Vec2::new(position.x, position.y), Vec2::new(6.0, 4.0));
+
+        world.get_mut::<ValueTracker>(tracker).unwrap().value = -1.0;
+        apply_custom_updaters(&mut world);
+        let position = world.get::<Position2D>(follower).unwrap();
+        assert_eq!(Vec2::new(position.x, position.y), Vec2::new(-3.0, 1.0));
+        assert_eq!(world.get::<ValueTracker>(counter).unwrap().value, 2.0);
+
+        // 追踪器删除后保持原位
+        world.despawn(tracker);
+        apply_custom_updaters(&mut world);
+        let position = world.get::<Position2D>(follower).unwrap();
+        assert_eq!(Vec2::new(position.x, position.y), Vec2::new(-3.0, 1.0));
+    }
+}
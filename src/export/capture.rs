@@ -0,0 +1,263 @@
+//! 逐帧截取：按固定帧率定位时间轴，把每一帧渲染到离屏图像并交给输出（PNG 序列、GIF 或视频）
+//!
+//! 离线渲染和交互模式下的动画导出共用这一流程，画面渲染到离屏目标（见 `target` 模块），
+//! 因此输出画面与屏幕一致，只是分辨率不同。
+
+use super::gif::GifEncoder;
+use super::target::CaptureTarget;
+use super::video::VideoEncoder;
+use super::ExportError;
+use crate::animation::Timeline;
+use bevy::prelude::*;
+use bevy::render::view::window::screenshot::{Screenshot, ScreenshotCaptured};
+use bevy::tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task};
+use image::DynamicImage;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+/// 时长为 duration 秒的时间轴按 fps 截取的帧数，包含结尾一帧，静态场景只有一帧
+pub fn frame_count(duration: f32, fps: u32) -> u32 {
+    (duration * fps as f32).ceil() as u32 + 1
+}
+
+/// PNG 序列中第 index 帧（从 1 开始）的文件路径
+pub fn sequence_frame_path(dir: &Path, index: u32) -> PathBuf {
+    dir.join(format!("frame_{:05}.png", index))
+}
+
+/// 逐帧截取的输出
+pub enum CaptureOutput {
+    /// 编号的 PNG 帧，写入目录
+    PngSequence(PathBuf),
+    /// 动画 GIF，帧按顺序在后台线程量化并写入
+    Gif {
+        path: PathBuf,
+        encoder: Option<GifEncoder>,
+    },
+    /// 通过管道交给 ffmpeg 编码的视频
+    Video {
+        path: PathBuf,
+        encoder: Option<VideoEncoder>,
+    },
+}
+
+impl CaptureOutput {
+    /// 输出的目录或文件
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::PngSequence(dir) => dir,
+            Self::Gif { path, .. } | Self::Video { path, .. } => path,
+        }
+    }
+
+    /// 处理第 index 帧（从 0 开始）
+    fn add_frame(&mut self, index: u32, image: DynamicImage) -> Result<(), ExportError> {
+        match self {
+            Self::PngSequence(dir) => {
+                let path = sequence_frame_path(dir, index + 1);
+                image.save(&path).map_err(|error| ExportError::EncodeImage {
+                    path,
+                    message: error.to_string(),
+                })
+            }
+            Self::Gif { encoder, .. } => encoder
+                .as_mut()
+                .ok_or(ExportError::EncoderClosed)?
+                .add_frame(index, image),
+            Self::Video { encoder, .. } => encoder
+                .as_mut()
+                .ok_or(ExportError::EncoderClosed)?
+                .add_frame(index, image),
+        }
+    }
+
+    /// 全部帧到齐后的收尾工作，放到后台线程执行；没有收尾工作时返回 None
+    fn finish(&mut self) -> Option<Task<Result<(), ExportError>>> {
+        match self {
+            Self::PngSequence(_) => None,
+            Self::Gif { encoder, .. } => {
+                let encoder = encoder.take()?;
+                Some(AsyncComputeTaskPool::get().spawn(async move { encoder.finish() }))
+            }
+            Self::Video { encoder, .. } => {
+                let encoder = encoder.take()?;
+                Some(AsyncComputeTaskPool::get().spawn(async move { encoder.finish() }))
+            }
+        }
+    }
+}
+
+/// 逐帧截取任务，资源存在期间每帧截取一张，结束后移除并发送 FrameCaptureFinished
+#[derive(Resource)]
+pub struct FrameCapture {
+    pub fps: u32,
+    /// 截取的帧号（从 1 开始，含两端），第 n 帧对应时间 (n - 1) / fps
+    pub frames: RangeInclusive<u32>,
+    /// 下一个要截取的帧号
+    next: u32,
+    /// 已处理完的帧数
+    pub completed: u32,
+    /// 出错后不再截取新帧
+    error: Option<ExportError>,
+    target: CaptureTarget,
+    output: CaptureOutput,
+    /// 截取前的播放头和播放状态，结束后恢复
+    restore: Option<(f32, bool)>,
+    finishing: Option<Task<Result<(), ExportError>>>,
+}
+
+impl FrameCapture {
+    /// 在离屏目标上逐帧截取，结束后删除目标的相机
+    pub fn new(
+        target: CaptureTarget,
+        fps: u32,
+        frames: RangeInclusive<u32>,
+        output: CaptureOutput,
+    ) -> Self {
+        Self {
+            fps: fps.max(1),
+            next: *frames.start(),
+            frames,
+            completed: 0,
+            error: None,
+            target,
+            output,
+            restore: None,
+            finishing: None,
+        }
+    }
+
+    /// 要截取的总帧数
+    pub fn total(&self) -> u32 {
+        self.frames.end() + 1 - self.frames.start()
+    }
+}
+
+/// 逐帧导出进度事件，开始截取时和每处理完一帧时发送；completed 等于 total 后进入编码阶段
+#[derive(Event, Debug, Clone)]
+pub struct ExportProgress {
+    pub path: PathBuf,
+    pub completed: u32,
+    pub total: u32,
+}
+
+impl ExportProgress {
+    /// 进度（0 到 1）
+    pub fn fraction(&self) -> f32 {
+        self.completed as f32 / self.total.max(1) as f32
+    }
+}
+
+/// 截取结束事件
+#[derive(Event, Debug, Clone)]
+pub struct FrameCaptureFinished {
+    pub path: PathBuf,
+    pub frames: u32,
+    pub result: Result<(), ExportError>,
+}
+
+/// 按帧号定位时间轴并截取当前帧，需在 drive_timeline 之后、evaluate_animations 之前运行
+pub fn capture_frames(
+    mut commands: Commands,
+    capture: Option<ResMut<FrameCapture>>,
+    mut timeline: ResMut<Timeline>,
+    mut progress: EventWriter<ExportProgress>,
+) {
+    let Some(mut capture) = capture else {
+        return;
+    };
+    if capture.error.is_some() || capture.next > *capture.frames.end() {
+        return;
+    }
+
+    // 播放头由帧号决定，不依赖时间轴自身的播放
+    if capture.restore.is_none() {
+        capture.restore = Some((timeline.playhead, timeline.is_playing));
+        progress.write(ExportProgress {
+            path: capture.output.path().to_path_buf(),
+            completed: 0,
+            total: capture.total(),
+        });
+    }
+    timeline.is_playing = false;
+    let frame = capture.next;
+    timeline.seek((frame - 1) as f32 / capture.fps as f32);
+    capture.next += 1;
+
+    let index = frame - capture.frames.start();
+    commands
+        .spawn(Screenshot::image(capture.target.image.clone()))
+        .observe(receive_frame(index));
+}
+
+/// 截图完成后交给输出处理
+fn receive_frame(
+    index: u32,
+) -> impl FnMut(Trigger<ScreenshotCaptured>, Option<ResMut<FrameCapture>>, EventWriter<ExportProgress>)
+{
+    move |trigger, capture, mut progress| {
+        // 截取已结束（例如出错）时忽略迟到的帧
+        let Some(mut capture) = capture else {
+            return;
+        };
+        let frame = capture.target.read(trigger.event().0.clone());
+        match frame.and_then(|frame| capture.output.add_frame(index, frame)) {
+            Ok(()) => {
+                capture.completed += 1;
+                progress.write(ExportProgress {
+                    path: capture.output.path().to_path_buf(),
+                    completed: capture.completed,
+                    total: capture.total(),
+                });
+                if capture.completed % 30 == 0 || capture.completed == capture.total() {
+                    info!("已截取 {}/{} 帧", capture.completed, capture.total());
+                }
+            }
+            Err(error) => {
+                capture.error.get_or_insert(error);
+            }
+        }
+    }
+}
+
+/// 全部帧处理完（或出错）后收尾：等待编码、恢复时间轴、删除相机并发送结束事件
+pub fn finish_frame_capture(
+    mut commands: Commands,
+    capture: Option<ResMut<FrameCapture>>,
+    mut timeline: ResMut<Timeline>,
+    mut finished: EventWriter<FrameCaptureFinished>,
+) {
+    let Some(mut capture) = capture else {
+        return;
+    };
+    let result = if let Some(task) = capture.finishing.as_mut() {
+        match block_on(future::poll_once(task)) {
+            Some(result) => result,
+            None => return,
+        }
+    } else if let Some(error) = capture.error.clone() {
+        Err(error)
+    } else if capture.completed < capture.total() {
+        return;
+    } else {
+        match capture.output.finish() {
+            Some(task) => {
+                capture.finishing = Some(task);
+                return;
+            }
+            None => Ok(()),
+        }
+    };
+
+    if let Some((playhead, is_playing)) = capture.restore {
+        timeline.seek(playhead);
+        timeline.is_playing = is_playing;
+    }
+    commands.entity(capture.target.camera).despawn();
+    commands.remove_resource::<FrameCapture>();
+    finished.write(FrameCaptureFinished {
+        path: capture.output.path().to_path_buf(),
+        frames: capture.total(),
+        result,
+    });
+}
@@ -0,0 +1,261 @@
+//! 动画 GIF 编码：帧按顺序交给后台线程，用 NeuQuant 量化为每帧的局部调色板后立即写入文件
+//!
+//! 截图回调只负责排序和转交，编码线程的队列有上限，内存中只保留少量等待编码的帧。
+
+use super::ExportError;
+use ::gif::{Encoder, EncodingError, Frame, Repeat};
+use image::DynamicImage;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// NeuQuant 的采样间隔（1 到 30），越小质量越高、越慢
+const QUANTIZE_SPEED: i32 = 10;
+/// 等待编码的最大帧数，队列满时截图回调等待编码线程
+const QUEUE_SIZE: usize = 4;
+
+/// GIF 循环方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GifRepeat {
+    /// 无限循环
+    Infinite,
+    /// 总共播放的次数，1 表示只播放一次
+    Times(u16),
+}
+
+impl GifRepeat {
+    /// NETSCAPE2.0 扩展记录的额外循环次数；只播放一次时省略该扩展
+    fn loops(self) -> Option<Repeat> {
+        match self {
+            GifRepeat::Infinite => Some(Repeat::Infinite),
+            GifRepeat::Times(times) if times > 1 => Some(Repeat::Finite(times - 1)),
+            GifRepeat::Times(_) => None,
+        }
+    }
+}
+
+/// 第 index 帧（从 0 开始）的间隔，单位 1/100 秒；按累计时间取整，避免误差积累
+fn frame_delay(index: u64, fps: u64) -> u16 {
+    (((index + 1) * 100).div_ceil(fps) - (index * 100).div_ceil(fps)) as u16
+}
+
+/// 动画 GIF 编码器
+pub struct GifEncoder {
+    resolution: (u32, u32),
+    frame_count: u32,
+    /// 下一个要交给编码线程的帧序号
+    next: u32,
+    /// 提前到达、等待按顺序编码的帧（RGB）
+    pending: BTreeMap<u32, Vec<u8>>,
+    sender: Option<SyncSender<Vec<u8>>>,
+    worker: Option<JoinHandle<Result<(), ExportError>>>,
+}
+
+impl GifEncoder {
+    /// 创建输出文件并启动编码线程，帧间隔由 fps 决定
+    pub fn create(
+        path: &Path,
+        resolution: (u32, u32),
+        frame_count: u32,
+        fps: u32,
+        repeat: GifRepeat,
+    ) -> Result<Self, ExportError> {
+        let write_error = |error: std::io::Error| ExportError::Write {
+            path: path.to_path_buf(),
+            source: Arc::new(error),
+        };
+        let gif_error = |error: EncodingError| ExportError::Gif(Arc::new(error));
+
+        let (width, height) = (resolution.0 as u16, resolution.1 as u16);
+        let file = File::create(path).map_err(write_error)?;
+        let mut encoder =
+            Encoder::new(BufWriter::new(file), width, height, &[]).map_err(gif_error)?;
+        if let Some(loops) = repeat.loops() {
+            encoder.set_repeat(loops).map_err(gif_error)?;
+        }
+
+        let (sender, receiver) = sync_channel::<Vec<u8>>(QUEUE_SIZE);
+        let fps = fps.max(1) as u64;
+        let path = path.to_path_buf();
+        let worker = std::thread::spawn(move || {
+            for (index, pixels) in receiver.into_iter().enumerate() {
+                let mut frame = Frame::from_rgb_speed(width, height, &pixels, QUANTIZE_SPEED);
+                frame.delay = frame_delay(index as u64, fps);
+                encoder.write_frame(&frame).map_err(gif_error)?;
+            }
+            encoder
+                .into_inner()
+                .and_then(|mut writer| writer.flush())
+                .map_err(|error| ExportError::Write {
+                    path,
+                    source: Arc::new(error),
+                })
+        });
+
+        Ok(Self {
+            resolution,
+            frame_count,
+            next: 0,
+            pending: BTreeMap::new(),
+            sender: Some(sender),
+            worker: Some(worker),
+        })
+    }
+
+    /// 加入第 index 帧（从 0 开始），截图可能不按顺序到达
+    pub fn add_frame(&mut self, index: u32, image: DynamicImage) -> Result<(), ExportError> {
+        let actual = (image.width(), image.height());
+        if actual != self.resolution {
+            return Err(ExportError::FrameSize {
+                actual,
+                expected: self.resolution,
+            });
+        }
+        if index >= self.frame_count {
+            return Err(ExportError::EncoderClosed);
+        }
+        self.pending.insert(index, image.to_rgb8().into_raw());
+
+        while let Some(pixels) = self.pending.remove(&self.next) {
+            let sender = self.sender.as_ref().ok_or(ExportError::EncoderClosed)?;
+            if sender.send(pixels).is_err() {
+                // 编码线程出错后已退出，错误原因是它的返回值
+                self.sender = None;
+                return Err(self.join().err().unwrap_or(ExportError::EncoderClosed));
+            }
+            self.next += 1;
+        }
+        Ok(())
+    }
+
+    /// 等待编码线程写完全部帧
+    pub fn finish(mut self) -> Result<(), ExportError> {
+        self.sender = None;
+        self.join()?;
+        if self.next < self.frame_count {
+            return Err(ExportError::MissingFrame(self.next + 1));
+        }
+        Ok(())
+    }
+
+    fn join(&mut self) -> Result<(), ExportError> {
+        self.worker
+            .take()
+            .ok_or(ExportError::EncoderClosed)?
+            .join()
+            .unwrap_or(Err(ExportError::EncoderClosed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::gif::{ColorOutput, DecodeOptions};
+    use image::{Rgb, RgbImage};
+
+    /// 解码 GIF，返回循环方式和每帧的间隔与 RGBA 像素
+    fn decode(path: &Path) -> (Repeat, Vec<(u16, Vec<u8>)>) {
+        let mut options = DecodeOptions::new();
+        options.set_color_output(ColorOutput::RGBA);
+        let mut decoder = options.read_info(File::open(path).unwrap()).unwrap();
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            frames.push((frame.delay, frame.buffer.to_vec()));
+        }
+        (decoder.repeat(), frames)
+    }
+
+    fn solid(color: [u8; 3]) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 4, Rgb(color)))
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("rim_{}_{}.gif", name, std::process::id()))
+    }
+
+    #[test]
+    fn delays_add_up_to_whole_seconds() {
+        for fps in [1, 7, 15, 24, 30, 60] {
+            let total: u64 = (0..fps).map(|index| frame_delay(index, fps) as u64).sum();
+            assert_eq!(total, 100, "fps = {fps}");
+        }
+        assert_eq!(frame_delay(0, 30), 4);
+        assert_eq!(frame_delay(1, 30), 3);
+    }
+
+    #[test]
+    fn writes_frames_in_order_with_delays_and_loops() {
+        let path = temp_path("order");
+        let colors = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]];
+        let mut encoder = GifEncoder::create(&path, (8, 4), 4, 30, GifRepeat::Times(3)).unwrap();
+        // 截图可能乱序到达
+        for index in [1, 0, 3, 2] {
+            encoder
+                .add_frame(index, solid(colors[index as usize]))
+                .unwrap();
+        }
+        encoder.finish().unwrap();
+
+        let (repeat, frames) = decode(&path);
+        assert_eq!(repeat, Repeat::Finite(2));
+        let delays: Vec<u16> = frames.iter().map(|(delay, _)| *delay).collect();
+        assert_eq!(delays, [4, 3, 3, 4]);
+        for ((_, pixels), color) in frames.iter().zip(colors) {
+            assert_eq!(pixels.len(), 8 * 4 * 4);
+            for pixel in pixels.chunks(4) {
+                for (actual, expected) in pixel.iter().zip(color) {
+                    assert!(actual.abs_diff(expected) <= 8, "{pixel:?} != {color:?}");
+                }
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn loop_extension_follows_repeat() {
+        for (repeat, expected) in [
+            (GifRepeat::Infinite, Repeat::Infinite),
+            // 只播放一次时没有循环扩展，解码器默认不重复
+            (GifRepeat::Times(1), Repeat::Finite(0)),
+        ] {
+            let path = temp_path("repeat");
+            let mut encoder = GifEncoder::create(&path, (8, 4), 1, 10, repeat).unwrap();
+            encoder.add_frame(0, solid([10, 20, 30])).unwrap();
+            encoder.finish().unwrap();
+            let (actual, frames) = decode(&path);
+            assert_eq!(actual, expected);
+            assert_eq!(frames.len(), 1);
+            assert_eq!(frames[0].0, 10);
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn reports_wrong_sizes_and_missing_frames() {
+        let path = temp_path("missing");
+        let mut encoder = GifEncoder::create(&path, (8, 4), 3, 10, GifRepeat::Infinite).unwrap();
+        let wrong = DynamicImage::ImageRgb8(RgbImage::new(4, 4));
+        assert!(matches!(
+            encoder.add_frame(0, wrong),
+            Err(ExportError::FrameSize {
+                actual: (4, 4),
+                expected: (8, 4)
+            })
+        ));
+        assert!(matches!(
+            encoder.add_frame(3, solid([0, 0, 0])),
+            Err(ExportError::EncoderClosed)
+        ));
+        encoder.add_frame(0, solid([0, 0, 0])).unwrap();
+        encoder.add_frame(2, solid([0, 0, 0])).unwrap();
+        assert!(matches!(
+            encoder.finish(),
+            Err(ExportError::MissingFrame(2))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
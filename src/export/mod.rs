@@ -1,15 +1,60 @@
+use crate::animation::{drive_timeline, evaluate_animations, Timeline};
+use crate::render::CoordinateSystem;
+use crate::scene::MathScene;
+use bevy::gizmos::UpdateGizmoMeshes;
 use bevy::prelude::*;
-use bevy::render::view::window::screenshot::{save_to_disk, Screenshot};
+use bevy::render::renderer::RenderDevice;
+use bevy::render::view::window::screenshot::{Screenshot, ScreenshotCaptured};
 use bevy::window::PrimaryWindow;
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+pub mod capture;
+pub mod gif;
+pub mod offline;
+pub mod settings;
+pub mod svg;
+pub mod target;
+pub mod video;
+
+pub use capture::*;
+pub use gif::*;
+pub use offline::*;
+pub use settings::*;
+pub use svg::*;
+pub use target::*;
+pub use video::*;
 
 pub struct ExportPlugin;
 
 impl Plugin for ExportPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ExportRequest>()
-            .add_systems(Update, handle_export_requests);
+            .add_event::<ExportProgress>()
+            .add_event::<FrameCaptureFinished>()
+            .add_event::<ExportFinished>()
+            .add_event::<ExportFailed>()
+            .insert_resource(ExportSettings::load(Path::new(EXPORT_SETTINGS_FILE)))
+            .init_resource::<AnimationExportSettings>()
+            .init_gizmo_group::<CaptureGizmos>()
+            .add_systems(Startup, setup_gizmo_layers)
+            .add_systems(
+                Update,
+                (
+                    handle_export_requests,
+                    capture_frames
+                        .after(drive_timeline)
+                        .before(evaluate_animations),
+                    finish_frame_capture,
+                    report_finished_captures.after(finish_frame_capture),
+                    save_export_settings.after(handle_export_requests),
+                ),
+            )
+            .add_systems(Last, copy_capture_gizmos.before(UpdateGizmoMeshes));
     }
 }
 
@@ -23,66 +68,390 @@ pub enum ExportFormat {
 }
 
 /// 导出请求事件
-#[derive(Event)]
+#[derive(Event, Debug, Clone)]
 pub struct ExportRequest {
     pub format: ExportFormat,
-    pub filename: String,
+    /// 输出目录中的文件名，None 时按 ExportSettings 的模板生成
+    pub filename: Option<String>,
+    /// 输出尺寸，画面覆盖与窗口相同的视口（SVG 使用窗口尺寸）
+    pub resolution: (u32, u32),
+    pub options: CaptureOptions,
+}
+
+/// 导出完成事件，文件已完整写入
+#[derive(Event, Debug, Clone)]
+pub struct ExportFinished {
+    pub path: PathBuf,
+    pub format: ExportFormat,
+    /// 从收到请求到写入完成的耗时
+    pub duration: Duration,
+}
+
+/// 导出失败事件
+#[derive(Event, Debug, Clone)]
+pub struct ExportFailed {
+    pub request: ExportRequest,
+    /// 按导出设置确定的输出路径
+    pub path: PathBuf,
+    pub error: ExportError,
+}
+
+/// 导出错误
+#[derive(Debug, Clone, Error)]
+pub enum ExportError {
+    #[error("无法创建目录 {}: {source}", .path.display())]
+    CreateDir {
+        path: PathBuf,
+        #[source]
+        source: Arc<std::io::Error>,
+    },
+    #[error("无法写入 {}: {source}", .path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: Arc<std::io::Error>,
+    },
+    #[error("无法编码图像 {}: {message}", .path.display())]
+    EncodeImage { path: PathBuf, message: String },
+    #[error("无法读取截图: {0}")]
+    Screenshot(String),
+    #[error("输出尺寸 {}x{} 超过显卡支持的最大纹理尺寸 {max}", .size.0, .size.1)]
+    TargetTooLarge { size: (u32, u32), max: u32 },
+    #[error("帧尺寸 {}x{} 与输出尺寸 {}x{} 不一致", .actual.0, .actual.1, .expected.0, .expected.1)]
+    FrameSize {
+        actual: (u32, u32),
+        expected: (u32, u32),
+    },
+    #[error("缺少第 {0} 帧")]
+    MissingFrame(u32),
+    #[error("编码已开始，无法再加入帧")]
+    EncoderClosed,
+    #[error("已有动画正在导出")]
+    Busy,
+    #[error("GIF 编码失败: {0}")]
+    Gif(#[source] Arc<::gif::EncodingError>),
+    #[error("无法启动 ffmpeg: {0}")]
+    SpawnEncoder(#[source] Arc<std::io::Error>),
+    #[error("无法向 ffmpeg 写入帧: {0}")]
+    Pipe(#[source] Arc<std::io::Error>),
+    #[error("ffmpeg 编码失败（{status}）: {message}")]
+    Encoder { status: String, message: String },
+}
+
+/// 动画导出设置，GIF 和视频都按时间轴逐帧截取
+#[derive(Resource, Clone, Debug)]
+pub struct AnimationExportSettings {
+    pub fps: u32,
     pub resolution: (u32, u32),
+    pub repeat: GifRepeat,
+    pub codec: VideoCodec,
+    /// 视频质量，越小质量越高、文件越大
+    pub crf: u8,
+    /// 只导出部分帧（帧号从 1 开始，含两端），None 时导出整个时间轴
+    pub frame_range: Option<(u32, u32)>,
+}
+
+impl Default for AnimationExportSettings {
+    fn default() -> Self {
+        Self {
+            fps: 20,
+            resolution: (960, 540),
+            repeat: GifRepeat::Infinite,
+            codec: VideoCodec::H264,
+            crf: VideoCodec::H264.default_crf(),
+            frame_range: None,
+        }
+    }
+}
+
+impl AnimationExportSettings {
+    /// 时间 time（秒）所在的帧号，从 1 开始
+    pub fn frame_at(&self, time: f32) -> u32 {
+        (time * self.fps as f32).round() as u32 + 1
+    }
+
+    /// 时长为 duration 秒的时间轴要截取的帧号范围，超出时间轴的部分被截掉
+    pub fn frames(&self, duration: f32) -> RangeInclusive<u32> {
+        let last = frame_count(duration, self.fps);
+        match self.frame_range {
+            Some((start, end)) => {
+                let start = start.clamp(1, last);
+                start..=end.clamp(start, last)
+            }
+            None => 1..=last,
+        }
+    }
+}
+
+/// 开始逐帧截取所需的资源
+type FrameCaptureParams<'w> = (
+    ResMut<'w, Assets<Image>>,
+    Res<'w, RenderDevice>,
+    Res<'w, AnimationExportSettings>,
+    Res<'w, Timeline>,
+    Option<Res<'w, FrameCapture>>,
+);
+
+/// 导出画面的视口和背景色
+type ExportViewParams<'w> = (Res<'w, CoordinateSystem>, Res<'w, ClearColor>);
+
+/// 生成输出路径所需的设置、场景名和本次运行中已分配的路径
+type ExportNamingParams<'w, 's> = (
+    ResMut<'w, ExportSettings>,
+    Query<'w, 's, &'static MathScene>,
+    Local<'s, HashSet<PathBuf>>,
+);
+
+/// 导出结果事件
+type ExportResultWriters<'w> = (
+    EventWriter<'w, ExportFinished>,
+    EventWriter<'w, ExportFailed>,
+);
+
+/// 由导出请求发起、尚未结束的逐帧截取
+#[derive(Resource)]
+struct PendingAnimationExport {
+    request: ExportRequest,
+    started: Instant,
 }
 
 /// 处理导出请求的系统
-fn handle_export_requests(mut export_events: EventReader<ExportRequest>, mut commands: Commands) {
+fn handle_export_requests(
+    mut export_events: EventReader<ExportRequest>,
+    mut commands: Commands,
+    svg_queries: SvgQueries,
+    (coordinate_system, clear_color): ExportViewParams,
+    (mut export_settings, scenes, mut taken): ExportNamingParams,
+    (mut images, device, animation_settings, timeline, capture): FrameCaptureParams,
+    (mut finished, mut failed): ExportResultWriters,
+) {
+    let mut capturing = capture.is_some();
+    let scene = scenes
+        .iter()
+        .find(|scene| scene.active)
+        .map_or("scene", |scene| scene.name.as_str());
+    let frame = animation_settings.frame_at(timeline.playhead);
     for event in export_events.read() {
-        match event.format {
+        let started = Instant::now();
+        let extension = match event.format {
+            ExportFormat::PNG => "png",
+            ExportFormat::SVG => "svg",
+            ExportFormat::GIF => "gif",
+            ExportFormat::MP4 => animation_settings.codec.extension(),
+        };
+        let path = export_settings.output_path(
+            event.filename.as_deref(),
+            scene,
+            frame,
+            extension,
+            &mut taken,
+        );
+
+        // 确保输出目录存在
+        let result = match path.parent() {
+            Some(parent) => std::fs::create_dir_all(parent).map_err(|e| ExportError::CreateDir {
+                path: parent.to_path_buf(),
+                source: Arc::new(e),
+            }),
+            None => Ok(()),
+        }
+        .and_then(|()| match event.format {
             ExportFormat::PNG => {
-                // 使用新的截图API
-                let path = format!("screenshots/{}", event.filename);
-
-                // 确保screenshots目录存在
-                if let Some(parent) = Path::new(&path).parent() {
-                    if let Err(e) = std::fs::create_dir_all(parent) {
-                        error!("Failed to create screenshots directory: {}", e);
-                        continue;
-                    }
+                // 渲染到请求尺寸的离屏目标，截图在渲染后异步完成，结果由观察者发送
+                let target = CaptureTarget::spawn(
+                    &mut commands,
+                    &mut images,
+                    &device,
+                    event.resolution,
+                    coordinate_system.viewport_size,
+                    event.options,
+                )?;
+                commands
+                    .spawn(Screenshot::image(target.image.clone()))
+                    .observe(save_screenshot(
+                        event.clone(),
+                        path.clone(),
+                        started,
+                        target,
+                    ));
+                info!("Screenshot requested: {}", path.display());
+                Ok(false)
+            }
+            ExportFormat::SVG => {
+                // 导出当前视口的矢量图形
+                let svg = scene_svg(&svg_queries, &coordinate_system, clear_color.0);
+                std::fs::write(&path, svg)
+                    .map(|()| true)
+                    .map_err(|e| ExportError::Write {
+                        path: path.clone(),
+                        source: Arc::new(e),
+                    })
+            }
+            ExportFormat::GIF | ExportFormat::MP4 => {
+                // 同一时间只能有一个逐帧截取任务
+                if capturing {
+                    return Err(ExportError::Busy);
                 }
+                // GIF 和视频不支持透明度
+                let target = CaptureTarget::spawn(
+                    &mut commands,
+                    &mut images,
+                    &device,
+                    event.resolution,
+                    coordinate_system.viewport_size,
+                    CaptureOptions {
+                        transparent_background: false,
+                        ..event.options
+                    },
+                )?;
+                let frames = animation_settings.frames(timeline.duration());
+                let output = if event.format == ExportFormat::GIF {
+                    GifEncoder::create(
+                        &path,
+                        event.resolution,
+                        frames.end() + 1 - frames.start(),
+                        animation_settings.fps,
+                        animation_settings.repeat,
+                    )
+                    .map(|encoder| CaptureOutput::Gif {
+                        path: path.clone(),
+                        encoder: Some(encoder),
+                    })
+                } else {
+                    video_output(&path, &animation_settings, event.resolution)
+                };
+                let output = match output {
+                    Ok(output) => output,
+                    Err(error) => {
+                        commands.entity(target.camera).despawn();
+                        return Err(error);
+                    }
+                };
+                capturing = true;
+                info!(
+                    "Animation export started: {} (frames {}-{}, {} fps)",
+                    path.display(),
+                    frames.start(),
+                    frames.end(),
+                    animation_settings.fps
+                );
+                let capture = FrameCapture::new(target, animation_settings.fps, frames, output);
+                commands.insert_resource(capture);
+                commands.insert_resource(PendingAnimationExport {
+                    request: event.clone(),
+                    started,
+                });
+                Ok(false)
+            }
+        });
 
-                // 使用新的截图API
-                commands
-                    .spawn(Screenshot::primary_window())
-                    .observe(save_to_disk(path.clone()));
+        match result {
+            Ok(true) => {
+                info!("Exported: {}", path.display());
+                finished.write(ExportFinished {
+                    path,
+                    format: event.format.clone(),
+                    duration: started.elapsed(),
+                });
+            }
+            Ok(false) => {}
+            Err(error) => {
+                error!("Failed to export {}: {}", path.display(), error);
+                failed.write(ExportFailed {
+                    request: event.clone(),
+                    path,
+                    error,
+                });
+            }
+        }
+    }
+}
 
-                info!("Screenshot requested: {}", path);
+/// 截图完成后写入 PNG、删除离屏相机并发送导出结果
+fn save_screenshot(
+    request: ExportRequest,
+    path: PathBuf,
+    started: Instant,
+    target: CaptureTarget,
+) -> impl FnMut(Trigger<ScreenshotCaptured>, Commands, ExportResultWriters) {
+    move |trigger, mut commands, (mut finished, mut failed)| {
+        commands.entity(target.camera).despawn();
+        let result = target.read(trigger.event().0.clone()).and_then(|image| {
+            image.save(&path).map_err(|e| ExportError::EncodeImage {
+                path: path.clone(),
+                message: e.to_string(),
+            })
+        });
+        match result {
+            Ok(()) => {
+                info!("Screenshot saved: {}", path.display());
+                finished.write(ExportFinished {
+                    path: path.clone(),
+                    format: request.format.clone(),
+                    duration: started.elapsed(),
+                });
             }
-            ExportFormat::SVG => {
-                // 导出SVG图像 (暂未实现)
-                warn!("SVG export not yet implemented: {}", event.filename);
+            Err(error) => {
+                error!("Failed to save screenshot {}: {}", path.display(), error);
+                failed.write(ExportFailed {
+                    request: request.clone(),
+                    path: path.clone(),
+                    error,
+                });
             }
-            ExportFormat::GIF => {
-                // 导出GIF动画 (暂未实现)
-                warn!("GIF export not yet implemented: {}", event.filename);
+        }
+    }
+}
+
+/// 把由导出请求发起的逐帧截取的结果转为导出结果事件，其余截取（如离线渲染）只记录日志
+fn report_finished_captures(
+    mut commands: Commands,
+    mut captures: EventReader<FrameCaptureFinished>,
+    pending: Option<Res<PendingAnimationExport>>,
+    (mut finished, mut failed): ExportResultWriters,
+) {
+    for event in captures.read() {
+        match &event.result {
+            Ok(()) => info!(
+                "Animation exported: {} ({} frames)",
+                event.path.display(),
+                event.frames
+            ),
+            Err(e) => error!("Failed to export {}: {}", event.path.display(), e),
+        }
+
+        let Some(pending) = pending.as_ref() else {
+            continue;
+        };
+        commands.remove_resource::<PendingAnimationExport>();
+        match &event.result {
+            Ok(()) => {
+                finished.write(ExportFinished {
+                    path: event.path.clone(),
+                    format: pending.request.format.clone(),
+                    duration: pending.started.elapsed(),
+                });
             }
-            ExportFormat::MP4 => {
-                // 导出MP4视频 (暂未实现)
-                warn!("MP4 export not yet implemented: {}", event.filename);
+            Err(error) => {
+                failed.write(ExportFailed {
+                    request: pending.request.clone(),
+                    path: event.path.clone(),
+                    error: error.clone(),
+                });
             }
         }
     }
 }
 
-/// 便利函数：请求PNG截图
+/// 便利函数：请求PNG截图，文件名为 None 时按导出设置的模板生成
 pub fn request_png_screenshot(
     export_writer: &mut EventWriter<ExportRequest>,
     filename: Option<String>,
 ) {
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let filename = filename.unwrap_or_else(|| format!("screenshot_{}.png", timestamp));
-
     export_writer.write(ExportRequest {
         format: ExportFormat::PNG,
         filename,
         resolution: (1920, 1080), // 默认分辨率
+        options: CaptureOptions::default(),
     });
 }
@@ -0,0 +1,416 @@
+//! 离线渲染：无窗口运行，按固定帧率逐帧推进时间轴并输出编号的 PNG 帧或动画 GIF
+//!
+//! ```text
+//! rim --render scenes/intro.rim --output frames --fps 30 --size 1920x1080
+//! rim --render scenes/intro.rim --output intro.gif --fps 15 --size 640x360
+//! ```
+//!
+//! 时间由 `TimeUpdateStrategy::ManualDuration` 按帧间隔推进，时间轴播放头直接按帧号定位，
+//! 因此输出只取决于场景和参数，与机器速度无关。
+
+use super::capture::{
+    capture_frames, frame_count, CaptureOutput, FrameCapture, FrameCaptureFinished,
+};
+use super::gif::{GifEncoder, GifRepeat};
+use super::target::{CaptureOptions, CaptureTarget};
+use crate::animation::Timeline;
+use crate::interaction::CameraState;
+use crate::render::CoordinateSystem;
+use crate::scene::{LoadSceneRequest, SceneFileState, SceneLoaded};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::render::renderer::RenderDevice;
+use bevy::time::TimeUpdateStrategy;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+/// 命令行用法
+pub const OFFLINE_RENDER_USAGE: &str = "用法: rim --render <场景文件> [--output <目录或 .gif 文件>] [--fps <帧率>] [--size <宽>x<高>] [--duration <秒>] [--supersample <1|2|4>] [--transparent]";
+
+/// 场景加载后、开始截帧前等待的帧数，让网格、文字等在第一帧之前准备好
+const WARM_UP_FRAMES: u32 = 3;
+
+/// 离线渲染命令行参数错误
+#[derive(Error, Debug)]
+pub enum RenderArgsError {
+    #[error("缺少场景文件路径")]
+    MissingScene,
+    #[error("参数 {0} 缺少取值")]
+    MissingValue(String),
+    #[error("参数 {flag} 的取值 '{value}' 无效")]
+    InvalidValue { flag: String, value: String },
+    #[error("未知参数 '{0}'")]
+    UnknownArgument(String),
+    #[error("场景文件路径重复: '{0}'")]
+    DuplicateScene(String),
+}
+
+/// 离线渲染设置
+#[derive(Resource, Clone, Debug)]
+pub struct OfflineRenderSettings {
+    pub scene: PathBuf,
+    /// 输出目录，以 .gif 结尾时为 GIF 文件
+    pub output: PathBuf,
+    pub fps: u32,
+    pub resolution: (u32, u32),
+    /// 渲染时长（秒），None 时渲染整个时间轴
+    pub duration: Option<f32>,
+    /// 超采样和透明背景，透明只对 PNG 序列有效
+    pub options: CaptureOptions,
+}
+
+impl OfflineRenderSettings {
+    /// 解析命令行参数（不含程序名），没有 `--render` 时返回 None
+    pub fn from_args(
+        args: impl IntoIterator<Item = String>,
+    ) -> Result<Option<Self>, RenderArgsError> {
+        let args: Vec<String> = args.into_iter().collect();
+        if !args.iter().any(|arg| arg == "--render") {
+            return Ok(None);
+        }
+
+        let mut scene = None;
+        let mut settings = Self {
+            scene: PathBuf::new(),
+            output: PathBuf::from("frames"),
+            fps: 30,
+            resolution: (1920, 1080),
+            duration: None,
+            options: CaptureOptions::default(),
+        };
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            if flag == "--render" {
+                continue;
+            }
+            if !flag.starts_with("--") {
+                if scene.is_some() {
+                    return Err(RenderArgsError::DuplicateScene(flag));
+                }
+                scene = Some(PathBuf::from(flag));
+                continue;
+            }
+            // 不带取值的开关
+            if flag == "--transparent" {
+                settings.options.transparent_background = true;
+                continue;
+            }
+
+            let value = args
+                .next()
+                .ok_or_else(|| RenderArgsError::MissingValue(flag.clone()))?;
+            let invalid = || RenderArgsError::InvalidValue {
+                flag: flag.clone(),
+                value: value.clone(),
+            };
+            match flag.as_str() {
+                "--output" => settings.output = PathBuf::from(&value),
+                "--fps" => {
+                    settings.fps = value
+                        .parse()
+                        .ok()
+                        .filter(|fps| *fps > 0)
+                        .ok_or_else(invalid)?;
+                }
+                "--size" => {
+                    settings.resolution = value
+                        .split_once('x')
+                        .and_then(|(width, height)| {
+                            Some((width.parse().ok()?, height.parse().ok()?))
+                        })
+                        .filter(|(width, height)| *width > 0 && *height > 0)
+                        .ok_or_else(invalid)?;
+                }
+                "--duration" => {
+                    settings.duration = Some(
+                        value
+                            .strip_suffix('s')
+                            .unwrap_or(&value)
+                            .parse()
+                            .ok()
+                            .filter(|duration: &f32| duration.is_finite() && *duration >= 0.0)
+                            .ok_or_else(invalid)?,
+                    );
+                }
+                "--supersample" => {
+                    settings.options.supersample = value
+                        .trim_end_matches(['x', '×'])
+                        .parse()
+                        .ok()
+                        .filter(|factor| matches!(factor, 1 | 2 | 4))
+                        .ok_or_else(invalid)?;
+                }
+                _ => return Err(RenderArgsError::UnknownArgument(flag)),
+            }
+        }
+
+        settings.scene = scene.ok_or(RenderArgsError::MissingScene)?;
+        Ok(Some(settings))
+    }
+
+    /// 帧间隔
+    pub fn frame_time(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.fps as f64)
+    }
+
+    /// 输出路径以 .gif 结尾时输出动画 GIF，否则输出 PNG 序列
+    pub fn is_gif(&self) -> bool {
+        self.output
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("gif"))
+    }
+}
+
+/// 离线渲染插件，需要配合无窗口的 DefaultPlugins 和 ScheduleRunnerPlugin 使用
+pub struct OfflineRenderPlugin(pub OfflineRenderSettings);
+
+impl Plugin for OfflineRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.0.clone())
+            .insert_resource(TimeUpdateStrategy::ManualDuration(self.0.frame_time()))
+            .init_resource::<CameraState>()
+            .init_resource::<OfflineRenderPhase>()
+            .add_systems(Startup, setup_offline_render)
+            .add_systems(Update, run_offline_render.before(capture_frames));
+    }
+}
+
+/// 离线渲染所处阶段
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq)]
+enum OfflineRenderPhase {
+    /// 等待场景加载
+    #[default]
+    Loading,
+    /// 场景已加载，还需等待的帧数
+    WarmUp(u32),
+    /// 正在逐帧截取，结束后退出
+    Capturing,
+}
+
+/// 创建输出目录并请求加载场景
+fn setup_offline_render(
+    settings: Res<OfflineRenderSettings>,
+    mut coordinate_system: ResMut<CoordinateSystem>,
+    mut load_requests: EventWriter<LoadSceneRequest>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let dir = if settings.is_gif() {
+        settings.output.parent().unwrap_or(Path::new(""))
+    } else {
+        &settings.output
+    };
+    if let Err(error) = std::fs::create_dir_all(dir) {
+        error!("无法创建输出目录 {}: {}", dir.display(), error);
+        exit.write(AppExit::error());
+        return;
+    }
+
+    // 没有窗口，视口尺寸即输出分辨率
+    let (width, height) = settings.resolution;
+    coordinate_system.viewport_size = Vec2::new(width as f32, height as f32);
+
+    info!(
+        "离线渲染 {} -> {}（{}x{}，{} fps）",
+        settings.scene.display(),
+        settings.output.display(),
+        width,
+        height,
+        settings.fps
+    );
+    load_requests.write(LoadSceneRequest::open(settings.scene.clone()));
+}
+
+/// 开始截取时读取的资源
+type OfflineCaptureParams<'w> = (
+    ResMut<'w, Assets<Image>>,
+    Res<'w, RenderDevice>,
+    Res<'w, Timeline>,
+    Res<'w, CoordinateSystem>,
+);
+
+/// 场景加载状态
+type OfflineSceneParams<'w, 's> = (EventReader<'w, 's, SceneLoaded>, Res<'w, SceneFileState>);
+
+/// 场景加载并预热后开始逐帧截取，截取结束后退出
+fn run_offline_render(
+    mut commands: Commands,
+    settings: Res<OfflineRenderSettings>,
+    mut phase: ResMut<OfflineRenderPhase>,
+    (mut images, device, timeline, coordinate_system): OfflineCaptureParams,
+    (mut loaded, scene_file): OfflineSceneParams,
+    mut finished: EventReader<FrameCaptureFinished>,
+    mut exit: EventWriter<AppExit>,
+) {
+    match *phase {
+        OfflineRenderPhase::Loading => {
+            if scene_file.show_error {
+                if let Some(Err(error)) = &scene_file.last_result {
+                    error!("无法加载场景: {}", error);
+                }
+                exit.write(AppExit::error());
+            } else if loaded.read().count() > 0 {
+                *phase = OfflineRenderPhase::WarmUp(WARM_UP_FRAMES);
+            }
+        }
+        OfflineRenderPhase::WarmUp(0) => {
+            let duration = settings.duration.unwrap_or(timeline.duration());
+            let frames = frame_count(duration, settings.fps);
+            let output = if settings.is_gif() {
+                let encoder = GifEncoder::create(
+                    &settings.output,
+                    settings.resolution,
+                    frames,
+                    settings.fps,
+                    GifRepeat::Infinite,
+                );
+                match encoder {
+                    Ok(encoder) => CaptureOutput::Gif {
+                        path: settings.output.clone(),
+                        encoder: Some(encoder),
+                    },
+                    Err(error) => {
+                        error!("无法创建 GIF 文件: {}", error);
+                        exit.write(AppExit::error());
+                        return;
+                    }
+                }
+            } else {
+                CaptureOutput::PngSequence(settings.output.clone())
+            };
+            let target = CaptureTarget::spawn(
+                &mut commands,
+                &mut images,
+                &device,
+                settings.resolution,
+                coordinate_system.viewport_size,
+                CaptureOptions {
+                    transparent_background: settings.options.transparent_background
+                        && !settings.is_gif(),
+                    ..settings.options
+                },
+            );
+            let target = match target {
+                Ok(target) => target,
+                Err(error) => {
+                    error!("无法创建渲染目标: {}", error);
+                    exit.write(AppExit::error());
+                    return;
+                }
+            };
+            let capture = FrameCapture::new(target, settings.fps, 1..=frames, output);
+            commands.insert_resource(capture);
+            *phase = OfflineRenderPhase::Capturing;
+            info!("开始渲染 {} 帧（{:.2} 秒）", frames, duration);
+        }
+        OfflineRenderPhase::WarmUp(frames) => {
+            *phase = OfflineRenderPhase::WarmUp(frames - 1);
+        }
+        OfflineRenderPhase::Capturing => {
+            for event in finished.read() {
+                match &event.result {
+                    Ok(()) => {
+                        info!(
+                            "离线渲染完成：{} 帧已写入 {}",
+                            event.frames,
+                            event.path.display()
+                        );
+                        exit.write(AppExit::Success);
+                    }
+                    Err(error) => {
+                        error!("离线渲染失败: {}", error);
+                        exit.write(AppExit::error());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Option<OfflineRenderSettings>, RenderArgsError> {
+        OfflineRenderSettings::from_args(args.split_whitespace().map(String::from))
+    }
+
+    fn invalid(args: &str) -> (String, String) {
+        match parse(args) {
+            Err(RenderArgsError::InvalidValue { flag, value }) => (flag, value),
+            other => panic!("期望无效取值错误，实际为 {:?}", other),
+        }
+    }
+
+    #[test]
+    fn without_render_flag_returns_none() {
+        assert!(parse("").unwrap().is_none());
+        assert!(parse("scene.rim --fps 10").unwrap().is_none());
+    }
+
+    #[test]
+    fn parses_all_options() {
+        let settings = parse(
+            "--render scene.rim --output out.gif --fps 12 --size 640x360 --duration 2s \
+             --supersample 2x --transparent",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(settings.scene, PathBuf::from("scene.rim"));
+        assert_eq!(settings.output, PathBuf::from("out.gif"));
+        assert!(settings.is_gif());
+        assert_eq!(settings.fps, 12);
+        assert_eq!(settings.resolution, (640, 360));
+        assert_eq!(settings.duration, Some(2.0));
+        assert_eq!(settings.options.supersample, 2);
+        assert!(settings.options.transparent_background);
+
+        let defaults = parse("scene.rim --render").unwrap().unwrap();
+        assert_eq!(defaults.output, PathBuf::from("frames"));
+        assert_eq!((defaults.fps, defaults.resolution), (30, (1920, 1080)));
+        assert_eq!(defaults.duration, None);
+        assert_eq!(
+            parse("--render a.rim --duration 1.5")
+                .unwrap()
+                .unwrap()
+                .duration,
+            Some(1.5)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        for size in ["0x0", "1920x", "x1080", "1920", "-1x10", "1920x0"] {
+            let args = format!("--render scene.rim --size {size}");
+            assert_eq!(invalid(&args), ("--size".to_string(), size.to_string()));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert_eq!(invalid("--render a.rim --fps 0").1, "0");
+        assert_eq!(invalid("--render a.rim --duration 2ss").1, "2ss");
+        assert_eq!(invalid("--render a.rim --duration -1").1, "-1");
+        assert_eq!(invalid("--render a.rim --supersample 3").1, "3");
+    }
+
+    #[test]
+    fn reports_missing_and_unexpected_arguments() {
+        assert!(matches!(
+            parse("--render a.rim --size"),
+            Err(RenderArgsError::MissingValue(flag)) if flag == "--size"
+        ));
+        assert!(matches!(
+            parse("--render --fps 30"),
+            Err(RenderArgsError::MissingScene)
+        ));
+        assert!(matches!(
+            parse("--render a.rim b.rim"),
+            Err(RenderArgsError::DuplicateScene(path)) if path == "b.rim"
+        ));
+        assert!(matches!(
+            parse("--render a.rim --speed 2"),
+            Err(RenderArgsError::UnknownArgument(flag)) if flag == "--speed"
+        ));
+    }
+}
@@ -0,0 +1,273 @@
+//! 导出设置：输出目录、文件名模板和重名处理，在运行之间保存到工作目录中的 JSON 文件
+//!
+//! 文件名模板可使用以下占位符，扩展名按导出格式自动添加：
+//!
+//! | 占位符 | 含义 |
+//! |--------|------|
+//! | `{scene}` | 活动场景名称 |
+//! | `{date}` | 导出时间（UTC），如 `20250612-153012` |
+//! | `{frame}` | 播放头所在帧号（按动画导出帧率，从 1 开始） |
+//! | `{counter}` | 每次导出递增的序号，在运行之间保留 |
+
+use super::target::CaptureOptions;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 导出设置的保存位置（相对于工作目录）
+pub const EXPORT_SETTINGS_FILE: &str = "rim_export_settings.json";
+
+/// 文件名模板中可用的占位符
+pub const FILENAME_TOKENS: [&str; 4] = ["{scene}", "{date}", "{frame}", "{counter}"];
+
+/// 目标文件已存在时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    /// 覆盖已有文件
+    Overwrite,
+    /// 在文件名后追加 `_2`、`_3`……直到不重名
+    Increment,
+}
+
+/// 导出选项面板中的设置，用于生成导出请求和输出路径
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportSettings {
+    /// 输出目录
+    pub directory: String,
+    /// 文件名模板（不含扩展名）
+    pub filename_template: String,
+    pub collision: CollisionPolicy,
+    /// `{counter}` 的下一个取值
+    pub counter: u32,
+    /// 截图尺寸
+    pub resolution: (u32, u32),
+    pub options: CaptureOptions,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            directory: "screenshots".to_string(),
+            filename_template: "rim_{scene}_{date}".to_string(),
+            collision: CollisionPolicy::Increment,
+            counter: 1,
+            resolution: (1920, 1080),
+            options: CaptureOptions::default(),
+        }
+    }
+}
+
+impl ExportSettings {
+    /// 读取保存的设置，文件不存在或无法解析时使用默认值
+    pub fn load(path: &Path) -> Self {
+        let Ok(json) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&json).unwrap_or_else(|error| {
+            warn!("无法解析导出设置 {}: {}", path.display(), error);
+            Self::default()
+        })
+    }
+
+    /// 保存设置
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// 按模板生成的文件名（不检查重名，不递增序号），用于界面预览
+    pub fn filename(&self, scene: &str, frame: u32, extension: &str) -> String {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let name = self
+            .filename_template
+            .replace("{scene}", &sanitize(scene))
+            .replace("{date}", &format_date(secs))
+            .replace("{frame}", &format!("{:05}", frame))
+            .replace("{counter}", &format!("{:04}", self.counter));
+        let name = name.trim();
+        let name = if name.is_empty() { "rim" } else { name };
+        format!("{}.{}", name, extension)
+    }
+
+    /// 确定输出路径：filename 为 None 时按模板生成，再按重名策略避开已有文件和
+    /// 本次运行中已分配但可能尚未写入的路径
+    pub fn output_path(
+        &mut self,
+        filename: Option<&str>,
+        scene: &str,
+        frame: u32,
+        extension: &str,
+        taken: &mut HashSet<PathBuf>,
+    ) -> PathBuf {
+        let filename = match filename {
+            Some(filename) => filename.to_string(),
+            None => {
+                let filename = self.filename(scene, frame, extension);
+                if self.filename_template.contains("{counter}") {
+                    self.counter += 1;
+                }
+                filename
+            }
+        };
+        let path = Path::new(&self.directory).join(filename);
+        let path = match self.collision {
+            CollisionPolicy::Overwrite => path,
+            CollisionPolicy::Increment => {
+                let free = |path: &PathBuf| !path.exists() && !taken.contains(path);
+                if free(&path) {
+                    path
+                } else {
+                    let stem = path
+                        .file_stem()
+                        .map_or("rim".into(), |stem| stem.to_string_lossy());
+                    let extension = path
+                        .extension()
+                        .map(|extension| format!(".{}", extension.to_string_lossy()))
+                        .unwrap_or_default();
+                    (2..)
+                        .map(|n| path.with_file_name(format!("{}_{}{}", stem, n, extension)))
+                        .find(free)
+                        .unwrap_or(path)
+                }
+            }
+        };
+        taken.insert(path.clone());
+        path
+    }
+}
+
+/// 设置变化后写回文件
+pub fn save_export_settings(
+    settings: Res<ExportSettings>,
+    mut saved: Local<Option<ExportSettings>>,
+) {
+    // 界面每帧都会可变借用设置，只在内容真正改变时写入
+    let Some(previous) = saved.as_ref() else {
+        *saved = Some(settings.clone());
+        return;
+    };
+    if *previous == *settings {
+        return;
+    }
+    if let Err(error) = settings.save(Path::new(EXPORT_SETTINGS_FILE)) {
+        warn!("无法保存导出设置 {}: {}", EXPORT_SETTINGS_FILE, error);
+    }
+    *saved = Some(settings.clone());
+}
+
+/// 场景名中不适合出现在文件名里的字符替换为下划线
+fn sanitize(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// UNIX 时间戳（秒）对应的 UTC 时间，格式为 `YYYYMMDD-HHMMSS`
+fn format_date(secs: u64) -> String {
+    let (days, seconds) = (secs / 86_400, secs % 86_400);
+    // 由 1970-01-01 起的天数换算公历日期（Howard Hinnant 的 civil_from_days）
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_date_uses_utc_calendar() {
+        assert_eq!(format_date(0), "19700101-000000");
+        assert_eq!(format_date(1_749_742_212), "20250612-153012");
+        // 闰日及其前后
+        assert_eq!(format_date(1_709_164_800 - 1), "20240228-235959");
+        assert_eq!(format_date(1_709_164_800 + 3_723), "20240229-010203");
+        assert_eq!(format_date(1_709_251_200), "20240301-000000");
+    }
+
+    fn settings(name: &str) -> ExportSettings {
+        let dir = std::env::temp_dir().join(format!("rim_output_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        ExportSettings {
+            directory: dir.to_string_lossy().into_owned(),
+            ..ExportSettings::default()
+        }
+    }
+
+    #[test]
+    fn output_path_increments_past_existing_and_taken_paths() {
+        let mut settings = settings("increment");
+        let dir = PathBuf::from(&settings.directory);
+        std::fs::write(dir.join("shot.png"), b"").unwrap();
+        std::fs::write(dir.join("shot_2.png"), b"").unwrap();
+        let mut taken = HashSet::new();
+
+        let path = settings.output_path(Some("shot.png"), "", 1, "png", &mut taken);
+        assert_eq!(path, dir.join("shot_3.png"));
+        // 已分配但尚未写入的路径也视为重名
+        let path = settings.output_path(Some("shot.png"), "", 1, "png", &mut taken);
+        assert_eq!(path, dir.join("shot_4.png"));
+        let path = settings.output_path(Some("other.png"), "", 1, "png", &mut taken);
+        assert_eq!(path, dir.join("other.png"));
+        let path = settings.output_path(Some("other.png"), "", 1, "png", &mut taken);
+        assert_eq!(path, dir.join("other_2.png"));
+
+        settings.collision = CollisionPolicy::Overwrite;
+        let path = settings.output_path(Some("shot.png"), "", 1, "png", &mut taken);
+        assert_eq!(path, dir.join("shot.png"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn output_path_fills_template_and_advances_counter() {
+        let mut settings = settings("template");
+        let dir = PathBuf::from(&settings.directory);
+        settings.filename_template = "{scene}_{frame}_{counter}".to_string();
+        let mut taken = HashSet::new();
+
+        let path = settings.output_path(None, "my scene", 12, "gif", &mut taken);
+        assert_eq!(path, dir.join("my_scene_00012_0001.gif"));
+        let path = settings.output_path(None, "my scene", 12, "gif", &mut taken);
+        assert_eq!(path, dir.join("my_scene_00012_0002.gif"));
+        assert_eq!(settings.counter, 3);
+
+        // 模板不含 {counter} 时序号不变，重名由递增后缀处理
+        settings.filename_template = "{scene}".to_string();
+        settings.output_path(None, "a", 1, "png", &mut taken);
+        let path = settings.output_path(None, "a", 1, "png", &mut taken);
+        assert_eq!(path, dir.join("a_2.png"));
+        assert_eq!(settings.counter, 3);
+        // 显式文件名不使用模板
+        settings.filename_template = "{counter}".to_string();
+        settings.output_path(Some("b.png"), "a", 1, "png", &mut taken);
+        assert_eq!(settings.counter, 3);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
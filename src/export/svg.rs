@@ -0,0 +1,347 @@
+//! SVG 导出：把当前视口中的数学对象写成矢量图形
+//!
+//! 几何与屏幕渲染共用同一套换算（`CoordinateSystem`、`screen_points`、`axes_segments` 等），
+//! 因此导出的 SVG 与窗口中看到的画面一致，只是线条和文字保持为矢量。
+
+use crate::math_objects::basic_shapes::Rectangle as MathRectangle;
+use crate::math_objects::{
+    Axes, FunctionGraph, Grid, Line, MathCircle, MathObject, MorphOutline, ParametricCurve,
+    Position2D, RevealProgress, Style as MathStyle,
+};
+use crate::render::{
+    axes_segments, fill_color, grid_colors, grid_segments, screen_points, stroke_color, AxisLabel,
+    AxisNameLabel, CoordinateSystem, AXES_ORIGIN_RADIUS, GIZMO_LINE_WIDTH,
+};
+use bevy::prelude::*;
+use std::fmt::Write;
+
+/// 导出时读取的对象组件
+type SvgObject<'a> = (
+    &'a MathObject,
+    &'a Position2D,
+    &'a Transform,
+    &'a MathStyle,
+    &'a InheritedVisibility,
+    Option<&'a RevealProgress>,
+    SvgShape<'a>,
+);
+
+type SvgShape<'a> = (
+    Option<&'a MathCircle>,
+    Option<&'a Line>,
+    Option<&'a MathRectangle>,
+    Option<&'a FunctionGraph>,
+    Option<&'a ParametricCurve>,
+    Option<&'a Axes>,
+    Option<&'a Grid>,
+    Option<&'a MorphOutline>,
+);
+
+/// 坐标轴的名称和数字标签
+type SvgLabel<'a> = (
+    &'a Text2d,
+    &'a TextFont,
+    &'a TextColor,
+    &'a GlobalTransform,
+    &'a InheritedVisibility,
+);
+
+/// 导出 SVG 所需的查询：数学对象和坐标轴标签
+pub type SvgQueries<'w, 's> = (
+    Query<'w, 's, SvgObject<'static>>,
+    Query<'w, 's, SvgLabel<'static>, Or<(With<AxisLabel>, With<AxisNameLabel>)>>,
+);
+
+/// 生成当前视口的 SVG 文档，尺寸为视口像素尺寸
+pub fn scene_svg(
+    (objects, labels): &SvgQueries,
+    coordinate_system: &CoordinateSystem,
+    background: Color,
+) -> String {
+    let mut svg = SvgWriter::new(coordinate_system.viewport_size);
+    svg.background(background);
+
+    // 按图层从下到上绘制，网格和坐标轴位于底层
+    let mut visible: Vec<_> = objects
+        .iter()
+        .filter(|(_, _, _, _, visibility, ..)| visibility.get())
+        .collect();
+    visible.sort_by_key(|(object, ..)| object.layer);
+
+    for (_, position, transform, style, _, reveal, shape) in visible {
+        let reveal = reveal.cloned().unwrap_or_default();
+        let (circle, line, rectangle, graph, curve, axes, grid, morph) = shape;
+
+        if let Some(grid) = grid {
+            let (major, minor) = grid_segments(grid, position, coordinate_system);
+            let (major_color, minor_color) = grid_colors(grid, style);
+            svg.segments(&minor, minor_color, GIZMO_LINE_WIDTH);
+            svg.segments(&major, major_color, GIZMO_LINE_WIDTH);
+            continue;
+        }
+        if let Some(axes) = axes {
+            let segments = axes_segments(axes, position, coordinate_system);
+            svg.segments(&segments, style.stroke_color, GIZMO_LINE_WIDTH);
+            let origin = coordinate_system.to_screen(Vec2::new(position.x, position.y));
+            svg.circle(
+                origin,
+                AXES_ORIGIN_RADIUS,
+                None,
+                Some((style.stroke_color, GIZMO_LINE_WIDTH)),
+            );
+            continue;
+        }
+
+        let stroke = stroke_color(style);
+        let to_screen =
+            |points: &[Vec2]| screen_points(points, position, transform, coordinate_system);
+        // 变形中的对象绘制 MorphOutline 代替自身形状
+        if let Some(morph) = morph {
+            svg.path(
+                &to_screen(&morph.points),
+                None,
+                Some((stroke, style.stroke_width)),
+                reveal.stroke,
+            );
+        } else if let Some(circle) = circle {
+            let center = coordinate_system.to_screen(Vec2::new(position.x, position.y));
+            let radius = coordinate_system.to_screen_length(circle.radius * transform.scale.x);
+            let fill = (circle.filled && reveal.fill > 0.0)
+                .then(|| stroke.with_alpha(stroke.alpha() * 0.6 * reveal.fill));
+            if reveal.stroke >= 1.0 {
+                svg.circle(center, radius, fill, Some((stroke, GIZMO_LINE_WIDTH)));
+            } else {
+                // 描边动画中只绘制部分轮廓
+                svg.circle(center, radius, fill, None);
+                let outline = to_screen(&circle.outline(circle.resolution.unwrap_or(96)));
+                svg.path(
+                    &outline,
+                    None,
+                    Some((stroke, GIZMO_LINE_WIDTH)),
+                    reveal.stroke,
+                );
+            }
+        } else if let Some(rectangle) = rectangle {
+            let outline = to_screen(&rectangle.outline());
+            let fill = fill_color(style)
+                .filter(|_| reveal.fill > 0.0)
+                .map(|fill| fill.with_alpha(fill.alpha() * reveal.fill));
+            if fill.is_some() {
+                svg.path(&outline, fill, None, 1.0);
+            }
+            svg.path(
+                &outline,
+                None,
+                Some((stroke, style.stroke_width)),
+                reveal.stroke,
+            );
+        } else {
+            let points = match (line, graph, curve) {
+                (Some(line), _, _) => line.outline(),
+                (_, Some(graph), _) => graph.points.clone(),
+                (_, _, Some(curve)) => curve.points.clone(),
+                _ => continue,
+            };
+            svg.path(
+                &to_screen(&points),
+                None,
+                Some((stroke, style.stroke_width)),
+                reveal.stroke,
+            );
+        }
+    }
+
+    // 文字标签位于最上层
+    for (text, font, color, transform, visibility) in labels.iter() {
+        if visibility.get() {
+            svg.text(
+                transform.translation().truncate(),
+                &text.0,
+                font.font_size,
+                color.0,
+            );
+        }
+    }
+
+    svg.finish()
+}
+
+/// 按弧长截取折线的前 progress 部分，保留 NaN 断点
+fn reveal_points(points: &[Vec2], progress: f32) -> Vec<Vec2> {
+    if progress >= 1.0 {
+        return points.to_vec();
+    }
+    let mut remaining = crate::math_objects::polyline_length(points) * progress.max(0.0);
+    let mut revealed = Vec::new();
+    let mut previous: Option<Vec2> = None;
+    for point in points {
+        if !point.is_finite() {
+            previous = None;
+            revealed.push(*point);
+            continue;
+        }
+        if let Some(start) = previous {
+            let length = start.distance(*point);
+            if length >= remaining {
+                revealed.push(start.lerp(*point, remaining / length.max(f32::EPSILON)));
+                return revealed;
+            }
+            remaining -= length;
+        }
+        revealed.push(*point);
+        previous = Some(*point);
+    }
+    revealed
+}
+
+/// 颜色属性，例如 `stroke="#ff8000" stroke-opacity="0.5"`
+fn paint(attribute: &str, color: Color) -> String {
+    let color = color.to_srgba();
+    let [r, g, b, a] = color.to_u8_array();
+    if a == 255 {
+        format!("{attribute}=\"#{r:02x}{g:02x}{b:02x}\"")
+    } else {
+        format!(
+            "{attribute}=\"#{r:02x}{g:02x}{b:02x}\" {attribute}-opacity=\"{:.3}\"",
+            color.alpha
+        )
+    }
+}
+
+/// 转义 XML 文本
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// SVG 文档构建器：屏幕坐标（原点在视口中心，Y 轴向上）换算为 SVG 坐标（原点在左上角，Y 轴向下）
+struct SvgWriter {
+    out: String,
+    size: Vec2,
+}
+
+impl SvgWriter {
+    fn new(size: Vec2) -> Self {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+            w = size.x,
+            h = size.y
+        );
+        Self { out, size }
+    }
+
+    fn point(&self, point: Vec2) -> Vec2 {
+        Vec2::new(point.x + self.size.x * 0.5, self.size.y * 0.5 - point.y)
+    }
+
+    fn background(&mut self, color: Color) {
+        let _ = writeln!(
+            self.out,
+            "  <rect width=\"100%\" height=\"100%\" {}/>",
+            paint("fill", color)
+        );
+    }
+
+    /// 一组独立线段，合并为一个 path 元素
+    fn segments(&mut self, segments: &[(Vec2, Vec2)], color: Color, width: f32) {
+        if segments.is_empty() {
+            return;
+        }
+        let mut data = String::new();
+        for (start, end) in segments {
+            let (start, end) = (self.point(*start), self.point(*end));
+            let _ = write!(
+                data,
+                "M{:.2} {:.2}L{:.2} {:.2}",
+                start.x, start.y, end.x, end.y
+            );
+        }
+        let _ = writeln!(
+            self.out,
+            "  <path d=\"{}\" fill=\"none\" {} stroke-width=\"{}\"/>",
+            data,
+            paint("stroke", color),
+            width
+        );
+    }
+
+    /// 折线路径，NaN 处断开；描边只绘制前 progress 比例的弧长
+    fn path(
+        &mut self,
+        points: &[Vec2],
+        fill: Option<Color>,
+        stroke: Option<(Color, f32)>,
+        progress: f32,
+    ) {
+        if progress <= 0.0 && fill.is_none() {
+            return;
+        }
+        let mut data = String::new();
+        let mut pen_down = false;
+        for point in reveal_points(points, progress) {
+            if !point.is_finite() {
+                pen_down = false;
+                continue;
+            }
+            let point = self.point(point);
+            let command = if pen_down { 'L' } else { 'M' };
+            let _ = write!(data, "{}{:.2} {:.2}", command, point.x, point.y);
+            pen_down = true;
+        }
+        if data.is_empty() {
+            return;
+        }
+
+        let fill = fill.map_or("fill=\"none\"".to_string(), |color| paint("fill", color));
+        let stroke = stroke.map_or(String::new(), |(color, width)| {
+            format!(
+                " {} stroke-width=\"{}\" stroke-linejoin=\"round\" stroke-linecap=\"round\"",
+                paint("stroke", color),
+                width
+            )
+        });
+        let _ = writeln!(self.out, "  <path d=\"{}\" {}{}/>", data, fill, stroke);
+    }
+
+    fn circle(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        fill: Option<Color>,
+        stroke: Option<(Color, f32)>,
+    ) {
+        let center = self.point(center);
+        let fill = fill.map_or("fill=\"none\"".to_string(), |color| paint("fill", color));
+        let stroke = stroke.map_or(String::new(), |(color, width)| {
+            format!(" {} stroke-width=\"{}\"", paint("stroke", color), width)
+        });
+        let _ = writeln!(
+            self.out,
+            "  <circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" {}{}/>",
+            center.x, center.y, radius, fill, stroke
+        );
+    }
+
+    /// 以 position 为中心的文字，与 Text2d 默认的居中锚点一致
+    fn text(&mut self, position: Vec2, text: &str, font_size: f32, color: Color) {
+        let position = self.point(position);
+        let _ = writeln!(
+            self.out,
+            "  <text x=\"{:.2}\" y=\"{:.2}\" font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" {}>{}</text>",
+            position.x,
+            position.y,
+            font_size,
+            paint("fill", color),
+            escape(text)
+        );
+    }
+
+    fn finish(mut self) -> String {
+        self.out.push_str("</svg>\n");
+        self.out
+    }
+}
@@ -0,0 +1,207 @@
+//! 离屏渲染目标：导出画面渲染到指定尺寸的图像，而不是截取窗口
+//!
+//! 相机投影覆盖与窗口相同的视口（数学单位下的可见范围），输出尺寸与窗口尺寸无关；
+//! 宽高比不同时保证窗口中的内容完整可见。超采样时按倍数放大渲染，读取后再缩小到输出尺寸。
+
+use super::ExportError;
+use crate::render::GIZMO_LINE_WIDTH;
+use bevy::gizmos::gizmos::GizmoStorage;
+use bevy::render::camera::{ClearColorConfig, RenderTarget, ScalingMode};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy::render::renderer::RenderDevice;
+use bevy::render::view::RenderLayers;
+use bevy::{asset::RenderAssetUsages, prelude::*};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+/// 离屏渲染的画质选项
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureOptions {
+    /// 超采样倍数（1、2 或 4）
+    pub supersample: u32,
+    /// 透明背景，用于合成；只有 PNG 输出保留透明度
+    pub transparent_background: bool,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        Self {
+            supersample: 1,
+            transparent_background: false,
+        }
+    }
+}
+
+/// 只有窗口相机渲染的层，默认配置组的 Gizmos 绘制在这一层
+pub const WINDOW_GIZMO_LAYER: usize = 1;
+/// 只有离屏相机渲染的层，CaptureGizmos 配置组绘制在这一层
+pub const CAPTURE_GIZMO_LAYER: usize = 2;
+
+/// 离屏渲染的 Gizmos 配置组，线宽按目标像素比例单独设置，不影响窗口中的线条
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct CaptureGizmos;
+
+/// 窗口相机的渲染层：普通实体（文字标签等）和窗口 Gizmos
+pub fn window_render_layers() -> RenderLayers {
+    RenderLayers::from_layers(&[0, WINDOW_GIZMO_LAYER])
+}
+
+/// 渲染到离屏目标的相机
+#[derive(Component)]
+pub struct CaptureCamera {
+    /// 目标图像像素与屏幕像素之比
+    pub pixel_scale: f32,
+}
+
+/// 离屏渲染目标：图像和渲染到该图像的相机
+#[derive(Clone)]
+pub struct CaptureTarget {
+    pub image: Handle<Image>,
+    pub camera: Entity,
+    /// 输出尺寸
+    pub resolution: (u32, u32),
+    pub options: CaptureOptions,
+}
+
+impl CaptureTarget {
+    /// 创建离屏图像和相机，相机投影覆盖 viewport（屏幕像素）
+    ///
+    /// 超采样后超出显卡支持的纹理尺寸时降低倍数，输出尺寸本身超出时返回错误。
+    pub fn spawn(
+        commands: &mut Commands,
+        images: &mut Assets<Image>,
+        device: &RenderDevice,
+        resolution: (u32, u32),
+        viewport: Vec2,
+        mut options: CaptureOptions,
+    ) -> Result<Self, ExportError> {
+        let (width, height) = resolution;
+        let largest = width.max(height).max(1);
+        let max = device.limits().max_texture_dimension_2d;
+        if largest > max {
+            return Err(ExportError::TargetTooLarge {
+                size: resolution,
+                max,
+            });
+        }
+        options.supersample = options.supersample.clamp(1, max / largest);
+
+        let size = Extent3d {
+            width: width * options.supersample,
+            height: height * options.supersample,
+            ..default()
+        };
+        let mut image = Image::new_fill(
+            size,
+            TextureDimension::D2,
+            &[0; 4],
+            TextureFormat::bevy_default(),
+            RenderAssetUsages::default(),
+        );
+        image.texture_descriptor.usage |= TextureUsages::COPY_SRC
+            | TextureUsages::RENDER_ATTACHMENT
+            | TextureUsages::TEXTURE_BINDING;
+        let handle = images.add(image);
+
+        let clear_color = if options.transparent_background {
+            ClearColorConfig::Custom(Color::NONE)
+        } else {
+            ClearColorConfig::Default
+        };
+        // AutoMin 与投影的缩放方式一致：视口完整可见时每个屏幕像素对应的目标像素数
+        let pixel_scale = (size.width as f32 / viewport.x).min(size.height as f32 / viewport.y);
+        let camera = commands
+            .spawn((
+                Camera2d,
+                Camera {
+                    target: RenderTarget::Image(handle.clone().into()),
+                    order: -1,
+                    clear_color,
+                    ..default()
+                },
+                Projection::Orthographic(OrthographicProjection {
+                    scaling_mode: ScalingMode::AutoMin {
+                        min_width: viewport.x,
+                        min_height: viewport.y,
+                    },
+                    ..OrthographicProjection::default_2d()
+                }),
+                CaptureCamera { pixel_scale },
+                RenderLayers::from_layers(&[0, CAPTURE_GIZMO_LAYER]),
+            ))
+            .id();
+
+        Ok(Self {
+            image: handle,
+            camera,
+            resolution,
+            options,
+        })
+    }
+
+    /// 把截图缩小到输出尺寸；不透明时去掉 alpha 通道
+    pub fn read(&self, image: Image) -> Result<DynamicImage, ExportError> {
+        let image = image
+            .try_into_dynamic()
+            .map_err(|error| ExportError::Screenshot(error.to_string()))?;
+        let (width, height) = self.resolution;
+        let image = if self.options.supersample > 1 {
+            image.resize_exact(width, height, FilterType::Triangle)
+        } else {
+            image
+        };
+        Ok(if self.options.transparent_background {
+            let mut image = image.to_rgba8();
+            image.pixels_mut().for_each(unpremultiply);
+            DynamicImage::ImageRgba8(image)
+        } else {
+            DynamicImage::ImageRgb8(image.to_rgb8())
+        })
+    }
+}
+
+/// 透明背景上混合得到的颜色已乘以 alpha，PNG 需要未预乘的颜色；在线性空间中还原
+fn unpremultiply(pixel: &mut image::Rgba<u8>) {
+    let [r, g, b, a] = pixel.0;
+    if a == 0 || a == u8::MAX {
+        return;
+    }
+    let alpha = a as f32 / 255.0;
+    let color = LinearRgba::from(Srgba::rgb_u8(r, g, b));
+    let color = Srgba::from(LinearRgba::rgb(
+        (color.red / alpha).min(1.0),
+        (color.green / alpha).min(1.0),
+        (color.blue / alpha).min(1.0),
+    ));
+    let [r, g, b, _] = color.to_u8_array();
+    pixel.0 = [r, g, b, a];
+}
+
+/// 把默认配置组和离屏配置组分到各自的渲染层，窗口和离屏相机互不看到对方的线条
+pub fn setup_gizmo_layers(mut config_store: ResMut<GizmoConfigStore>) {
+    let (config, _) = config_store.config_mut::<DefaultGizmoConfigGroup>();
+    config.render_layers = RenderLayers::layer(WINDOW_GIZMO_LAYER);
+    let (config, _) = config_store.config_mut::<CaptureGizmos>();
+    config.render_layers = RenderLayers::layer(CAPTURE_GIZMO_LAYER);
+}
+
+/// 离屏渲染期间把本帧绘制的 Gizmos 复制到离屏配置组，并按目标像素比例设置其线宽，
+/// 使导出画面的线条粗细与屏幕一致
+pub fn copy_capture_gizmos(
+    cameras: Query<&CaptureCamera>,
+    window: Res<GizmoStorage<DefaultGizmoConfigGroup, ()>>,
+    mut capture: ResMut<GizmoStorage<CaptureGizmos, ()>>,
+    mut config_store: ResMut<GizmoConfigStore>,
+) {
+    let Some(scale) = cameras.iter().map(|camera| camera.pixel_scale).next() else {
+        return;
+    };
+    let (config, _) = config_store.config_mut::<CaptureGizmos>();
+    let width = GIZMO_LINE_WIDTH * scale;
+    if config.line.width != width {
+        config.line.width = width;
+    }
+    capture.append_storage(&*window);
+}
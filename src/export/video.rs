@@ -0,0 +1,332 @@
+//! 视频导出：把逐帧截取的原始像素通过管道交给 ffmpeg 编码为 MP4 或 WebM
+//!
+//! PATH 中没有 ffmpeg 时退回为 PNG 序列，并在帧目录中写入可直接运行的编码脚本
+//! （`encode.sh` 和 `encode.bat`），装好 ffmpeg 后运行即可得到同样的视频。
+
+use super::capture::CaptureOutput;
+use super::{AnimationExportSettings, ExportError};
+use bevy::prelude::*;
+use image::DynamicImage;
+use std::collections::BTreeMap;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Arc;
+
+/// 外部编码器程序名
+const FFMPEG: &str = "ffmpeg";
+
+/// 视频编码格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VideoCodec {
+    /// H.264，输出 MP4
+    H264,
+    /// VP9，输出 WebM
+    VP9,
+}
+
+impl VideoCodec {
+    pub const ALL: [VideoCodec; 2] = [VideoCodec::H264, VideoCodec::VP9];
+
+    /// 界面显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::H264 => "H.264 (MP4)",
+            Self::VP9 => "VP9 (WebM)",
+        }
+    }
+
+    /// ffmpeg 编码器名称
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            Self::H264 => "libx264",
+            Self::VP9 => "libvpx-vp9",
+        }
+    }
+
+    /// 输出文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::H264 => "mp4",
+            Self::VP9 => "webm",
+        }
+    }
+
+    /// 常用的默认质量参数
+    pub fn default_crf(&self) -> u8 {
+        match self {
+            Self::H264 => 23,
+            Self::VP9 => 31,
+        }
+    }
+
+    /// 编码参数（输入之后、输出文件之前的部分）
+    fn args(&self, crf: u8) -> Vec<String> {
+        let mut args = vec![
+            "-c:v".to_string(),
+            self.encoder().to_string(),
+            "-crf".to_string(),
+            crf.to_string(),
+        ];
+        // VP9 只有在码率为 0 时才按 CRF 控制质量
+        if *self == Self::VP9 {
+            args.extend(["-b:v".to_string(), "0".to_string()]);
+        }
+        // yuv420p 要求宽高为偶数，奇数尺寸补一行黑边
+        args.extend(
+            [
+                "-vf",
+                "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+                "-pix_fmt",
+                "yuv420p",
+            ]
+            .map(String::from),
+        );
+        args
+    }
+}
+
+/// 为视频导出选择输出：优先启动 ffmpeg，找不到时改为 PNG 序列加编码脚本
+pub fn video_output(
+    path: &Path,
+    settings: &AnimationExportSettings,
+    resolution: (u32, u32),
+) -> Result<CaptureOutput, ExportError> {
+    match VideoEncoder::spawn(path, settings, resolution) {
+        Ok(encoder) => Ok(CaptureOutput::Video {
+            path: path.to_path_buf(),
+            encoder: Some(encoder),
+        }),
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            let dir = fallback_dir(path);
+            std::fs::create_dir_all(&dir).map_err(|error| ExportError::CreateDir {
+                path: dir.clone(),
+                source: Arc::new(error),
+            })?;
+            write_encode_scripts(&dir, path, settings).map_err(|error| ExportError::Write {
+                path: dir.clone(),
+                source: Arc::new(error),
+            })?;
+            warn!(
+                "未找到 {}，改为输出 PNG 序列 {}，安装后运行其中的 encode 脚本生成视频",
+                FFMPEG,
+                dir.display()
+            );
+            Ok(CaptureOutput::PngSequence(dir))
+        }
+        Err(error) => Err(ExportError::SpawnEncoder(Arc::new(error))),
+    }
+}
+
+/// 退回 PNG 序列时的帧目录，例如 `screenshots/rim_video_1.mp4` 对应 `screenshots/rim_video_1_frames`
+fn fallback_dir(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map_or("video".into(), |stem| stem.to_string_lossy());
+    path.with_file_name(format!("{}_frames", stem))
+}
+
+/// 在帧目录中写入 shell 和批处理编码脚本，视频输出到帧目录旁边
+fn write_encode_scripts(
+    dir: &Path,
+    path: &Path,
+    settings: &AnimationExportSettings,
+) -> std::io::Result<()> {
+    let output = path
+        .file_name()
+        .map_or("video".into(), |name| name.to_string_lossy());
+    let mut args = vec![
+        FFMPEG.to_string(),
+        "-y".to_string(),
+        "-framerate".to_string(),
+        settings.fps.to_string(),
+        "-i".to_string(),
+        "frame_%05d.png".to_string(),
+    ];
+    args.extend(settings.codec.args(settings.crf));
+
+    // 文件名来自可编辑的模板，参数一律加引号转义，不能被当作命令执行
+    let output = format!("../{}", output);
+    let shell: Vec<String> = args
+        .iter()
+        .chain([&output])
+        .map(|arg| shell_quote(arg))
+        .collect();
+    let script = format!(
+        "#!/bin/sh\ncd \"$(dirname \"$0\")\" || exit 1\n{}\n",
+        shell.join(" ")
+    );
+    let script_path = dir.join("encode.sh");
+    std::fs::write(&script_path, script)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&script_path, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    let output = output.replace('/', "\\");
+    let batch: Vec<String> = args
+        .iter()
+        .chain([&output])
+        .map(|arg| batch_quote(arg))
+        .collect();
+    let batch = format!("@echo off\r\ncd /d \"%~dp0\"\r\n{}\r\n", batch.join(" "));
+    std::fs::write(dir.join("encode.bat"), batch)
+}
+
+/// sh 单引号参数：引号内没有任何特殊字符，' 写成 '\''
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// 批处理双引号参数：" 写成 ""，% 写成 %%
+fn batch_quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('"', "\"\"").replace('%', "%%"))
+}
+
+/// ffmpeg 子进程，按帧序号顺序写入 RGBA 原始像素
+pub struct VideoEncoder {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    resolution: (u32, u32),
+    /// 下一个要写入的帧序号
+    next: u32,
+    /// 提前到达、等待按顺序写入的帧
+    pending: BTreeMap<u32, Vec<u8>>,
+}
+
+impl VideoEncoder {
+    /// 启动 ffmpeg，从标准输入读取 rawvideo
+    pub fn spawn(
+        path: &Path,
+        settings: &AnimationExportSettings,
+        resolution: (u32, u32),
+    ) -> std::io::Result<Self> {
+        let (width, height) = resolution;
+        let mut child = Command::new(FFMPEG)
+            .args(["-hide_banner", "-loglevel", "error", "-y"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{}x{}", width, height)])
+            .args(["-framerate", &settings.fps.to_string()])
+            .args(["-i", "-"])
+            .args(settings.codec.args(settings.crf))
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        Ok(Self {
+            stdin: child.stdin.take(),
+            child,
+            resolution,
+            next: 0,
+            pending: BTreeMap::new(),
+        })
+    }
+
+    /// 加入第 index 帧（从 0 开始），按顺序写入管道
+    pub fn add_frame(&mut self, index: u32, image: DynamicImage) -> Result<(), ExportError> {
+        let actual = (image.width(), image.height());
+        if actual != self.resolution {
+            return Err(ExportError::FrameSize {
+                actual,
+                expected: self.resolution,
+            });
+        }
+        let pixels = image.to_rgba8().into_raw();
+        self.pending.insert(index, pixels);
+
+        while let Some(pixels) = self.pending.remove(&self.next) {
+            let stdin = self.stdin.as_mut().ok_or(ExportError::EncoderClosed)?;
+            if let Err(error) = stdin.write_all(&pixels) {
+                // 管道断开通常是 ffmpeg 已退出，错误原因在它的输出中
+                self.stdin = None;
+                return Err(self
+                    .wait()
+                    .err()
+                    .unwrap_or(ExportError::Pipe(Arc::new(error))));
+            }
+            self.next += 1;
+        }
+        Ok(())
+    }
+
+    /// 关闭管道并等待 ffmpeg 完成编码
+    pub fn finish(mut self) -> Result<(), ExportError> {
+        self.stdin = None;
+        self.wait()
+    }
+
+    fn wait(&mut self) -> Result<(), ExportError> {
+        let status = self
+            .child
+            .wait()
+            .map_err(|error| ExportError::Pipe(Arc::new(error)))?;
+        if status.success() {
+            return Ok(());
+        }
+        let mut message = String::new();
+        if let Some(stderr) = self.child.stderr.as_mut() {
+            let _ = stderr.read_to_string(&mut message);
+        }
+        Err(ExportError::Encoder {
+            status: status.to_string(),
+            message: message.trim().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 含引号、百分号和命令分隔符的文件名
+    const NAME: &str = "it's \"x\" 100%; touch pwned.mp4";
+
+    fn scripts(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("rim_scripts_{}_{}", name, std::process::id()));
+        let dir = root.join("frames");
+        std::fs::create_dir_all(&dir).unwrap();
+        write_encode_scripts(&dir, &root.join(NAME), &AnimationExportSettings::default()).unwrap();
+        dir
+    }
+
+    #[test]
+    fn batch_script_escapes_quotes_and_percent() {
+        let dir = scripts("bat");
+        let batch = std::fs::read_to_string(dir.join("encode.bat")).unwrap();
+        assert!(batch.contains("\"frame_%%05d.png\""));
+        assert!(batch.contains(" \"..\\it's \"\"x\"\" 100%%; touch pwned.mp4\"\r\n"));
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shell_script_passes_the_filename_as_one_argument() {
+        let dir = scripts("sh");
+        // 假的 ffmpeg 把收到的参数逐行写入 args.txt
+        let bin = dir.join("bin");
+        std::fs::create_dir(&bin).unwrap();
+        let fake = bin.join(FFMPEG);
+        std::fs::write(&fake, "#!/bin/sh\nprintf '%s\\n' \"$@\" > args.txt\n").unwrap();
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let path = format!(
+            "{}:{}",
+            bin.display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        let status = Command::new(dir.join("encode.sh"))
+            .env("PATH", path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let args = std::fs::read_to_string(dir.join("args.txt")).unwrap();
+        assert_eq!(args.lines().last(), Some(format!("../{NAME}").as_str()));
+        assert!(args.lines().any(|arg| arg == "frame_%05d.png"));
+        assert!(!dir.join("pwned.mp4").exists());
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
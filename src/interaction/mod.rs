@@ -1,11 +1,54 @@
+use crate::animation::{MathAnimation, Timeline};
+use crate::scene::{in_scene, LoadSceneRequest, MathScene, SaveSceneRequest, SceneFileState};
 use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
+use bevy_egui::EguiContexts;
 
 pub struct InteractionPlugin;
 
 impl Plugin for InteractionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (handle_mouse_input, handle_keyboard_input));
+        app.init_resource::<CameraState>()
+            .add_systems(Update, (handle_mouse_input, handle_keyboard_input));
+    }
+}
+
+/// 相机状态资源，管理缩放和平移
+#[derive(Resource)]
+pub struct CameraState {
+    pub zoom: f32,                       // 当前缩放级别
+    pub target_zoom: f32,                // 目标缩放级别
+    pub zoom_speed: f32,                 // 缩放速度
+    pub min_zoom: f32,                   // 最小缩放
+    pub max_zoom: f32,                   // 最大缩放
+    pub translation: Vec2,               // 相机平移（屏幕中心对应的数学坐标）
+    pub target_translation: Vec2,        // 目标平移
+    pub pan_speed: f32,                  // 键盘平移速度（像素/秒）
+    pub is_panning: bool,                // 是否正在拖拽平移
+    pub zoom_anchor: Option<ZoomAnchor>, // 缩放锚点，缩放过程中保持该点在屏幕上不动
+}
+
+/// 缩放锚点：数学坐标点及其固定的屏幕位置
+#[derive(Clone, Copy)]
+pub struct ZoomAnchor {
+    pub math: Vec2,   // 光标下的数学坐标
+    pub screen: Vec2, // 该点对应的屏幕像素坐标（原点在屏幕中心）
+}
+
+impl Default for CameraState {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            target_zoom: 1.0,
+            zoom_speed: 0.1,
+            min_zoom: 0.1,
+            max_zoom: 10.0,
+            translation: Vec2::ZERO,
+            target_translation: Vec2::ZERO,
+            pan_speed: 600.0,
+            is_panning: false,
+            zoom_anchor: None,
+        }
     }
 }
 
@@ -33,18 +76,58 @@ fn handle_mouse_input(
     }
 }
 
+/// 场景文件的保存和加载请求
+type SceneFileRequests<'w> = (
+    EventWriter<'w, SaveSceneRequest>,
+    EventWriter<'w, LoadSceneRequest>,
+);
+
 /// 处理键盘输入的系统
-fn handle_keyboard_input(keyboard_input: Res<ButtonInput<KeyCode>>) {
+fn handle_keyboard_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut timeline: ResMut<Timeline>,
+    mut animations: Query<(&mut MathAnimation, Option<&ChildOf>)>,
+    scenes: Query<(Entity, &MathScene)>,
+    mut contexts: EguiContexts,
+    scene_file: Res<SceneFileState>,
+    (mut save_requests, mut load_requests): SceneFileRequests,
+) {
+    // 输入框获得焦点时不响应快捷键
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
     if keyboard_input.just_pressed(KeyCode::Space) {
-        // 播放/暂停动画
+        // 播放/暂停动画：优先控制时间轴，时间轴为空时控制独立播放的动画
+        // 只控制活动场景中的动画
+        if !timeline.is_empty() {
+            timeline.toggle_playing();
+        } else if let Some((scene, _)) = scenes.iter().find(|(_, scene)| scene.active) {
+            let mut active: Vec<_> = animations
+                .iter_mut()
+                .filter(|(_, child_of)| in_scene(*child_of, scene, true))
+                .map(|(animation, _)| animation)
+                .collect();
+            if active.iter().any(|animation| animation.is_playing) {
+                for animation in &mut active {
+                    animation.pause();
+                }
+            } else {
+                for animation in &mut active {
+                    animation.play();
+                }
+            }
+        }
     }
 
-    if keyboard_input.pressed(KeyCode::ControlLeft) {
+    if keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
         if keyboard_input.just_pressed(KeyCode::KeyS) {
             // 保存场景
+            save_requests.write(SaveSceneRequest(scene_file.path.clone().into()));
         }
         if keyboard_input.just_pressed(KeyCode::KeyO) {
             // 打开场景
+            load_requests.write(LoadSceneRequest::open(scene_file.path.clone()));
         }
     }
 }
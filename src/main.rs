@@ -15,7 +15,10 @@
  * along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
 
+use bevy::app::ScheduleRunnerPlugin;
 use bevy::prelude::*;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
 use bevy_egui::{egui, EguiContextPass, EguiContexts, EguiPlugin};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -28,15 +31,29 @@ mod math_objects;
 mod render;
 mod scene;
 
-use animation::AnimationPlugin;
-use export::{ExportFormat, ExportPlugin, ExportRequest};
-use interaction::InteractionPlugin;
+use animation::{
+    create_animation, create_graph_tracker, AnimationPlugin, AnimationType, MathAnimation,
+    RateFunction, Timeline, TimelineBlock, Updater,
+};
+use export::{
+    window_render_layers, AnimationExportSettings, CollisionPolicy, ExportFailed, ExportFinished,
+    ExportFormat, ExportPlugin, ExportProgress, ExportRequest, ExportSettings,
+    FrameCaptureFinished, GifRepeat, OfflineRenderPlugin, OfflineRenderSettings, VideoCodec,
+    OFFLINE_RENDER_USAGE,
+};
+use interaction::{CameraState, InteractionPlugin, ZoomAnchor};
+use math_objects::basic_shapes::Rectangle as MathRectangle;
 use math_objects::{
-    create_axes_with_labels, create_circle_with_resolution, create_grid, Axes, Grid,
-    MathObjectPlugin, Style as MathStyle,
+    create_circle_with_resolution, create_default_coordinate_system,
+    create_function_graph_from_expression, create_line, create_parametric_curve_from_expressions,
+    Axes, FunctionGraph, GraphParameter, Grid, Line, MathCircle, MathObject, MathObjectPlugin,
+    MathPath, ParametricCurve, Position2D, Style as MathStyle,
+};
+use render::{CoordinateSystem, RenderPlugin};
+use scene::{
+    handle_load_requests, in_scene, LoadSceneRequest, MathScene, SaveSceneRequest, SceneAction,
+    SceneFileState, SceneLoaded, ScenePlugin,
 };
-use render::RenderPlugin;
-use scene::ScenePlugin;
 
 /// UI显示状态资源
 #[derive(Resource)]
@@ -84,33 +101,9 @@ impl Default for PerformanceState {
     }
 }
 
-/// 相机状态资源，管理缩放和平移
-#[derive(Resource)]
-struct CameraState {
-    pub zoom: f32,                // 当前缩放级别
-    pub target_zoom: f32,         // 目标缩放级别
-    pub zoom_speed: f32,          // 缩放速度
-    pub min_zoom: f32,            // 最小缩放
-    pub max_zoom: f32,            // 最大缩放
-    pub translation: Vec2,        // 相机平移
-    pub target_translation: Vec2, // 目标平移
-    pub previous_zoom: f32,       // 上一帧的缩放级别，用于检测变化
-}
-
-impl Default for CameraState {
-    fn default() -> Self {
-        Self {
-            zoom: 1.0,
-            target_zoom: 1.0,
-            zoom_speed: 0.1,
-            min_zoom: 0.1,
-            max_zoom: 10.0,
-            translation: Vec2::ZERO,
-            target_translation: Vec2::ZERO,
-            previous_zoom: 1.0,
-        }
-    }
-}
+/// 缩放到适应所有数学对象的请求事件
+#[derive(Event)]
+struct ZoomToFitRequest;
 
 /// 坐标系显示状态资源
 #[derive(Resource)]
@@ -152,82 +145,229 @@ impl Default for CircleState {
     }
 }
 
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "RIM - Mathematical Visualization Tool".into(),
-                resolution: (1200., 800.).into(),
-                ..default()
+/// 直线管理状态资源
+#[derive(Resource)]
+struct LineState {
+    pub lines: Vec<Entity>,
+    pub show_panel: bool, // 是否展开直线创建面板
+    pub start: Vec2,
+    pub end: Vec2,
+    pub color: Color,
+    pub stroke_width: f32,
+}
+
+impl Default for LineState {
+    fn default() -> Self {
+        Self {
+            lines: Vec::new(),
+            show_panel: false,
+            start: Vec2::new(-2.0, -1.0),
+            end: Vec2::new(2.0, 1.0),
+            color: Color::srgb(0.9, 0.6, 0.2), // 橙色
+            stroke_width: 2.0,
+        }
+    }
+}
+
+/// 函数图形管理状态资源
+#[derive(Resource)]
+struct FunctionState {
+    pub graphs: Vec<Entity>,
+    pub trackers: Vec<Entity>,         // 数值追踪器及依赖它的对象
+    pub show_panel: bool,              // 是否展开函数输入面板
+    pub input: String,                 // 表达式输入
+    pub parametric: bool,              // 是否输入参数方程
+    pub curve_input: (String, String), // 参数方程 x(t)、y(t) 输入
+    pub domain: (f32, f32),            // 定义域（参数方程时为 t 的范围）
+    pub color: Color,
+    pub error: Option<String>, // 最近一次解析错误
+}
+
+impl Default for FunctionState {
+    fn default() -> Self {
+        Self {
+            graphs: Vec::new(),
+            trackers: Vec::new(),
+            show_panel: false,
+            input: "sin(x) * exp(-x^2/4)".to_string(),
+            parametric: false,
+            curve_input: ("cos(a*t)".to_string(), "sin(b*t)".to_string()),
+            domain: (-5.0, 5.0),
+            color: Color::srgb(0.3, 0.6, 1.0), // 蓝色
+            error: None,
+        }
+    }
+}
+
+/// 动画控制面板状态资源
+#[derive(Resource)]
+struct AnimationPanelState {
+    pub animation_type: AnimationType, // 要添加的动画类型
+    pub rate_function: RateFunction,   // 速率函数
+    pub duration: f32,                 // 动画时长（秒）
+    pub loop_animation: bool,
+    pub steps: Vec<Vec<Entity>>, // 每次添加的一组动画，作为时间轴中的一步
+    pub lagged: bool,            // 步骤内的动画是否错开开始
+    pub lag: f32,                // 错开的间隔（秒）
+    pub wait: f32,               // 步骤之间的等待（秒）
+}
+
+impl Default for AnimationPanelState {
+    fn default() -> Self {
+        Self {
+            animation_type: AnimationType::Draw,
+            rate_function: RateFunction::Smooth,
+            duration: 2.0,
+            loop_animation: false,
+            steps: Vec::new(),
+            lagged: false,
+            lag: 0.3,
+            wait: 0.5,
+        }
+    }
+}
+
+/// 动画面板中可选的动画预设
+fn animation_presets() -> Vec<AnimationType> {
+    vec![
+        AnimationType::Transform {
+            shift: Vec2::new(2.0, 1.0),
+            scale: 1.5,
+            rotation: std::f32::consts::FRAC_PI_2,
+        },
+        AnimationType::Fade { from: 0.0, to: 1.0 },
+        AnimationType::Draw,
+        AnimationType::Write,
+        // 变形为半径 1 的橙色圆
+        AnimationType::Morph {
+            target: MathPath::circle(1.0),
+            style: Some(MathStyle {
+                stroke_color: Color::srgb(1.0, 0.6, 0.2),
+                fill_color: None,
+                stroke_width: 3.0,
+                opacity: 1.0,
             }),
+        },
+    ]
+}
+
+fn main() -> AppExit {
+    let offline = match OfflineRenderSettings::from_args(std::env::args().skip(1)) {
+        Ok(offline) => offline,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("{}", OFFLINE_RENDER_USAGE);
+            return AppExit::error();
+        }
+    };
+
+    let mut app = App::new();
+    match offline {
+        // 离线渲染：无窗口、无界面，渲染完成后退出
+        Some(settings) => {
+            app.add_plugins(
+                DefaultPlugins
+                    .set(WindowPlugin {
+                        primary_window: None,
+                        exit_condition: ExitCondition::DontExit,
+                        close_when_requested: false,
+                    })
+                    .disable::<WinitPlugin>(),
+            )
+            .add_plugins((
+                ScheduleRunnerPlugin::run_loop(Duration::ZERO),
+                OfflineRenderPlugin(settings),
+            ));
+        }
+        None => add_interactive_app(&mut app),
+    }
+
+    app.add_plugins((
+        RenderPlugin,
+        MathObjectPlugin,
+        AnimationPlugin,
+        ScenePlugin,
+        ExportPlugin,
+    ))
+    .add_systems(Startup, (setup_scene, setup_coordinate_system))
+    .add_systems(
+        Update,
+        (
+            update_camera_smooth,
+            update_coordinate_system.after(update_camera_smooth),
+        ),
+    )
+    .run()
+}
+
+/// 带窗口和控制面板的交互模式
+fn add_interactive_app(app: &mut App) {
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            title: "RIM - Mathematical Visualization Tool".into(),
+            resolution: (1200., 800.).into(),
             ..default()
-        }))
-        .add_plugins(EguiPlugin {
-            enable_multipass_for_primary_context: true,
-        })
-        .add_plugins((
-            RenderPlugin,
-            MathObjectPlugin,
-            AnimationPlugin,
-            ScenePlugin,
-            InteractionPlugin,
-            ExportPlugin,
-        ))
-        .init_resource::<UiVisibility>()
-        .init_resource::<CameraState>()
-        .init_resource::<CoordinateSystemState>()
-        .init_resource::<CircleState>()
-        .init_resource::<PerformanceState>()
-        .add_systems(Startup, (setup_scene, setup_fonts, setup_coordinate_system))
-        .add_systems(
-            Update,
-            (
-                handle_ui_toggle,
-                handle_mouse_input,
-                update_camera_smooth,
-                update_coordinate_system,
-                handle_coordinate_system_toggle,
-                update_performance_monitor,
-                handle_performance_toggle,
-            ),
-        )
-        .add_systems(EguiContextPass, ui_system)
-        .run();
+        }),
+        ..default()
+    }))
+    .add_plugins(EguiPlugin {
+        enable_multipass_for_primary_context: true,
+    })
+    .add_plugins(InteractionPlugin)
+    .init_resource::<UiVisibility>()
+    .init_resource::<CoordinateSystemState>()
+    .init_resource::<CircleState>()
+    .init_resource::<LineState>()
+    .init_resource::<FunctionState>()
+    .init_resource::<AnimationPanelState>()
+    .init_resource::<PerformanceState>()
+    .add_event::<ZoomToFitRequest>()
+    .add_systems(Startup, (setup_fonts, load_scene_from_args))
+    .add_systems(
+        Update,
+        (
+            handle_ui_toggle,
+            handle_mouse_input,
+            handle_camera_pan,
+            handle_zoom_to_fit,
+            handle_coordinate_system_toggle,
+            update_performance_monitor,
+            handle_performance_toggle,
+            sync_loaded_scene.after(handle_load_requests),
+        ),
+    )
+    .add_systems(
+        EguiContextPass,
+        (ui_system, scene_error_overlay, export_toasts),
+    );
 }
 
 fn setup_scene(mut commands: Commands) {
     // 设置2D相机
-    commands.spawn(Camera2d);
+    commands.spawn((Camera2d, window_render_layers()));
+
+    // 默认数学场景，决定单位长度等场景级设置
+    commands.spawn(MathScene::default());
 }
 
 /// 设置坐标系统 - 创建坐标轴和网格用于测试
 fn setup_coordinate_system(mut commands: Commands) {
-    // 创建网格
-    create_grid(
-        &mut commands,
-        1.0, // 网格间距
-        MathStyle {
-            stroke_color: Color::srgba(0.3, 0.3, 0.3, 1.0),
-            fill_color: None,
-            stroke_width: 1.0,
-            opacity: 0.3,
-        },
-    );
+    create_default_coordinate_system(&mut commands);
+}
 
-    // 创建坐标轴
-    create_axes_with_labels(
-        &mut commands,
-        (-10.0, 10.0),   // x 范围
-        (-8.0, 8.0),     // y 范围
-        "x".to_string(), // x轴标签
-        "y".to_string(), // y轴标签
-        MathStyle {
-            stroke_color: Color::WHITE,
-            fill_color: None,
-            stroke_width: 2.0,
-            opacity: 1.0,
-        },
-    );
+/// 启动时加载命令行指定的场景，如 `rim lessons/intro.rim`
+///
+/// 场景在第一帧加载，替换 setup_coordinate_system 创建的默认坐标轴和网格。
+fn load_scene_from_args(
+    mut scene_file: ResMut<SceneFileState>,
+    mut load_requests: EventWriter<LoadSceneRequest>,
+) {
+    let Some(path) = std::env::args().nth(1) else {
+        return;
+    };
+    info!("加载场景: {}", path);
+    load_requests.write(LoadSceneRequest::open(path.clone()));
+    scene_file.path = path;
 }
 
 /// 处理UI切换的输入
@@ -248,23 +388,184 @@ fn handle_ui_toggle(
     }
 }
 
-/// 处理鼠标输入（滚轮缩放）
+/// 处理鼠标输入（滚轮缩放，以光标位置为中心）
 fn handle_mouse_input(
     mut scroll_events: EventReader<bevy::input::mouse::MouseWheel>,
     mut camera_state: ResMut<CameraState>,
+    coordinate_system: Res<CoordinateSystem>,
+    windows: Query<&Window>,
 ) {
+    let cursor = windows
+        .single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+
     for event in scroll_events.read() {
         // 计算缩放变化
         let zoom_delta = event.y * camera_state.zoom_speed;
         camera_state.target_zoom = (camera_state.target_zoom + zoom_delta)
             .clamp(camera_state.min_zoom, camera_state.max_zoom);
 
+        // 锁定光标下的数学坐标点，让缩放围绕该点进行
+        if let Some(cursor) = cursor {
+            let anchor = ZoomAnchor {
+                math: coordinate_system.cursor_to_math(cursor),
+                screen: coordinate_system.cursor_to_screen(cursor),
+            };
+            camera_state.target_translation = anchor.math
+                - anchor.screen / (coordinate_system.unit_size * camera_state.target_zoom);
+            camera_state.zoom_anchor = Some(anchor);
+        }
+
         info!("目标缩放级别: {:.2}", camera_state.target_zoom);
     }
 }
 
+/// 处理相机平移（鼠标中键/右键拖拽、方向键）
+fn handle_camera_pan(
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    time: Res<Time>,
+    coordinate_system: Res<CoordinateSystem>,
+    mut camera_state: ResMut<CameraState>,
+    mut contexts: EguiContexts,
+) {
+    let ctx = contexts.ctx_mut();
+    let pan_buttons = [MouseButton::Middle, MouseButton::Right];
+
+    // 只有在UI面板之外按下时才开始拖拽
+    if mouse_button_input.any_just_pressed(pan_buttons) && !ctx.is_pointer_over_area() {
+        camera_state.is_panning = true;
+    }
+    if !mouse_button_input.any_pressed(pan_buttons) {
+        camera_state.is_panning = false;
+    }
+
+    // 拖拽平移：光标前后位置对应的数学坐标之差即为视图的位移
+    for event in cursor_moved_events.read() {
+        if !camera_state.is_panning {
+            continue;
+        }
+        if let Some(delta) = event.delta {
+            let previous = coordinate_system.cursor_to_math(event.position - delta);
+            camera_state.target_translation +=
+                previous - coordinate_system.cursor_to_math(event.position);
+            camera_state.zoom_anchor = None;
+        }
+    }
+
+    // 方向键平移（输入框获得焦点时不响应）
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+    let mut direction = Vec2::ZERO;
+    if keyboard_input.pressed(KeyCode::ArrowLeft) {
+        direction.x -= 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::ArrowRight) {
+        direction.x += 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::ArrowDown) {
+        direction.y -= 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::ArrowUp) {
+        direction.y += 1.0;
+    }
+    if direction != Vec2::ZERO {
+        let step = camera_state.pan_speed * time.delta_secs() / coordinate_system.pixels_per_unit();
+        camera_state.target_translation += direction.normalize() * step;
+        camera_state.zoom_anchor = None;
+    }
+}
+
+/// 参与“适应所有对象”计算的对象组件
+type FitObject<'a> = (
+    &'a Position2D,
+    &'a InheritedVisibility,
+    Option<&'a MathCircle>,
+    Option<&'a Line>,
+    Option<&'a MathRectangle>,
+    Option<&'a FunctionGraph>,
+    Option<&'a ParametricCurve>,
+);
+
+/// 处理“适应所有对象”请求：计算所有数学对象的包围盒并让相机平滑地框住它们
+fn handle_zoom_to_fit(
+    mut requests: EventReader<ZoomToFitRequest>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut camera_state: ResMut<CameraState>,
+    coordinate_system: Res<CoordinateSystem>,
+    objects: Query<FitObject, With<MathObject>>,
+    mut contexts: EguiContexts,
+) {
+    // F键或UI按钮触发（输入框获得焦点时不响应F键）
+    let key_pressed =
+        keyboard_input.just_pressed(KeyCode::KeyF) && !contexts.ctx_mut().wants_keyboard_input();
+    let requested = requests.read().count() > 0 || key_pressed;
+    if !requested {
+        return;
+    }
+
+    let mut bounds: Option<Rect> = None;
+    for (position, visibility, circle, line, rectangle, graph, curve) in objects.iter() {
+        if !visibility.get() {
+            continue;
+        }
+
+        let center = Vec2::new(position.x, position.y);
+        let object_bounds = circle
+            .map(|circle| circle.bounds(center))
+            .or_else(|| line.map(|line| line.bounds(center)))
+            .or_else(|| rectangle.map(|rectangle| rectangle.bounds(center)))
+            .or_else(|| graph.and_then(|graph| graph.bounds()))
+            .or_else(|| curve.and_then(|curve| curve.bounds()))
+            .map(|rect| {
+                // 函数图形和参数曲线的采样点以 Position2D 为原点
+                if graph.is_some() || curve.is_some() {
+                    Rect::from_corners(rect.min + center, rect.max + center)
+                } else {
+                    rect
+                }
+            });
+
+        if let Some(object_bounds) = object_bounds {
+            bounds = Some(match bounds {
+                Some(rect) => rect.union(object_bounds),
+                None => object_bounds,
+            });
+        }
+    }
+
+    camera_state.zoom_anchor = None;
+    let Some(bounds) = bounds else {
+        // 没有可见对象时回到默认视图
+        camera_state.target_zoom = 1.0;
+        camera_state.target_translation = Vec2::ZERO;
+        info!("没有可适应的对象，已重置视图");
+        return;
+    };
+
+    // 留出 10% 边距，退化的包围盒（如单个点）至少占 1 个单位
+    let size = bounds.size().max(Vec2::ONE) * 1.1;
+    let fit_zoom =
+        (coordinate_system.viewport_size / (size * coordinate_system.unit_size)).min_element();
+    camera_state.target_zoom = fit_zoom.clamp(camera_state.min_zoom, camera_state.max_zoom);
+    camera_state.target_translation = bounds.center();
+    info!(
+        "适应所有对象: 中心({:.2}, {:.2}), 缩放{:.2}x",
+        bounds.center().x,
+        bounds.center().y,
+        camera_state.target_zoom
+    );
+}
+
 /// 平滑更新相机状态
-fn update_camera_smooth(time: Res<Time>, mut camera_state: ResMut<CameraState>) {
+fn update_camera_smooth(
+    time: Res<Time>,
+    mut camera_state: ResMut<CameraState>,
+    coordinate_system: Res<CoordinateSystem>,
+) {
     // 平滑插值到目标缩放
     let lerp_speed = 8.0; // 插值速度
     let dt = time.delta_secs();
@@ -276,42 +577,100 @@ fn update_camera_smooth(time: Res<Time>, mut camera_state: ResMut<CameraState>)
     if (camera_state.target_zoom - camera_state.zoom).abs() < 0.001 {
         camera_state.zoom = camera_state.target_zoom;
     }
+
+    // 有缩放锚点时，由锚点推算平移，保证光标下的点在缩放过程中保持不动
+    if let Some(anchor) = camera_state.zoom_anchor {
+        camera_state.translation =
+            anchor.math - anchor.screen / (coordinate_system.unit_size * camera_state.zoom);
+        if camera_state.zoom == camera_state.target_zoom {
+            camera_state.zoom_anchor = None;
+        }
+        return;
+    }
+
+    // 平滑插值到目标平移
+    let translation_delta = camera_state.target_translation - camera_state.translation;
+    camera_state.translation += translation_delta * (lerp_speed * dt).min(1.0);
+
+    if camera_state
+        .translation
+        .distance(camera_state.target_translation)
+        < 0.0001
+    {
+        camera_state.translation = camera_state.target_translation;
+    }
 }
 
-/// 根据相机状态更新坐标系统
+/// 根据相机状态更新坐标映射和坐标系统
 fn update_coordinate_system(
-    mut camera_state: ResMut<CameraState>,
-    mut axes_query: Query<&mut Axes>,
+    camera_state: Res<CameraState>,
+    mut coordinate_system: ResMut<CoordinateSystem>,
+    mut axes_query: Query<(&mut Axes, &Position2D)>,
     mut grid_query: Query<&mut Grid>,
 ) {
-    // 检查缩放是否有变化
-    if (camera_state.zoom - camera_state.previous_zoom).abs() > 0.001 {
-        // 更新坐标轴
-        for mut axes in axes_query.iter_mut() {
-            axes.update_for_zoom(camera_state.zoom);
+    // 将相机缩放和平移同步到坐标映射
+    if coordinate_system.zoom != camera_state.zoom {
+        coordinate_system.zoom = camera_state.zoom;
+    }
+    if coordinate_system.center != camera_state.translation {
+        coordinate_system.center = camera_state.translation;
+    }
+
+    // 缩放、平移、单位长度或视口尺寸有变化时更新坐标轴和网格
+    if coordinate_system.is_changed() {
+        let visible = coordinate_system.visible_rect();
+
+        // 更新坐标轴（范围相对坐标轴原点）
+        for (mut axes, position) in axes_query.iter_mut() {
+            axes.update_for_view(
+                (visible.min.x - position.x, visible.max.x - position.x),
+                (visible.min.y - position.y, visible.max.y - position.y),
+            );
         }
 
         // 更新网格
         for mut grid in grid_query.iter_mut() {
-            grid.update_for_zoom(camera_state.zoom);
+            grid.update_for_zoom(coordinate_system.zoom);
         }
-
-        camera_state.previous_zoom = camera_state.zoom;
     }
 }
 
+/// 坐标轴和网格的可见性及所属场景
+type CoordinateVisibility<'w, 's> = (
+    Query<'w, 's, (&'static mut Visibility, Option<&'static ChildOf>), (With<Axes>, Without<Grid>)>,
+    Query<'w, 's, (&'static mut Visibility, Option<&'static ChildOf>), (With<Grid>, Without<Axes>)>,
+);
+
+/// 当前活动场景，没有活动场景时返回占位实体
+fn active_scene(scenes: &Query<(Entity, &MathScene)>) -> Entity {
+    scenes
+        .iter()
+        .find(|(_, scene)| scene.active)
+        .map_or(Entity::PLACEHOLDER, |(entity, _)| entity)
+}
+
 /// 处理坐标系显示切换的键盘快捷键
 fn handle_coordinate_system_toggle(
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut coordinate_state: ResMut<CoordinateSystemState>,
-    mut axes_query: Query<&mut Visibility, (With<Axes>, Without<Grid>)>,
-    mut grid_query: Query<&mut Visibility, (With<Grid>, Without<Axes>)>,
-    mut export_events: EventWriter<ExportRequest>,
+    (mut axes_query, mut grid_query): CoordinateVisibility,
+    (mut export_events, export_settings): (EventWriter<ExportRequest>, Res<ExportSettings>),
+    scenes: Query<(Entity, &MathScene)>,
+    mut contexts: EguiContexts,
 ) {
-    // A键切换坐标轴显示
+    // 输入框获得焦点时不响应快捷键
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    let active_scene = active_scene(&scenes);
+
+    // A键切换坐标轴显示，只影响活动场景
     if keyboard_input.just_pressed(KeyCode::KeyA) {
         coordinate_state.show_axes = !coordinate_state.show_axes;
-        for mut visibility in axes_query.iter_mut() {
+        for (mut visibility, _) in axes_query
+            .iter_mut()
+            .filter(|(_, child_of)| in_scene(*child_of, active_scene, true))
+        {
             *visibility = if coordinate_state.show_axes {
                 Visibility::Inherited
             } else {
@@ -331,7 +690,10 @@ fn handle_coordinate_system_toggle(
     // G键切换网格显示
     if keyboard_input.just_pressed(KeyCode::KeyG) {
         coordinate_state.show_grid = !coordinate_state.show_grid;
-        for mut visibility in grid_query.iter_mut() {
+        for (mut visibility, _) in grid_query
+            .iter_mut()
+            .filter(|(_, child_of)| in_scene(*child_of, active_scene, true))
+        {
             *visibility = if coordinate_state.show_grid {
                 Visibility::Inherited
             } else {
@@ -348,23 +710,163 @@ fn handle_coordinate_system_toggle(
         );
     }
 
-    // S键保存截图
-    if keyboard_input.just_pressed(KeyCode::KeyS) {
+    // S键保存截图（Ctrl+S 为保存场景）
+    if keyboard_input.just_pressed(KeyCode::KeyS)
+        && !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    {
         export_events.write(ExportRequest {
             format: ExportFormat::PNG,
-            filename: format!(
-                "rim_screenshot_{}.png",
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs()
-            ),
-            resolution: (1920, 1080),
+            filename: None,
+            resolution: export_settings.resolution,
+            options: export_settings.options,
         });
         info!("截图快捷键触发 - 截图请求已发送");
     }
 }
 
+/// 场景加载失败时在画面上方显示错误浮层，当前场景保持不变
+///
+/// 浮层不受 F1 隐藏界面的影响，修正文件后热重载成功会自动关闭。
+fn scene_error_overlay(mut contexts: EguiContexts, mut scene_file: ResMut<SceneFileState>) {
+    if !scene_file.show_error {
+        return;
+    }
+    let Some(Err(error)) = &scene_file.last_result else {
+        return;
+    };
+
+    let mut dismissed = false;
+    egui::Window::new("⚠ 场景加载失败")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 20.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(
+                egui::RichText::new(error)
+                    .monospace()
+                    .color(egui::Color32::from_rgb(255, 120, 120)),
+            );
+            ui.label("修正文件并保存后将自动重新加载，画面保持上一次成功加载的场景");
+            if ui.button("关闭").clicked() {
+                dismissed = true;
+            }
+        });
+    if dismissed {
+        scene_file.show_error = false;
+    }
+}
+
+/// 导出结果提示
+struct ExportToast {
+    message: String,
+    is_error: bool,
+    /// 显示到的时间（秒）
+    until: f32,
+}
+
+/// 导出成功或失败时在右下角短暂显示提示，失败提示停留更久
+///
+/// 与错误浮层一样不受 F1 隐藏界面的影响。
+fn export_toasts(
+    mut contexts: EguiContexts,
+    time: Res<Time>,
+    mut finished: EventReader<ExportFinished>,
+    mut failed: EventReader<ExportFailed>,
+    mut toasts: Local<Vec<ExportToast>>,
+) {
+    let now = time.elapsed_secs();
+    for event in finished.read() {
+        toasts.push(ExportToast {
+            message: format!(
+                "✅ 已导出 {:?}: {}（{:.1} 秒）",
+                event.format,
+                event.path.display(),
+                event.duration.as_secs_f32()
+            ),
+            is_error: false,
+            until: now + 4.0,
+        });
+    }
+    for event in failed.read() {
+        toasts.push(ExportToast {
+            message: format!(
+                "❌ 导出 {:?} {} 失败: {}",
+                event.request.format,
+                event.path.display(),
+                event.error
+            ),
+            is_error: true,
+            until: now + 8.0,
+        });
+    }
+    toasts.retain(|toast| toast.until > now);
+    if toasts.is_empty() {
+        return;
+    }
+
+    egui::Area::new(egui::Id::new("export_toasts"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+        .show(contexts.ctx_mut(), |ui| {
+            for toast in toasts.iter() {
+                let color = if toast.is_error {
+                    egui::Color32::from_rgb(255, 120, 120)
+                } else {
+                    egui::Color32::from_rgb(120, 220, 120)
+                };
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.colored_label(color, &toast.message);
+                });
+            }
+        });
+}
+
+/// 加载场景后同步界面状态所需的查询：圆形、直线、函数图形、动画、坐标轴和网格
+type LoadedSceneQueries<'w, 's> = (
+    Query<'w, 's, (Entity, Option<&'static ChildOf>), (With<MathCircle>, Without<Updater>)>,
+    Query<'w, 's, (Entity, Option<&'static ChildOf>), (With<Line>, Without<Updater>)>,
+    Query<
+        'w,
+        's,
+        (Entity, Option<&'static ChildOf>),
+        Or<(With<FunctionGraph>, With<ParametricCurve>)>,
+    >,
+    Query<'w, 's, (Entity, Option<&'static ChildOf>), With<MathAnimation>>,
+    Query<'w, 's, (&'static Visibility, Option<&'static ChildOf>), With<Axes>>,
+    Query<'w, 's, (&'static Visibility, Option<&'static ChildOf>), With<Grid>>,
+);
+
+/// 加载或切换场景后按活动场景的实体重建控制面板的状态
+fn sync_loaded_scene(
+    mut loaded: EventReader<SceneLoaded>,
+    (mut circle_state, mut line_state, mut function_state, mut animation_state, _): UiPanelStates,
+    mut coordinate_state: ResMut<CoordinateSystemState>,
+    (circles, lines, graphs, animations, axes, grids): LoadedSceneQueries,
+    scenes: Query<(Entity, &MathScene)>,
+) {
+    if loaded.read().count() == 0 {
+        return;
+    }
+    let scene = active_scene(&scenes);
+    let in_active =
+        |(item, child_of): (_, Option<&ChildOf>)| in_scene(child_of, scene, true).then_some(item);
+
+    circle_state.circles = circles.iter().filter_map(in_active).collect();
+    line_state.lines = lines.iter().filter_map(in_active).collect();
+    function_state.graphs = graphs.iter().filter_map(in_active).collect();
+    // 追踪器不会被保存；时间轴已由场景文件重建，每个动画单独作为一步以便清除或重新编排
+    function_state.trackers.clear();
+    let mut steps: Vec<Entity> = animations.iter().filter_map(in_active).collect();
+    steps.sort();
+    animation_state.steps = steps.into_iter().map(|entity| vec![entity]).collect();
+
+    coordinate_state.show_axes = axes.iter().any(|(visibility, child_of)| {
+        in_scene(child_of, scene, true) && *visibility != Visibility::Hidden
+    });
+    coordinate_state.show_grid = grids.iter().any(|(visibility, child_of)| {
+        in_scene(child_of, scene, true) && *visibility != Visibility::Hidden
+    });
+}
+
 /// 更新性能监控数据
 fn update_performance_monitor(_time: Res<Time>, mut performance_state: ResMut<PerformanceState>) {
     performance_state.frame_count += 1;
@@ -414,9 +916,10 @@ fn update_performance_monitor(_time: Res<Time>, mut performance_state: ResMut<Pe
 fn handle_performance_toggle(
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut performance_state: ResMut<PerformanceState>,
+    mut contexts: EguiContexts,
 ) {
-    // 切换性能监控显示 (P键)
-    if keyboard_input.just_pressed(KeyCode::KeyP) {
+    // 切换性能监控显示 (P键)，输入框获得焦点时不响应
+    if keyboard_input.just_pressed(KeyCode::KeyP) && !contexts.ctx_mut().wants_keyboard_input() {
         performance_state.show_performance = !performance_state.show_performance;
         info!(
             "性能监控显示状态: {}",
@@ -545,19 +1048,97 @@ fn setup_fonts(mut contexts: EguiContexts) {
     ctx.set_fonts(fonts);
 }
 
+/// 控制面板各分区的状态资源
+type UiPanelStates<'w> = (
+    ResMut<'w, CircleState>,
+    ResMut<'w, LineState>,
+    ResMut<'w, FunctionState>,
+    ResMut<'w, AnimationPanelState>,
+    ResMut<'w, Timeline>,
+);
+
+/// 场景文件的路径、读写状态和请求
+type UiSceneFile<'w> = (
+    ResMut<'w, SceneFileState>,
+    EventWriter<'w, SaveSceneRequest>,
+    EventWriter<'w, LoadSceneRequest>,
+    EventWriter<'w, SceneAction>,
+);
+
+/// 导出请求、动画导出设置和动画导出进度（最近一次进度事件，结束后清空）
+type UiExport<'w, 's> = (
+    EventWriter<'w, ExportRequest>,
+    ResMut<'w, ExportSettings>,
+    ResMut<'w, AnimationExportSettings>,
+    EventReader<'w, 's, ExportProgress>,
+    EventReader<'w, 's, FrameCaptureFinished>,
+    Local<'s, Option<ExportProgress>>,
+);
+
+/// 控制面板直接编辑的数学对象：函数图形、参数曲线、动画以及可添加动画的对象
+type UiObjectQueries<'w, 's> = (
+    Query<'w, 's, &'static mut FunctionGraph>,
+    Query<'w, 's, &'static mut ParametricCurve>,
+    Query<'w, 's, &'static mut MathAnimation>,
+    Query<
+        'w,
+        's,
+        (Entity, Option<&'static ChildOf>),
+        (With<MathObject>, Without<Axes>, Without<Grid>),
+    >,
+);
+
+/// 绘制参数滑块，返回本帧被拖动的参数名和新值
+fn parameter_sliders(ui: &mut egui::Ui, parameters: &[GraphParameter]) -> Option<(String, f32)> {
+    let mut changed = None;
+    for parameter in parameters {
+        let mut value = parameter.value;
+        let response = ui.add(
+            egui::Slider::new(&mut value, parameter.min..=parameter.max).text(&parameter.name),
+        );
+        if response.changed() {
+            changed = Some((parameter.name.clone(), value));
+        }
+    }
+    changed
+}
+
 fn ui_system(
     mut commands: Commands,
     mut contexts: EguiContexts,
     ui_visibility: Res<UiVisibility>,
-    camera_state: Res<CameraState>,
+    mut camera_state: ResMut<CameraState>,
+    coordinate_system: Res<CoordinateSystem>,
     mut coordinate_state: ResMut<CoordinateSystemState>,
-    mut circle_state: ResMut<CircleState>,
-    mut axes_query: Query<&mut Visibility, (With<Axes>, Without<Grid>)>,
-    mut grid_query: Query<&mut Visibility, (With<Grid>, Without<Axes>)>,
-    mut export_events: EventWriter<ExportRequest>,
+    (mut circle_state, mut line_state, mut function_state, mut animation_state, mut timeline): UiPanelStates,
+    mut scene_query: Query<(Entity, &mut MathScene)>,
+    (mut axes_query, mut grid_query): CoordinateVisibility,
+    (
+        mut export_events,
+        mut export_settings,
+        mut animation_export,
+        mut progress_events,
+        mut finished_events,
+        mut export_progress,
+    ): UiExport,
+    mut zoom_to_fit_events: EventWriter<ZoomToFitRequest>,
     mut performance_state: ResMut<PerformanceState>,
     windows: Query<&Window>,
+    (mut graph_query, mut curve_query, mut animation_query, animatable_query): UiObjectQueries,
+    (mut scene_file, mut save_scene_events, mut load_scene_events, mut scene_actions): UiSceneFile,
 ) {
+    if let Some(progress) = progress_events.read().last() {
+        *export_progress = Some(progress.clone());
+    }
+    if finished_events.read().count() > 0 {
+        *export_progress = None;
+    }
+
+    let active_scene = scene_query
+        .iter()
+        .find(|(_, scene)| scene.active)
+        .map_or(Entity::PLACEHOLDER, |(entity, _)| entity);
+
     // 获取窗口宽度用于右对齐计算
     let window_width = if let Ok(window) = windows.single() {
         window.resolution.width()
@@ -576,6 +1157,7 @@ fn ui_system(
             ui.label("⌨️ F1 显示/隐藏UI");
             ui.label(format!("🔍 缩放: {:.1}x", camera_state.zoom));
             ui.label("🖱️ 滚轮缩放");
+            ui.label("🖱️ 中键/右键拖拽平移");
             ui.label("⌨️ P 性能信息");
         });
 
@@ -654,8 +1236,11 @@ fn ui_system(
                     ui.label("坐标轴设置");
                     if ui.button("显示/隐藏坐标轴").clicked() {
                         coordinate_state.show_axes = !coordinate_state.show_axes;
-                        // 更新所有坐标轴的可见性
-                        for mut visibility in axes_query.iter_mut() {
+                        // 更新活动场景中坐标轴的可见性
+                        for (mut visibility, _) in axes_query
+                            .iter_mut()
+                            .filter(|(_, child_of)| in_scene(*child_of, active_scene, true))
+                        {
                             *visibility = if coordinate_state.show_axes {
                                 Visibility::Inherited
                             } else {
@@ -673,8 +1258,11 @@ fn ui_system(
                     }
                     if ui.button("显示/隐藏网格").clicked() {
                         coordinate_state.show_grid = !coordinate_state.show_grid;
-                        // 更新所有网格的可见性
-                        for mut visibility in grid_query.iter_mut() {
+                        // 更新活动场景中网格的可见性
+                        for (mut visibility, _) in grid_query
+                            .iter_mut()
+                            .filter(|(_, child_of)| in_scene(*child_of, active_scene, true))
+                        {
                             *visibility = if coordinate_state.show_grid {
                                 Visibility::Inherited
                             } else {
@@ -691,21 +1279,43 @@ fn ui_system(
                         );
                     }
                     if ui.button("重置坐标轴").clicked() {
-                        // TODO: 重置坐标轴到默认状态
+                        // 恢复默认缩放并回到原点
+                        camera_state.target_zoom = 1.0;
+                        camera_state.target_translation = Vec2::ZERO;
+                        camera_state.zoom_anchor = None;
+                        info!("坐标轴已重置");
+                    }
+                    if ui.button("🔍 适应所有对象").clicked() {
+                        zoom_to_fit_events.write(ZoomToFitRequest);
                     }
                     ui.separator();
                     ui.label("缩放控制");
                     ui.label(format!("当前缩放: {:.2}x", camera_state.zoom));
                     ui.label(format!("目标缩放: {:.2}x", camera_state.target_zoom));
                     ui.label("使用鼠标滚轮进行缩放");
+                    ui.label("中键/右键拖拽或方向键平移");
+                    ui.label(format!(
+                        "视图中心: ({:.2}, {:.2})",
+                        camera_state.translation.x, camera_state.translation.y
+                    ));
+                    for (_, mut scene) in scene_query.iter_mut().filter(|(_, scene)| scene.active) {
+                        ui.horizontal(|ui| {
+                            ui.label("单位长度:");
+                            ui.add(
+                                egui::DragValue::new(&mut scene.unit_size)
+                                    .speed(1.0)
+                                    .range(10.0..=200.0)
+                                    .suffix(" px"),
+                            );
+                        });
+                    }
                     ui.separator();
 
-                    // 计算当前视图范围
-                    let half_width = 10.0 / camera_state.zoom; // 基于初始范围20计算
-                    let half_height = 8.0 / camera_state.zoom; // 基于初始范围16计算
+                    // 当前视图范围（与屏幕上显示的区域一致）
+                    let visible = coordinate_system.visible_rect();
                     ui.label("坐标轴范围");
-                    ui.label(format!("X: {:.1} 到 {:.1}", -half_width, half_width));
-                    ui.label(format!("Y: {:.1} 到 {:.1}", -half_height, half_height));
+                    ui.label(format!("X: {:.1} 到 {:.1}", visible.min.x, visible.max.x));
+                    ui.label(format!("Y: {:.1} 到 {:.1}", visible.min.y, visible.max.y));
 
                     ui.separator();
                     ui.label("显示状态");
@@ -856,61 +1466,765 @@ fn ui_system(
                     ui.separator();
                     ui.label(format!("当前圆形数量: {}", circle_state.circles.len()));
 
+                    ui.separator();
                     if ui.button("添加直线").clicked() {
-                        // TODO: 添加直线对象
+                        line_state.show_panel = !line_state.show_panel;
+                    }
+                    if line_state.show_panel {
+                        ui.label("📏 直线控制 (数学坐标系)");
+                        ui.horizontal(|ui| {
+                            ui.label("起点:");
+                            ui.add(
+                                egui::DragValue::new(&mut line_state.start.x)
+                                    .speed(0.1)
+                                    .range(-10.0..=10.0)
+                                    .prefix("x "),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut line_state.start.y)
+                                    .speed(0.1)
+                                    .range(-10.0..=10.0)
+                                    .prefix("y "),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("终点:");
+                            ui.add(
+                                egui::DragValue::new(&mut line_state.end.x)
+                                    .speed(0.1)
+                                    .range(-10.0..=10.0)
+                                    .prefix("x "),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut line_state.end.y)
+                                    .speed(0.1)
+                                    .range(-10.0..=10.0)
+                                    .prefix("y "),
+                            );
+                        });
+
+                        // 颜色选择
+                        let mut line_color = [
+                            line_state.color.to_srgba().red,
+                            line_state.color.to_srgba().green,
+                            line_state.color.to_srgba().blue,
+                        ];
+                        ui.horizontal(|ui| {
+                            ui.label("颜色:");
+                            ui.color_edit_button_rgb(&mut line_color);
+                        });
+                        line_state.color = Color::srgb(line_color[0], line_color[1], line_color[2]);
+
+                        // 线宽控制
+                        ui.horizontal(|ui| {
+                            ui.label("线宽:");
+                            ui.add(
+                                egui::DragValue::new(&mut line_state.stroke_width)
+                                    .speed(0.5)
+                                    .range(1.0..=12.0)
+                                    .suffix(" px"),
+                            );
+                        });
+
+                        ui.horizontal(|ui| {
+                            if ui.button("📏 创建直线").clicked() {
+                                let style = MathStyle {
+                                    stroke_color: line_state.color,
+                                    fill_color: None,
+                                    stroke_width: line_state.stroke_width,
+                                    opacity: 1.0,
+                                };
+                                let line_entity = create_line(
+                                    &mut commands,
+                                    line_state.start,
+                                    line_state.end,
+                                    style,
+                                );
+                                line_state.lines.push(line_entity);
+                                info!(
+                                    "添加直线: ({:.1}, {:.1}) -> ({:.1}, {:.1})",
+                                    line_state.start.x,
+                                    line_state.start.y,
+                                    line_state.end.x,
+                                    line_state.end.y
+                                );
+                            }
+                            if ui.button("🗑️ 清除所有直线").clicked() {
+                                for entity in &line_state.lines {
+                                    commands.entity(*entity).despawn();
+                                }
+                                line_state.lines.clear();
+                                info!("已清除所有直线");
+                            }
+                        });
+                        ui.label(format!("当前直线数量: {}", line_state.lines.len()));
                     }
+                    ui.separator();
                     if ui.button("添加函数图形").clicked() {
-                        // TODO: 添加函数图形
+                        function_state.show_panel = !function_state.show_panel;
+                    }
+                    if function_state.show_panel {
+                        ui.checkbox(&mut function_state.parametric, "参数方程");
+                        let submitted = if function_state.parametric {
+                            ui.label("📈 参数方程 x = x(t), y = y(t)");
+                            let (x_input, y_input) = &mut function_state.curve_input;
+                            let x_response = ui.horizontal(|ui| {
+                                ui.label("x(t) =");
+                                ui.text_edit_singleline(x_input)
+                            });
+                            let y_response = ui.horizontal(|ui| {
+                                ui.label("y(t) =");
+                                ui.text_edit_singleline(y_input)
+                            });
+                            (x_response.inner.lost_focus() || y_response.inner.lost_focus())
+                                && ui.input(|input| input.key_pressed(egui::Key::Enter))
+                        } else {
+                            ui.label("📈 函数表达式 y = f(x)");
+                            let response = ui.add(
+                                egui::TextEdit::singleline(&mut function_state.input)
+                                    .hint_text("例如 a*sin(b*x + c)"),
+                            );
+                            response.lost_focus()
+                                && ui.input(|input| input.key_pressed(egui::Key::Enter))
+                        };
+                        ui.small("支持 + - * / ^、pi、e 以及 sin cos tan exp ln sqrt abs 等函数");
+                        ui.small("自变量以外的变量（如 a、b、c）会生成参数滑块");
+
+                        ui.horizontal(|ui| {
+                            ui.label(if function_state.parametric {
+                                "t 范围:"
+                            } else {
+                                "定义域:"
+                            });
+                            let (start, end) = &mut function_state.domain;
+                            ui.add(egui::DragValue::new(start).speed(0.1).range(-100.0..=100.0));
+                            ui.label("到");
+                            ui.add(egui::DragValue::new(end).speed(0.1).range(-100.0..=100.0));
+                        });
+
+                        // 颜色选择
+                        let mut graph_color = [
+                            function_state.color.to_srgba().red,
+                            function_state.color.to_srgba().green,
+                            function_state.color.to_srgba().blue,
+                        ];
+                        ui.horizontal(|ui| {
+                            ui.label("颜色:");
+                            ui.color_edit_button_rgb(&mut graph_color);
+                        });
+                        function_state.color =
+                            Color::srgb(graph_color[0], graph_color[1], graph_color[2]);
+
+                        ui.horizontal(|ui| {
+                            if ui.button("📈 绘制").clicked() || submitted {
+                                let style = MathStyle {
+                                    stroke_color: function_state.color,
+                                    fill_color: None,
+                                    stroke_width: 2.0,
+                                    opacity: 1.0,
+                                };
+                                let (start, end) = function_state.domain;
+                                let range = (start.min(end), start.max(end));
+                                let result = if function_state.parametric {
+                                    let (x_input, y_input) = &function_state.curve_input;
+                                    create_parametric_curve_from_expressions(
+                                        &mut commands,
+                                        x_input,
+                                        y_input,
+                                        range,
+                                        style,
+                                    )
+                                } else {
+                                    create_function_graph_from_expression(
+                                        &mut commands,
+                                        &function_state.input,
+                                        range,
+                                        style,
+                                    )
+                                };
+                                match result {
+                                    Ok(graph_entity) => {
+                                        function_state.graphs.push(graph_entity);
+                                        function_state.error = None;
+                                        info!("添加函数图形: {}", function_state.input);
+                                    }
+                                    Err(error) => {
+                                        warn!("函数表达式解析失败: {}", error);
+                                        function_state.error = Some(error.to_string());
+                                    }
+                                }
+                            }
+                            if ui.button("🗑️ 清除所有函数").clicked() {
+                                let function_state = &mut *function_state;
+                                for entity in function_state
+                                    .graphs
+                                    .drain(..)
+                                    .chain(function_state.trackers.drain(..))
+                                {
+                                    commands.entity(entity).despawn();
+                                }
+                                info!("已清除所有函数图形");
+                            }
+                        });
+
+                        if let Some(error) = &function_state.error {
+                            ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+                        }
+                        ui.label(format!("当前函数数量: {}", function_state.graphs.len()));
+
+                        // 每个图形的参数滑块，拖动时实时重新采样
+                        let mut tracked_graph = None;
+                        for entity in &function_state.graphs {
+                            if let Ok(mut graph) = graph_query.get_mut(*entity) {
+                                let source = graph
+                                    .function
+                                    .as_ref()
+                                    .and_then(|function| function.source())
+                                    .unwrap_or("f(x)");
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("y = {}", source));
+                                    if ui
+                                        .small_button("📍 切线追踪")
+                                        .on_hover_text("添加沿图形移动的点、切线和数值标签")
+                                        .clicked()
+                                    {
+                                        tracked_graph =
+                                            Some((*entity, graph.domain_start, graph.domain_end));
+                                    }
+                                });
+                                if let Some((name, value)) =
+                                    parameter_sliders(ui, &graph.parameters)
+                                {
+                                    graph.set_parameter(&name, value);
+                                }
+                            } else if let Ok(mut curve) = curve_query.get_mut(*entity) {
+                                let x_source = curve
+                                    .x_function
+                                    .as_ref()
+                                    .and_then(|function| function.source())
+                                    .unwrap_or("x(t)");
+                                let y_source = curve
+                                    .y_function
+                                    .as_ref()
+                                    .and_then(|function| function.source())
+                                    .unwrap_or("y(t)");
+                                ui.label(format!("(x, y) = ({}, {})", x_source, y_source));
+                                if let Some((name, value)) =
+                                    parameter_sliders(ui, &curve.parameters)
+                                {
+                                    curve.set_parameter(&name, value);
+                                }
+                            }
+                        }
+
+                        // 追踪器的值由数值动画从定义域起点变化到终点，作为一个动画步骤加入
+                        if let Some((graph, start, end)) = tracked_graph {
+                            let style = MathStyle {
+                                stroke_color: Color::srgb(1.0, 0.8, 0.2),
+                                fill_color: None,
+                                stroke_width: 2.0,
+                                opacity: 1.0,
+                            };
+                            let (tracker, dependents) =
+                                create_graph_tracker(&mut commands, graph, start, style);
+                            let animation = create_animation(
+                                &mut commands,
+                                tracker,
+                                MathAnimation {
+                                    rate_function: animation_state.rate_function,
+                                    ..MathAnimation::new(
+                                        AnimationType::SetValue { to: end },
+                                        animation_state.duration,
+                                    )
+                                },
+                            );
+                            animation_state.steps.push(vec![animation]);
+                            function_state.trackers.push(tracker);
+                            function_state.trackers.extend(dependents);
+                            info!("添加切线追踪: x 从 {} 到 {}", start, end);
+                        }
                     }
                 });
 
                 ui.collapsing("动画控制", |ui| {
-                    if ui.button("播放动画").clicked() {
-                        // TODO: 播放动画
+                    egui::ComboBox::from_label("动画类型")
+                        .selected_text(animation_state.animation_type.label())
+                        .show_ui(ui, |ui| {
+                            for preset in animation_presets() {
+                                let label = preset.label();
+                                ui.selectable_value(
+                                    &mut animation_state.animation_type,
+                                    preset,
+                                    label,
+                                );
+                            }
+                        });
+                    egui::ComboBox::from_label("速率函数")
+                        .selected_text(animation_state.rate_function.label())
+                        .show_ui(ui, |ui| {
+                            for preset in RateFunction::PRESETS {
+                                ui.selectable_value(
+                                    &mut animation_state.rate_function,
+                                    preset,
+                                    preset.label(),
+                                );
+                            }
+                        });
+                    if let RateFunction::CubicBezier { x1, y1, x2, y2 } =
+                        &mut animation_state.rate_function
+                    {
+                        ui.horizontal(|ui| {
+                            ui.label("控制点:");
+                            ui.add(egui::DragValue::new(x1).speed(0.01).range(0.0..=1.0));
+                            ui.add(egui::DragValue::new(y1).speed(0.01));
+                            ui.add(egui::DragValue::new(x2).speed(0.01).range(0.0..=1.0));
+                            ui.add(egui::DragValue::new(y2).speed(0.01));
+                        });
                     }
-                    if ui.button("暂停动画").clicked() {
-                        // TODO: 暂停动画
+                    ui.horizontal(|ui| {
+                        ui.label("时长:");
+                        ui.add(
+                            egui::DragValue::new(&mut animation_state.duration)
+                                .speed(0.1)
+                                .range(0.1..=20.0)
+                                .suffix(" 秒"),
+                        );
+                        ui.checkbox(&mut animation_state.loop_animation, "循环");
+                    });
+
+                    if ui.button("🎬 为所有对象添加动画").clicked() {
+                        // 每次添加的动画作为时间轴中的一步
+                        let step: Vec<Entity> = animatable_query
+                            .iter()
+                            .filter(|(_, child_of)| in_scene(*child_of, active_scene, true))
+                            .map(|(target, _)| {
+                                create_animation(
+                                    &mut commands,
+                                    target,
+                                    MathAnimation {
+                                        rate_function: animation_state.rate_function,
+                                        loop_animation: animation_state.loop_animation,
+                                        ..MathAnimation::new(
+                                            animation_state.animation_type.clone(),
+                                            animation_state.duration,
+                                        )
+                                    },
+                                )
+                            })
+                            .collect();
+                        info!(
+                            "为 {} 个对象添加{}动画",
+                            step.len(),
+                            animation_state.animation_type.label()
+                        );
+                        if !step.is_empty() {
+                            animation_state.steps.push(step);
+                        }
                     }
+
+                    ui.horizontal(|ui| {
+                        // 时间轴不为空时控制时间轴，否则控制独立播放的动画
+                        if ui.button("播放动画").clicked() {
+                            if timeline.is_empty() {
+                                for mut animation in animation_query.iter_mut() {
+                                    animation.play();
+                                }
+                            } else if !timeline.is_playing {
+                                timeline.toggle_playing();
+                            }
+                        }
+                        if ui.button("暂停动画").clicked() {
+                            if timeline.is_empty() {
+                                for mut animation in animation_query.iter_mut() {
+                                    animation.pause();
+                                }
+                            } else {
+                                timeline.is_playing = false;
+                            }
+                        }
+                        if ui.button("🗑️ 清除所有动画").clicked() {
+                            for entity in animation_state.steps.drain(..).flatten() {
+                                commands.entity(entity).despawn();
+                            }
+                            timeline.clear();
+                            info!("已清除所有动画");
+                        }
+                    });
+                    let playing = animation_query
+                        .iter()
+                        .filter(|animation| animation.is_playing)
+                        .count();
+                    ui.label(format!(
+                        "动画: {} 个，播放中 {} 个",
+                        animation_query.iter().count(),
+                        playing
+                    ));
                     ui.separator();
                     ui.label("时间轴控制");
-                    // TODO: 添加时间轴滑块
+                    ui.label(format!(
+                        "步骤: {} 个，时间轴中动画: {} 个",
+                        animation_state.steps.len(),
+                        timeline.entries().len()
+                    ));
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut animation_state.lagged, "错开开始");
+                        ui.add_enabled(
+                            animation_state.lagged,
+                            egui::DragValue::new(&mut animation_state.lag)
+                                .speed(0.05)
+                                .range(0.0..=5.0)
+                                .suffix(" 秒"),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("步骤间等待:");
+                        ui.add(
+                            egui::DragValue::new(&mut animation_state.wait)
+                                .speed(0.05)
+                                .range(0.0..=10.0)
+                                .suffix(" 秒"),
+                        );
+                    });
+                    if ui.button("🧩 按步骤生成时间轴").clicked() {
+                        // 步骤依次播放，步骤内的动画同时或错开开始，步骤之间插入等待
+                        let mut blocks = Vec::new();
+                        for (i, step) in animation_state.steps.iter().enumerate() {
+                            if i > 0 && animation_state.wait > 0.0 {
+                                blocks.push(TimelineBlock::Wait(animation_state.wait));
+                            }
+                            let plays = step
+                                .iter()
+                                .map(|entity| TimelineBlock::Play(*entity))
+                                .collect();
+                            blocks.push(if animation_state.lagged {
+                                TimelineBlock::LaggedStart {
+                                    lag: animation_state.lag,
+                                    blocks: plays,
+                                }
+                            } else {
+                                TimelineBlock::Group(plays)
+                            });
+                        }
+                        timeline.clear();
+                        timeline.push(TimelineBlock::Sequence(blocks));
+                        info!("已生成时间轴: {} 个步骤", animation_state.steps.len());
+                    }
+
+                    // 可拖动的播放头
+                    let duration = timeline.duration();
+                    let mut playhead = timeline.playhead;
+                    let response = ui.add_enabled(
+                        duration > 0.0,
+                        egui::Slider::new(&mut playhead, 0.0..=duration.max(0.01))
+                            .suffix(" 秒")
+                            .text(format!("/ {:.1} 秒", duration)),
+                    );
+                    if response.changed() {
+                        timeline.seek(playhead);
+                    }
+                    ui.add_enabled_ui(duration > 0.0, |ui| {
+                        ui.horizontal(|ui| {
+                            let speed = timeline.speed.abs();
+                            if ui.button("⏮").on_hover_text("回到开头").clicked() {
+                                timeline.seek(0.0);
+                            }
+                            if ui.button("⏪ 倒放").clicked() {
+                                timeline.play_with_speed(-speed);
+                            }
+                            if ui.button("▶ 正放").clicked() {
+                                timeline.play_with_speed(speed);
+                            }
+                            if ui.button("⏭").on_hover_text("跳到结尾").clicked() {
+                                timeline.seek(duration);
+                            }
+                            let mut magnitude = speed;
+                            if ui
+                                .add(
+                                    egui::DragValue::new(&mut magnitude)
+                                        .speed(0.05)
+                                        .range(0.1..=4.0)
+                                        .prefix("×"),
+                                )
+                                .changed()
+                            {
+                                timeline.speed = magnitude.copysign(timeline.speed);
+                            }
+                        });
+                    });
+                    ui.label(match (timeline.is_playing, timeline.speed < 0.0) {
+                        (true, false) => "▶ 时间轴播放中（空格键暂停）",
+                        (true, true) => "⏪ 时间轴倒放中（空格键暂停）",
+                        (false, _) => "⏸ 时间轴已暂停（空格键播放）",
+                    });
                 });
 
                 ui.collapsing("场景设置", |ui| {
-                    if ui.button("新建场景").clicked() {
-                        // TODO: 新建场景
+                    let scene_count = scene_query.iter().count();
+                    for (entity, mut scene) in scene_query.iter_mut() {
+                        ui.horizontal(|ui| {
+                            let label = if scene.active { "●" } else { "○" };
+                            if ui
+                                .selectable_label(scene.active, label)
+                                .on_hover_text("切换到此场景")
+                                .clicked()
+                            {
+                                scene_actions.write(SceneAction::Activate(entity));
+                            }
+                            ui.add(
+                                egui::TextEdit::singleline(&mut scene.name).desired_width(120.0),
+                            );
+                            if ui.button("📄").on_hover_text("复制场景").clicked() {
+                                scene_actions.write(SceneAction::Duplicate(entity));
+                            }
+                            if ui
+                                .add_enabled(scene_count > 1, egui::Button::new("🗑"))
+                                .on_hover_text("删除场景")
+                                .clicked()
+                            {
+                                scene_actions.write(SceneAction::Delete(entity));
+                            }
+                        });
+                    }
+                    if let Ok((_, mut scene)) = scene_query.get_mut(active_scene) {
+                        let mut color = scene.background_color.to_srgba().to_f32_array_no_alpha();
+                        ui.horizontal(|ui| {
+                            ui.label("背景颜色:");
+                            if ui.color_edit_button_rgb(&mut color).changed() {
+                                scene.background_color = Color::srgb_from_array(color);
+                            }
+                        });
                     }
-                    if ui.button("保存场景").clicked() {
-                        // TODO: 保存场景
+                    if ui.button("➕ 新建场景").clicked() {
+                        scene_actions.write(SceneAction::Create {
+                            name: format!("场景 {}", scene_count + 1),
+                        });
                     }
-                    if ui.button("加载场景").clicked() {
-                        // TODO: 加载场景
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("文件:");
+                        ui.text_edit_singleline(&mut scene_file.path);
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("💾 保存场景").on_hover_text("Ctrl+S").clicked() {
+                            save_scene_events
+                                .write(SaveSceneRequest(scene_file.path.clone().into()));
+                        }
+                        if ui.button("📂 加载场景").on_hover_text("Ctrl+O").clicked() {
+                            load_scene_events
+                                .write(LoadSceneRequest::open(scene_file.path.clone()));
+                        }
+                    });
+                    ui.checkbox(&mut scene_file.hot_reload, "🔄 文件修改后自动重新加载");
+                    match &scene_file.last_result {
+                        Some(Ok(message)) => {
+                            ui.colored_label(egui::Color32::GREEN, format!("✅ {}", message));
+                        }
+                        Some(Err(error)) => {
+                            ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+                        }
+                        None => {}
                     }
                 });
 
                 ui.collapsing("导出选项", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("输出目录:");
+                        ui.text_edit_singleline(&mut export_settings.directory);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("文件名:");
+                        ui.text_edit_singleline(&mut export_settings.filename_template)
+                            .on_hover_text(format!(
+                                "可用占位符: {}\n扩展名按导出格式自动添加",
+                                export::FILENAME_TOKENS.join(" ")
+                            ));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("重名时:");
+                        ui.radio_value(
+                            &mut export_settings.collision,
+                            CollisionPolicy::Increment,
+                            "自动编号",
+                        );
+                        ui.radio_value(
+                            &mut export_settings.collision,
+                            CollisionPolicy::Overwrite,
+                            "覆盖",
+                        );
+                    });
+                    let scene_name = scene_query
+                        .iter()
+                        .find(|(_, scene)| scene.active)
+                        .map_or("scene", |(_, scene)| scene.name.as_str());
+                    let frame = animation_export.frame_at(timeline.playhead);
+                    ui.weak(format!(
+                        "预览: {}",
+                        std::path::Path::new(&export_settings.directory)
+                            .join(export_settings.filename(scene_name, frame, "png"))
+                            .display()
+                    ));
+                    ui.horizontal(|ui| {
+                        ui.label("截图尺寸:");
+                        let (width, height) = &mut export_settings.resolution;
+                        ui.add(egui::DragValue::new(width).speed(10.0).range(16..=7680));
+                        ui.label("x");
+                        ui.add(egui::DragValue::new(height).speed(10.0).range(16..=4320));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("超采样:");
+                        for factor in [1, 2, 4] {
+                            ui.selectable_value(
+                                &mut export_settings.options.supersample,
+                                factor,
+                                format!("{}×", factor),
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text("按倍数放大渲染后缩小，线条和文字边缘更平滑");
+                    ui.checkbox(
+                        &mut export_settings.options.transparent_background,
+                        "透明背景（仅 PNG）",
+                    );
                     if ui.button("📸 保存截图").clicked() {
                         export_events.write(ExportRequest {
                             format: ExportFormat::PNG,
-                            filename: format!(
-                                "rim_screenshot_{}.png",
-                                std::time::SystemTime::now()
-                                    .duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap()
-                                    .as_secs()
-                            ),
-                            resolution: (1920, 1080),
+                            filename: None,
+                            resolution: export_settings.resolution,
+                            options: export_settings.options,
                         });
                         info!("截图请求已发送");
                     }
-                    if ui.button("导出动画").clicked() {
-                        // TODO: 导出动画
+                    if ui.button("📐 导出 SVG").clicked() {
+                        export_events.write(ExportRequest {
+                            format: ExportFormat::SVG,
+                            filename: None,
+                            resolution: export_settings.resolution,
+                            options: export_settings.options,
+                        });
+                        info!("SVG 导出请求已发送");
                     }
+
+                    ui.separator();
+                    ui.label("🎞 动画导出");
+                    ui.horizontal(|ui| {
+                        ui.label("帧率:");
+                        // GIF 帧间隔以 1/100 秒计，超过 50 fps 会被播放器放慢
+                        ui.add(
+                            egui::DragValue::new(&mut animation_export.fps)
+                                .speed(1.0)
+                                .range(1..=60)
+                                .suffix(" fps"),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("尺寸:");
+                        let (width, height) = &mut animation_export.resolution;
+                        ui.add(egui::DragValue::new(width).speed(10.0).range(16..=3840));
+                        ui.label("x");
+                        ui.add(egui::DragValue::new(height).speed(10.0).range(16..=2160));
+                    });
+                    ui.horizontal(|ui| {
+                        let mut infinite = animation_export.repeat == GifRepeat::Infinite;
+                        ui.checkbox(&mut infinite, "无限循环");
+                        if infinite {
+                            animation_export.repeat = GifRepeat::Infinite;
+                        } else if animation_export.repeat == GifRepeat::Infinite {
+                            animation_export.repeat = GifRepeat::Times(1);
+                        }
+                        if let GifRepeat::Times(times) = &mut animation_export.repeat {
+                            ui.add(
+                                egui::DragValue::new(times)
+                                    .speed(1.0)
+                                    .range(1..=100)
+                                    .suffix(" 次"),
+                            );
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        let previous = animation_export.codec;
+                        egui::ComboBox::from_label("视频编码")
+                            .selected_text(animation_export.codec.label())
+                            .show_ui(ui, |ui| {
+                                for codec in VideoCodec::ALL {
+                                    ui.selectable_value(
+                                        &mut animation_export.codec,
+                                        codec,
+                                        codec.label(),
+                                    );
+                                }
+                            });
+                        // 两种编码的 CRF 取值范围不同，切换时换成对应的默认值
+                        if animation_export.codec != previous {
+                            animation_export.crf = animation_export.codec.default_crf();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("CRF:");
+                        ui.add(egui::DragValue::new(&mut animation_export.crf).range(0..=63))
+                            .on_hover_text("越小画质越高、文件越大");
+                    });
+                    ui.horizontal(|ui| {
+                        let mut all_frames = animation_export.frame_range.is_none();
+                        ui.checkbox(&mut all_frames, "全部帧");
+                        if all_frames {
+                            animation_export.frame_range = None;
+                        } else if animation_export.frame_range.is_none() {
+                            let last =
+                                export::frame_count(timeline.duration(), animation_export.fps);
+                            animation_export.frame_range = Some((1, last));
+                        }
+                        if let Some((start, end)) = &mut animation_export.frame_range {
+                            ui.add(egui::DragValue::new(start).speed(1.0).range(1..=*end));
+                            ui.label("-");
+                            ui.add(
+                                egui::DragValue::new(end)
+                                    .speed(1.0)
+                                    .range(*start..=u32::MAX),
+                            );
+                        }
+                    });
+                    ui.label(format!(
+                        "时间轴共 {} 帧",
+                        export::frame_count(timeline.duration(), animation_export.fps)
+                    ));
+                    if let Some(progress) = export_progress.as_ref() {
+                        ui.label(format!("正在导出 {}", progress.path.display()));
+                        let text = if progress.completed < progress.total {
+                            format!("截取 {}/{} 帧", progress.completed, progress.total)
+                        } else {
+                            "编码中…".to_string()
+                        };
+                        ui.add(egui::ProgressBar::new(progress.fraction()).text(text));
+                    } else {
+                        ui.horizontal(|ui| {
+                            if ui.button("🎞 导出 GIF").clicked() {
+                                export_events.write(ExportRequest {
+                                    format: ExportFormat::GIF,
+                                    filename: None,
+                                    resolution: animation_export.resolution,
+                                    options: export_settings.options,
+                                });
+                                info!("GIF 导出请求已发送");
+                            }
+                            if ui.button("🎬 导出动画").clicked() {
+                                export_events.write(ExportRequest {
+                                    format: ExportFormat::MP4,
+                                    filename: None,
+                                    resolution: animation_export.resolution,
+                                    options: export_settings.options,
+                                });
+                                info!("视频导出请求已发送");
+                            }
+                        });
+                    }
+
                     ui.separator();
                     ui.label("💡 截图说明");
-                    ui.label("• 截图将保存到 screenshots/ 目录");
-                    ui.label("• 支持PNG格式");
-                    ui.label("• 自动生成时间戳文件名");
+                    ui.label("• 支持PNG截图、SVG矢量图、GIF动画和MP4/WebM视频");
+                    ui.label("• 画面按设定尺寸离屏渲染，与窗口大小无关");
+                    ui.label("• 视频需要 ffmpeg，未安装时输出 PNG 序列和编码脚本");
+                    ui.label("• 设置会保存并在下次启动时恢复");
                 });
 
                 ui.collapsing("性能监控", |ui| {
@@ -1124,6 +2438,8 @@ fn ui_system(
                     }
                 ));
                 ui.label(format!("🔵 圆形: {} 个", circle_state.circles.len()));
+                ui.label(format!("📏 直线: {} 个", line_state.lines.len()));
+                ui.label(format!("📈 函数: {} 个", function_state.graphs.len()));
 
                 ui.separator();
                 ui.label("快捷键");
@@ -1133,6 +2449,9 @@ fn ui_system(
                 ui.label("S - 保存截图");
                 ui.label("P - 显示/隐藏性能信息");
                 ui.label("鼠标滚轮 - 缩放");
+                ui.label("方向键/中键/右键拖拽 - 平移");
+                ui.label("F - 适应所有对象");
+                ui.label("空格 - 播放/暂停动画");
             });
     }
 }
@@ -63,14 +63,12 @@ impl Axes {
         }
     }
 
-    /// 根据缩放级别更新坐标轴范围
-    pub fn update_for_zoom(&mut self, zoom: f32) {
-        let half_width = self.base_range.0 / (2.0 * zoom);
-        let half_height = self.base_range.1 / (2.0 * zoom);
-
-        self.x_range = (-half_width, half_width);
-        self.y_range = (-half_height, half_height);
-        self.tick_spacing = self.calculate_tick_spacing(zoom);
+    /// 根据当前可见范围更新坐标轴范围（范围相对坐标轴原点）
+    ///
+    /// 刻度间距由 `update_axes` 根据新的范围重新计算。
+    pub fn update_for_view(&mut self, x_range: (f32, f32), y_range: (f32, f32)) {
+        self.x_range = x_range;
+        self.y_range = y_range;
     }
 }
 
@@ -209,24 +207,50 @@ pub fn create_grid(commands: &mut Commands, spacing: f32, style: Style) -> Entit
         .id()
 }
 
+/// 创建默认的网格和坐标轴（x ∈ [-10, 10]，y ∈ [-8, 8]），返回 (网格, 坐标轴)
+pub fn create_default_coordinate_system(commands: &mut Commands) -> (Entity, Entity) {
+    let grid = create_grid(
+        commands,
+        1.0, // 网格间距
+        Style {
+            stroke_color: Color::srgba(0.3, 0.3, 0.3, 1.0),
+            fill_color: None,
+            stroke_width: 1.0,
+            opacity: 0.3,
+        },
+    );
+
+    let axes = create_axes_with_labels(
+        commands,
+        (-10.0, 10.0),   // x 范围
+        (-8.0, 8.0),     // y 范围
+        "x".to_string(), // x轴标签
+        "y".to_string(), // y轴标签
+        Style {
+            stroke_color: Color::WHITE,
+            fill_color: None,
+            stroke_width: 2.0,
+            opacity: 1.0,
+        },
+    );
+
+    (grid, axes)
+}
+
 /// 更新坐标轴的系统
 fn update_axes(mut query: Query<&mut Axes, Changed<Axes>>) {
     for mut axes in query.iter_mut() {
-        // 这里可以添加坐标轴更新逻辑
-        // 比如根据视图范围自动调整刻度间隔
+        // 根据可见范围相对基础范围的比例推算缩放级别，自动调整刻度间隔
         let x_span = axes.x_range.1 - axes.x_range.0;
-        let y_span = axes.y_range.1 - axes.y_range.0;
-
-        // 自动调整刻度间隔
-        let max_span = x_span.max(y_span);
-        axes.tick_spacing = if max_span > 50.0 {
-            10.0
-        } else if max_span > 20.0 {
-            5.0
-        } else if max_span > 10.0 {
-            2.0
-        } else {
-            1.0
-        };
+        if x_span <= 0.0 {
+            continue;
+        }
+
+        let zoom = axes.base_range.0 / x_span;
+        let tick_spacing = axes.calculate_tick_spacing(zoom);
+        // 只在间距变化时写入，避免每帧触发 Changed<Axes>
+        if (axes.tick_spacing - tick_spacing).abs() > f32::EPSILON {
+            axes.tick_spacing = tick_spacing;
+        }
     }
 }
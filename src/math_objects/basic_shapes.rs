@@ -39,6 +39,24 @@ pub struct MathCircle {
     pub resolution: Option<u32>,
 }
 
+impl MathCircle {
+    /// 以 center 为圆心时的包围盒（数学坐标）
+    pub fn bounds(&self, center: Vec2) -> Rect {
+        Rect::from_center_half_size(center, Vec2::splat(self.radius))
+    }
+
+    /// 以圆心为原点的闭合轮廓点
+    pub fn outline(&self, segments: u32) -> Vec<Vec2> {
+        let segments = segments.max(3);
+        (0..=segments)
+            .map(|i| {
+                let angle = std::f32::consts::TAU * i as f32 / segments as f32;
+                Vec2::from_angle(angle) * self.radius
+            })
+            .collect()
+    }
+}
+
 impl Default for MathCircle {
     fn default() -> Self {
         Self {
@@ -57,6 +75,27 @@ pub struct Line {
     pub end: Vec2,
 }
 
+impl Line {
+    /// 以 center 为中点的包围盒（数学坐标）
+    ///
+    /// 直线以 Position2D 为中点，平移动画后端点随之移动。
+    pub fn bounds(&self, center: Vec2) -> Rect {
+        let offset = center - self.midpoint();
+        Rect::from_corners(self.start + offset, self.end + offset)
+    }
+
+    /// 直线中点
+    pub fn midpoint(&self) -> Vec2 {
+        (self.start + self.end) * 0.5
+    }
+
+    /// 以中点为原点的轮廓点
+    pub fn outline(&self) -> Vec<Vec2> {
+        let midpoint = self.midpoint();
+        vec![self.start - midpoint, self.end - midpoint]
+    }
+}
+
 /// 矩形组件
 #[derive(Component, Reflect, Clone)]
 pub struct Rectangle {
@@ -64,6 +103,25 @@ pub struct Rectangle {
     pub height: f32,
 }
 
+impl Rectangle {
+    /// 以 center 为中心时的包围盒（数学坐标）
+    pub fn bounds(&self, center: Vec2) -> Rect {
+        Rect::from_center_size(center, Vec2::new(self.width, self.height))
+    }
+
+    /// 以中心为原点的闭合轮廓点（逆时针，从左下角开始）
+    pub fn outline(&self) -> Vec<Vec2> {
+        let half = Vec2::new(self.width, self.height) * 0.5;
+        vec![
+            Vec2::new(-half.x, -half.y),
+            Vec2::new(half.x, -half.y),
+            Vec2::new(half.x, half.y),
+            Vec2::new(-half.x, half.y),
+            Vec2::new(-half.x, -half.y),
+        ]
+    }
+}
+
 impl Default for Rectangle {
     fn default() -> Self {
         Self {
@@ -102,7 +160,7 @@ pub fn create_circle_with_resolution(
             Position2D::from(position),
             style,
             Transform::from_translation(position.extend(0.0)),
-            Visibility::Visible,
+            Visibility::Inherited,
         ))
         .id()
 }
@@ -120,7 +178,31 @@ pub fn create_line(commands: &mut Commands, start: Vec2, end: Vec2, style: Style
             Position2D::from((start + end) * 0.5),
             style,
             Transform::from_translation(((start + end) * 0.5).extend(0.0)),
-            Visibility::Visible,
+            Visibility::Inherited,
+        ))
+        .id()
+}
+
+/// 创建矩形的便利函数（以 position 为中心）
+pub fn create_rectangle(
+    commands: &mut Commands,
+    position: Vec2,
+    width: f32,
+    height: f32,
+    style: Style,
+) -> Entity {
+    commands
+        .spawn((
+            MathObject {
+                id: format!("rectangle_{}", rand::random::<u32>()),
+                visible: true,
+                layer: 0,
+            },
+            Rectangle { width, height },
+            Position2D::from(position),
+            style,
+            Transform::from_translation(position.extend(0.0)),
+            Visibility::Inherited,
         ))
         .id()
 }
@@ -0,0 +1,709 @@
+/*
+ * RIM - Mathematical Visualization Tool
+ * Copyright (C) 2024 m1911star
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 数学表达式解析与求值
+//!
+//! 支持的语法：
+//! - 数字：`2`、`3.14`、`1e-3`
+//! - 运算符：`+ - * / ^`（`^` 右结合），一元 `+ -`，以及隐式乘法（`2x`、`3(x+1)`、`a(x+1)`）
+//! - 常量：`pi`、`e`、`tau`
+//! - 函数：`sin cos tan asin acos atan sinh cosh tanh exp ln log sqrt abs floor ceil sign min max pow`；
+//!   其他名称后跟括号时视为变量或常量与括号的乘积
+
+use super::function_graph::functions;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// 表达式解析错误，column 为从 1 开始的字符列号
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ExpressionError {
+    #[error("表达式为空")]
+    Empty,
+    #[error("第 {column} 个字符处出现无法识别的字符 '{found}'")]
+    UnexpectedCharacter { found: char, column: usize },
+    #[error("第 {column} 个字符处的数字 '{text}' 无效")]
+    InvalidNumber { text: String, column: usize },
+    #[error("第 {column} 个字符处期望{expected}，实际为 '{found}'")]
+    UnexpectedToken {
+        expected: &'static str,
+        found: String,
+        column: usize,
+    },
+    #[error("表达式意外结束，期望{expected}")]
+    UnexpectedEnd { expected: &'static str },
+    #[error("函数 '{name}' 需要 {expected} 个参数，实际为 {found} 个")]
+    ArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("变量 '{name}' 未定义")]
+    UndefinedVariable { name: String },
+}
+
+impl ExpressionError {
+    /// 出错位置从 1 开始的字符列号，无法定位到字符时为 None
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            ExpressionError::UnexpectedCharacter { column, .. }
+            | ExpressionError::InvalidNumber { column, .. }
+            | ExpressionError::UnexpectedToken { column, .. } => Some(*column),
+            _ => None,
+        }
+    }
+}
+
+/// 已解析的数学表达式
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    source: String,
+    root: Node,
+    variables: Vec<String>,
+}
+
+impl Expression {
+    /// 解析表达式文本
+    pub fn parse(source: &str) -> Result<Self, ExpressionError> {
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Err(ExpressionError::Empty);
+        }
+
+        let mut parser = Parser { tokens, index: 0 };
+        let root = parser.parse_expression()?;
+        if let Some(token) = parser.peek() {
+            return Err(ExpressionError::UnexpectedToken {
+                expected: "运算符或表达式结尾",
+                found: token.kind.to_string(),
+                column: token.position + 1,
+            });
+        }
+
+        let mut variables = Vec::new();
+        root.collect_variables(&mut variables);
+
+        Ok(Self {
+            source: source.trim().to_string(),
+            root,
+            variables,
+        })
+    }
+
+    /// 原始表达式文本
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 表达式中出现的自由变量（按首次出现顺序）
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// 检查表达式只使用了允许的变量
+    pub fn check_variables(&self, allowed: &[&str]) -> Result<(), ExpressionError> {
+        match self
+            .variables
+            .iter()
+            .find(|name| !allowed.contains(&name.as_str()))
+        {
+            Some(name) => Err(ExpressionError::UndefinedVariable { name: name.clone() }),
+            None => Ok(()),
+        }
+    }
+
+    /// 求值，变量通过 lookup 查找；未定义的变量求值为 NaN
+    pub fn eval(&self, lookup: &impl Fn(&str) -> Option<f32>) -> f32 {
+        self.root.eval(lookup)
+    }
+
+    /// 以单个变量 `x` 求值
+    pub fn eval_x(&self, x: f32) -> f32 {
+        self.eval(&|name| (name == "x").then_some(x))
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ExpressionError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Self::parse(source)
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// 语法树节点
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f32),
+    Variable(String),
+    Negate(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+impl Node {
+    fn eval(&self, lookup: &impl Fn(&str) -> Option<f32>) -> f32 {
+        match self {
+            Node::Number(value) => *value,
+            Node::Variable(name) => lookup(name).unwrap_or(f32::NAN),
+            Node::Negate(inner) => -inner.eval(lookup),
+            Node::Binary(op, lhs, rhs) => {
+                let (a, b) = (lhs.eval(lookup), rhs.eval(lookup));
+                match op {
+                    BinaryOp::Add => a + b,
+                    BinaryOp::Sub => a - b,
+                    BinaryOp::Mul => a * b,
+                    BinaryOp::Div => a / b,
+                    BinaryOp::Pow => a.powf(b),
+                }
+            }
+            Node::Call(function, args) => {
+                let values: Vec<f32> = args.iter().map(|arg| arg.eval(lookup)).collect();
+                function.apply(&values)
+            }
+        }
+    }
+
+    fn collect_variables(&self, variables: &mut Vec<String>) {
+        match self {
+            Node::Number(_) => {}
+            Node::Variable(name) => {
+                if !variables.contains(name) {
+                    variables.push(name.clone());
+                }
+            }
+            Node::Negate(inner) => inner.collect_variables(variables),
+            Node::Binary(_, lhs, rhs) => {
+                lhs.collect_variables(variables);
+                rhs.collect_variables(variables);
+            }
+            Node::Call(_, args) => {
+                for arg in args {
+                    arg.collect_variables(variables);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+/// 内置函数
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Ln,
+    Log10,
+    Sqrt,
+    Abs,
+    Floor,
+    Ceil,
+    Sign,
+    Min,
+    Max,
+    Pow,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "asin" => Function::Asin,
+            "acos" => Function::Acos,
+            "atan" => Function::Atan,
+            "sinh" => Function::Sinh,
+            "cosh" => Function::Cosh,
+            "tanh" => Function::Tanh,
+            "exp" => Function::Exp,
+            "ln" => Function::Ln,
+            "log" => Function::Log10,
+            "sqrt" => Function::Sqrt,
+            "abs" => Function::Abs,
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            "sign" => Function::Sign,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "pow" => Function::Pow,
+            _ => return None,
+        })
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Function::Min | Function::Max | Function::Pow => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(self, args: &[f32]) -> f32 {
+        let x = args[0];
+        match self {
+            Function::Sin => functions::sin(x),
+            Function::Cos => functions::cos(x),
+            Function::Tan => x.tan(),
+            Function::Asin => x.asin(),
+            Function::Acos => x.acos(),
+            Function::Atan => x.atan(),
+            Function::Sinh => x.sinh(),
+            Function::Cosh => x.cosh(),
+            Function::Tanh => x.tanh(),
+            Function::Exp => functions::exp(x),
+            Function::Ln => functions::ln(x),
+            Function::Log10 => {
+                if x > 0.0 {
+                    x.log10()
+                } else {
+                    f32::NAN
+                }
+            }
+            Function::Sqrt => x.sqrt(),
+            Function::Abs => x.abs(),
+            Function::Floor => x.floor(),
+            Function::Ceil => x.ceil(),
+            Function::Sign => {
+                if x == 0.0 {
+                    0.0
+                } else {
+                    x.signum()
+                }
+            }
+            Function::Min => x.min(args[1]),
+            Function::Max => x.max(args[1]),
+            Function::Pow => x.powf(args[1]),
+        }
+    }
+}
+
+/// 词法单元
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(f32),
+    Identifier(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Number(value) => write!(f, "{}", value),
+            TokenKind::Identifier(name) => f.write_str(name),
+            TokenKind::Plus => f.write_str("+"),
+            TokenKind::Minus => f.write_str("-"),
+            TokenKind::Star => f.write_str("*"),
+            TokenKind::Slash => f.write_str("/"),
+            TokenKind::Caret => f.write_str("^"),
+            TokenKind::LeftParen => f.write_str("("),
+            TokenKind::RightParen => f.write_str(")"),
+            TokenKind::Comma => f.write_str(","),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+/// 将表达式文本切分为词法单元
+fn tokenize(source: &str) -> Result<Vec<Token>, ExpressionError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let position = i;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() || c == '.' {
+            // 数字：整数/小数部分，可带科学计数法指数
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[position..i].iter().collect();
+            let value = text
+                .parse::<f32>()
+                .map_err(|_| ExpressionError::InvalidNumber {
+                    text: text.clone(),
+                    column: position + 1,
+                })?;
+            tokens.push(Token {
+                kind: TokenKind::Number(value),
+                position,
+            });
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Identifier(chars[position..i].iter().collect()),
+                position,
+            });
+            continue;
+        }
+
+        let kind = match c {
+            '+' => TokenKind::Plus,
+            '-' => TokenKind::Minus,
+            '*' => TokenKind::Star,
+            '/' => TokenKind::Slash,
+            '^' => TokenKind::Caret,
+            '(' => TokenKind::LeftParen,
+            ')' => TokenKind::RightParen,
+            ',' => TokenKind::Comma,
+            _ => {
+                return Err(ExpressionError::UnexpectedCharacter {
+                    found: c,
+                    column: position + 1,
+                });
+            }
+        };
+        tokens.push(Token { kind, position });
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+/// 递归下降语法分析器
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek().is_some_and(|token| &token.kind == kind) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &'static str) -> Result<(), ExpressionError> {
+        match self.next() {
+            Some(token) if token.kind == kind => Ok(()),
+            Some(token) => Err(ExpressionError::UnexpectedToken {
+                expected,
+                found: token.kind.to_string(),
+                column: token.position + 1,
+            }),
+            None => Err(ExpressionError::UnexpectedEnd { expected }),
+        }
+    }
+
+    /// expression := term (('+' | '-') term)*
+    fn parse_expression(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.parse_term()?;
+        loop {
+            let op = if self.eat(&TokenKind::Plus) {
+                BinaryOp::Add
+            } else if self.eat(&TokenKind::Minus) {
+                BinaryOp::Sub
+            } else {
+                return Ok(node);
+            };
+            let rhs = self.parse_term()?;
+            node = Node::Binary(op, Box::new(node), Box::new(rhs));
+        }
+    }
+
+    /// term := unary (('*' | '/') unary | 隐式乘法)*
+    fn parse_term(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.parse_unary()?;
+        loop {
+            let op = if self.eat(&TokenKind::Star) {
+                BinaryOp::Mul
+            } else if self.eat(&TokenKind::Slash) {
+                BinaryOp::Div
+            } else if matches!(
+                self.peek().map(|token| &token.kind),
+                Some(TokenKind::Number(_) | TokenKind::Identifier(_) | TokenKind::LeftParen)
+            ) {
+                // 隐式乘法：2x、3(x + 1)、x sin(x)
+                BinaryOp::Mul
+            } else {
+                return Ok(node);
+            };
+            let rhs = self.parse_unary()?;
+            node = Node::Binary(op, Box::new(node), Box::new(rhs));
+        }
+    }
+
+    /// unary := ('-' | '+') unary | power
+    fn parse_unary(&mut self) -> Result<Node, ExpressionError> {
+        if self.eat(&TokenKind::Minus) {
+            return Ok(Node::Negate(Box::new(self.parse_unary()?)));
+        }
+        if self.eat(&TokenKind::Plus) {
+            return self.parse_unary();
+        }
+        self.parse_power()
+    }
+
+    /// power := primary ('^' unary)?
+    fn parse_power(&mut self) -> Result<Node, ExpressionError> {
+        let base = self.parse_primary()?;
+        if self.eat(&TokenKind::Caret) {
+            let exponent = self.parse_unary()?;
+            return Ok(Node::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    /// primary := number | constant | variable | function '(' args ')' | '(' expression ')'
+    fn parse_primary(&mut self) -> Result<Node, ExpressionError> {
+        let Some(token) = self.next() else {
+            return Err(ExpressionError::UnexpectedEnd {
+                expected: "数字、变量或括号",
+            });
+        };
+
+        match token.kind {
+            TokenKind::Number(value) => Ok(Node::Number(value)),
+            TokenKind::LeftParen => {
+                let node = self.parse_expression()?;
+                self.expect(TokenKind::RightParen, "')'")?;
+                Ok(node)
+            }
+            TokenKind::Identifier(name) => {
+                // 不是函数名时括号留给隐式乘法，如 a(x + 1)
+                if let Some(function) = Function::from_name(&name) {
+                    if self.eat(&TokenKind::LeftParen) {
+                        return self.parse_call(name, function);
+                    }
+                }
+                Ok(match name.as_str() {
+                    "pi" => Node::Number(std::f32::consts::PI),
+                    "tau" => Node::Number(std::f32::consts::TAU),
+                    "e" => Node::Number(std::f32::consts::E),
+                    _ => Node::Variable(name),
+                })
+            }
+            kind => Err(ExpressionError::UnexpectedToken {
+                expected: "数字、变量或括号",
+                found: kind.to_string(),
+                column: token.position + 1,
+            }),
+        }
+    }
+
+    /// 解析函数调用参数（左括号已消费）
+    fn parse_call(&mut self, name: String, function: Function) -> Result<Node, ExpressionError> {
+        let mut args = Vec::new();
+        if !self.eat(&TokenKind::RightParen) {
+            loop {
+                args.push(self.parse_expression()?);
+                if self.eat(&TokenKind::Comma) {
+                    continue;
+                }
+                self.expect(TokenKind::RightParen, "',' 或 ')'")?;
+                break;
+            }
+        }
+
+        if args.len() != function.arity() {
+            return Err(ExpressionError::ArgumentCount {
+                name,
+                expected: function.arity(),
+                found: args.len(),
+            });
+        }
+
+        Ok(Node::Call(function, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, x: f32) -> f32 {
+        Expression::parse(source).unwrap().eval_x(x)
+    }
+
+    fn error(source: &str) -> ExpressionError {
+        Expression::parse(source).unwrap_err()
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3", 0.0), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", 0.0), 9.0);
+        assert_eq!(eval("8 / 4 / 2", 0.0), 1.0);
+        assert_eq!(eval("10 - 4 - 3", 0.0), 3.0);
+        assert_eq!(eval("2 * 3 ^ 2", 0.0), 18.0);
+    }
+
+    #[test]
+    fn power_is_right_associative() {
+        assert_eq!(eval("2 ^ 3 ^ 2", 0.0), 512.0);
+        assert_eq!(eval("2 ^ -1", 0.0), 0.5);
+    }
+
+    #[test]
+    fn implicit_multiplication() {
+        assert_eq!(eval("2x", 3.0), 6.0);
+        assert_eq!(eval("3(x + 1)", 1.0), 6.0);
+        assert_eq!(eval("2x^2", 3.0), 18.0);
+        assert_eq!(eval("x sin(0)", 5.0), 0.0);
+        assert_eq!(eval("(x)(x + 1)", 2.0), 6.0);
+        assert_eq!(eval("x(x - 1)", 3.0), 6.0);
+        assert!((eval("pi(x + 1)", 1.0) - std::f32::consts::TAU).abs() < 1e-6);
+        let expression = Expression::parse("a(x + 1)").unwrap();
+        assert_eq!(expression.variables(), ["a", "x"]);
+        let value = expression.eval(&|name| match name {
+            "a" => Some(2.0),
+            "x" => Some(3.0),
+            _ => None,
+        });
+        assert_eq!(value, 8.0);
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-2 ^ 2", 0.0), -4.0);
+        assert_eq!(eval("--x", 2.0), 2.0);
+        assert_eq!(eval("3 - -x", 2.0), 5.0);
+        assert_eq!(eval("+x", 2.0), 2.0);
+        assert_eq!(eval("-x * 3", 2.0), -6.0);
+    }
+
+    #[test]
+    fn constants_functions_and_numbers() {
+        assert!((eval("2pi", 0.0) - std::f32::consts::TAU).abs() < 1e-6);
+        assert_eq!(eval("max(x, 2)", 5.0), 5.0);
+        assert_eq!(eval("pow(2, 10)", 0.0), 1024.0);
+        assert_eq!(eval("1e-3 * 1000", 0.0), 1.0);
+        assert_eq!(eval("abs(-x)", 4.0), 4.0);
+    }
+
+    #[test]
+    fn variables_in_order_of_appearance() {
+        let expression = Expression::parse("a * x + b + a").unwrap();
+        assert_eq!(expression.variables(), ["a", "x", "b"]);
+        assert_eq!(
+            expression.check_variables(&["x", "a"]),
+            Err(ExpressionError::UndefinedVariable {
+                name: "b".to_string()
+            })
+        );
+        assert!(eval("y", 1.0).is_nan());
+    }
+
+    #[test]
+    fn error_columns() {
+        assert_eq!(error("x + $").column(), Some(5));
+        assert_eq!(error("1.2.3").column(), Some(1));
+        assert_eq!(error("2 + * 3").column(), Some(5));
+        assert_eq!(error("(x + 1))").column(), Some(8));
+        assert_eq!(error("  foo(x))").column(), Some(9));
+        assert!(matches!(
+            error("2 + * 3"),
+            ExpressionError::UnexpectedToken { found, .. } if found == "*"
+        ));
+    }
+
+    #[test]
+    fn errors_without_column() {
+        assert_eq!(error("   "), ExpressionError::Empty);
+        assert!(matches!(
+            error("sin(x"),
+            ExpressionError::UnexpectedEnd { .. }
+        ));
+        assert!(matches!(
+            error("x *"),
+            ExpressionError::UnexpectedEnd { .. }
+        ));
+        assert_eq!(
+            error("max(1)"),
+            ExpressionError::ArgumentCount {
+                name: "max".to_string(),
+                expected: 2,
+                found: 1,
+            }
+        );
+        assert_eq!(error("sin(x").column(), None);
+    }
+}
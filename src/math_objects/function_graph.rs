@@ -1,12 +1,18 @@
+use super::expression::{Expression, ExpressionError};
 use super::{MathObject, Position2D, Style};
+use crate::render::CoordinateSystem;
 use bevy::prelude::*;
 
+/// 自适应采样的初始步长（像素）
+const INITIAL_STEP_PIXELS: f32 = 8.0;
+
 pub struct FunctionGraphPlugin;
 
 impl Plugin for FunctionGraphPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<FunctionGraph>()
             .register_type::<ParametricCurve>()
+            .register_type::<GraphParameter>()
             .add_systems(Update, update_function_graphs);
     }
 }
@@ -14,14 +20,123 @@ impl Plugin for FunctionGraphPlugin {
 /// 函数类型
 pub type MathFunction = Box<dyn Fn(f32) -> f32 + Send + Sync>;
 
+/// 图形的函数来源
+#[derive(Clone, Debug)]
+pub enum GraphFunction {
+    /// Rust 函数
+    Native(fn(f32) -> f32),
+    /// 文本表达式
+    Expression(Expression),
+}
+
+impl GraphFunction {
+    /// 以自变量 `variable = value` 求值，其余变量从 parameters 中查找
+    pub fn evaluate(&self, variable: &str, value: f32, parameters: &[GraphParameter]) -> f32 {
+        match self {
+            GraphFunction::Native(func) => func(value),
+            GraphFunction::Expression(expression) => expression.eval(&|name| {
+                if name == variable {
+                    Some(value)
+                } else {
+                    parameters
+                        .iter()
+                        .find(|parameter| parameter.name == name)
+                        .map(|parameter| parameter.value)
+                }
+            }),
+        }
+    }
+
+    /// 表达式文本，Rust 函数返回 None
+    pub fn source(&self) -> Option<&str> {
+        match self {
+            GraphFunction::Native(_) => None,
+            GraphFunction::Expression(expression) => Some(expression.source()),
+        }
+    }
+}
+
+/// 表达式中除自变量外的命名参数，如 `a*sin(b*x + c)` 中的 a、b、c
+#[derive(Reflect, Clone, Debug)]
+pub struct GraphParameter {
+    pub name: String,
+    pub value: f32,
+    /// 滑块范围
+    pub min: f32,
+    pub max: f32,
+}
+
+impl GraphParameter {
+    /// 创建默认值为 1、滑块范围为 [-10, 10] 的参数
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: 1.0,
+            min: -10.0,
+            max: 10.0,
+        }
+    }
+}
+
+/// 收集表达式中除 variable 以外的变量作为参数（按首次出现顺序去重）
+fn collect_parameters(expressions: &[&Expression], variable: &str) -> Vec<GraphParameter> {
+    let mut parameters: Vec<GraphParameter> = Vec::new();
+    for name in expressions
+        .iter()
+        .flat_map(|expression| expression.variables())
+    {
+        if name != variable && !parameters.iter().any(|parameter| &parameter.name == name) {
+            parameters.push(GraphParameter::new(name.clone()));
+        }
+    }
+    parameters
+}
+
+/// 设置同名参数的值，参数不存在或值未变化时返回 false
+fn set_parameter_value(parameters: &mut [GraphParameter], name: &str, value: f32) -> bool {
+    match parameters
+        .iter_mut()
+        .find(|parameter| parameter.name == name)
+    {
+        Some(parameter) if parameter.value != value => {
+            parameter.value = value;
+            true
+        }
+        _ => false,
+    }
+}
+
 /// 函数图形组件
 #[derive(Component, Reflect)]
 pub struct FunctionGraph {
     pub domain_start: f32,
     pub domain_end: f32,
+    /// 初始均匀采样的最少区间数
     pub sample_count: u32,
+    /// 自适应采样允许的最大误差（像素）
+    pub tolerance: f32,
+    /// 自适应细分的最大深度
+    pub max_depth: u32,
+    /// 采样点，NaN 表示曲线在此断开
     #[reflect(ignore)]
     pub points: Vec<Vec2>,
+    /// 被绘制的函数，修改后调用 invalidate 重新采样
+    #[reflect(ignore)]
+    pub function: Option<GraphFunction>,
+    /// 表达式中的命名参数
+    pub parameters: Vec<GraphParameter>,
+    /// 当前采样点对应的采样窗口，None 表示需要重新采样
+    #[reflect(ignore)]
+    pub sample_window: Option<SampleWindow>,
+}
+
+/// 一次采样覆盖的 x 范围和精度
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampleWindow {
+    /// 采样的 x 范围（图形局部坐标）
+    pub x_range: (f32, f32),
+    /// 每个像素对应的数学长度
+    pub pixel_size: f32,
 }
 
 /// 参数方程曲线组件
@@ -32,6 +147,201 @@ pub struct ParametricCurve {
     pub sample_count: u32,
     #[reflect(ignore)]
     pub points: Vec<Vec2>,
+    /// x(t)，修改后调用 resample
+    #[reflect(ignore)]
+    pub x_function: Option<GraphFunction>,
+    /// y(t)，修改后调用 resample
+    #[reflect(ignore)]
+    pub y_function: Option<GraphFunction>,
+    /// 表达式中的命名参数
+    pub parameters: Vec<GraphParameter>,
+}
+
+impl FunctionGraph {
+    /// 在 x 处求函数值，没有函数时返回 None
+    pub fn evaluate(&self, x: f32) -> Option<f32> {
+        self.function
+            .as_ref()
+            .map(|function| function.evaluate("x", x, &self.parameters))
+    }
+
+    /// 标记需要重新采样，下一帧由 update_function_graphs 完成
+    ///
+    /// 旧的采样点保留到重新采样为止，避免图形闪烁。
+    pub fn invalidate(&mut self) {
+        self.sample_window = None;
+    }
+
+    /// 修改命名参数并按当前采样窗口立即重新采样
+    pub fn set_parameter(&mut self, name: &str, value: f32) {
+        if !set_parameter_value(&mut self.parameters, name, value) {
+            return;
+        }
+
+        match self.sample_window {
+            Some(window) => self.resample(window.x_range, window.pixel_size),
+            None => self.invalidate(),
+        }
+    }
+
+    /// 当前采样是否无法覆盖可见范围，或精度与当前缩放不再匹配
+    pub fn needs_resample(&self, visible_range: (f32, f32), pixel_size: f32) -> bool {
+        let Some(window) = self.sample_window else {
+            return true;
+        };
+
+        let covered = window.x_range.0 <= visible_range.0.max(self.domain_start)
+            && window.x_range.1 >= visible_range.1.min(self.domain_end);
+        let ratio = pixel_size / window.pixel_size;
+        !covered || !(0.8..=1.25).contains(&ratio)
+    }
+
+    /// 在定义域与 x_range 的交集内自适应采样
+    ///
+    /// 先按不超过 INITIAL_STEP_PIXELS 的步长均匀划分，再对中点偏离弦线超过
+    /// tolerance 的区间递归二分。到达 max_depth 仍不满足误差的区间视为间断
+    /// （渐近线或跳跃），在该处插入 NaN 断开曲线。
+    pub fn resample(&mut self, x_range: (f32, f32), pixel_size: f32) {
+        self.sample_window = Some(SampleWindow {
+            x_range,
+            pixel_size,
+        });
+        self.points.clear();
+
+        let Some(function) = &self.function else {
+            return;
+        };
+
+        let start = self.domain_start.max(x_range.0);
+        let end = self.domain_end.min(x_range.1);
+        if start >= end || pixel_size <= 0.0 {
+            return;
+        }
+
+        let intervals = ((end - start) / (pixel_size * INITIAL_STEP_PIXELS))
+            .ceil()
+            .max(self.sample_count.max(1) as f32) as u32;
+        let parameters = &self.parameters;
+        let sample = |x: f32| Vec2::new(x, function.evaluate("x", x, parameters));
+        let tolerance = self.tolerance * pixel_size;
+
+        let mut previous = sample(start);
+        self.points.push(previous);
+        for i in 1..=intervals {
+            let x = start + (end - start) * i as f32 / intervals as f32;
+            let next = sample(x);
+            subdivide(
+                &sample,
+                previous,
+                next,
+                tolerance,
+                self.max_depth,
+                &mut self.points,
+            );
+            previous = next;
+        }
+    }
+
+    /// 整个定义域上的包围盒（忽略非有限值），没有有效采样点时返回 None
+    pub fn bounds(&self) -> Option<Rect> {
+        let Some(function) = &self.function else {
+            return points_bounds(&self.points);
+        };
+
+        let sample_count = self.sample_count.max(2);
+        let points: Vec<Vec2> = (0..sample_count)
+            .map(|i| {
+                let t = i as f32 / (sample_count - 1) as f32;
+                let x = self.domain_start + t * (self.domain_end - self.domain_start);
+                Vec2::new(x, function.evaluate("x", x, &self.parameters))
+            })
+            .collect();
+        points_bounds(&points)
+    }
+}
+
+/// 递归二分 [a, b]，把 b 及中间采样点追加到 points（不含 a）
+fn subdivide(
+    sample: &impl Fn(f32) -> Vec2,
+    a: Vec2,
+    b: Vec2,
+    tolerance: f32,
+    depth: u32,
+    points: &mut Vec<Vec2>,
+) {
+    let m = sample((a.x + b.x) * 0.5);
+
+    // 整段无定义，直接断开
+    if !a.y.is_finite() && !m.y.is_finite() && !b.y.is_finite() {
+        points.push(b);
+        return;
+    }
+
+    let smooth = a.y.is_finite()
+        && m.y.is_finite()
+        && b.y.is_finite()
+        && (m.y - (a.y + b.y) * 0.5).abs() <= tolerance;
+    if smooth {
+        points.push(m);
+        points.push(b);
+        return;
+    }
+
+    if depth == 0 {
+        // 区间已足够小仍不平滑：渐近线、跳跃或定义域边界
+        points.push(Vec2::new(m.x, f32::NAN));
+        points.push(b);
+        return;
+    }
+
+    subdivide(sample, a, m, tolerance, depth - 1, points);
+    subdivide(sample, m, b, tolerance, depth - 1, points);
+}
+
+impl ParametricCurve {
+    /// 在参数范围内均匀重新采样
+    pub fn resample(&mut self) {
+        let (Some(x_function), Some(y_function)) = (&self.x_function, &self.y_function) else {
+            return;
+        };
+
+        let sample_count = self.sample_count.max(2);
+        self.points = (0..sample_count)
+            .map(|i| {
+                let t = self.param_start
+                    + (i as f32 / (sample_count - 1) as f32) * (self.param_end - self.param_start);
+                Vec2::new(
+                    x_function.evaluate("t", t, &self.parameters),
+                    y_function.evaluate("t", t, &self.parameters),
+                )
+            })
+            .collect();
+    }
+
+    /// 修改命名参数并立即重新采样
+    pub fn set_parameter(&mut self, name: &str, value: f32) {
+        if set_parameter_value(&mut self.parameters, name, value) {
+            self.resample();
+        }
+    }
+
+    /// 采样点的包围盒（忽略非有限值），没有有效采样点时返回 None
+    pub fn bounds(&self) -> Option<Rect> {
+        points_bounds(&self.points)
+    }
+}
+
+/// 计算点集的包围盒，跳过 NaN 和无穷大
+pub fn points_bounds(points: &[Vec2]) -> Option<Rect> {
+    points
+        .iter()
+        .filter(|point| point.is_finite())
+        .fold(None, |bounds: Option<Rect>, point| {
+            Some(match bounds {
+                Some(rect) => rect.union_point(*point),
+                None => Rect::from_center_size(*point, Vec2::ZERO),
+            })
+        })
 }
 
 impl Default for FunctionGraph {
@@ -40,7 +350,12 @@ impl Default for FunctionGraph {
             domain_start: -5.0,
             domain_end: 5.0,
             sample_count: 100,
+            tolerance: 0.5,
+            max_depth: 10,
             points: Vec::new(),
+            function: None,
+            parameters: Vec::new(),
+            sample_window: None,
         }
     }
 }
@@ -52,6 +367,9 @@ impl Default for ParametricCurve {
             param_end: 1.0,
             sample_count: 100,
             points: Vec::new(),
+            x_function: None,
+            y_function: None,
+            parameters: Vec::new(),
         }
     }
 }
@@ -63,21 +381,53 @@ pub fn create_function_graph(
     domain: (f32, f32),
     style: Style,
 ) -> Entity {
-    let mut graph = FunctionGraph {
+    spawn_function_graph(
+        commands,
+        GraphFunction::Native(func),
+        Vec::new(),
+        domain,
+        style,
+    )
+}
+
+/// 从表达式文本创建函数图形，例如 `sin(x) * exp(-x^2/4)`
+///
+/// 除 x 以外的变量成为可调参数，如 `a*sin(b*x + c)` 中的 a、b、c。
+pub fn create_function_graph_from_expression(
+    commands: &mut Commands,
+    source: &str,
+    domain: (f32, f32),
+    style: Style,
+) -> Result<Entity, ExpressionError> {
+    let expression = Expression::parse(source)?;
+    let parameters = collect_parameters(&[&expression], "x");
+
+    Ok(spawn_function_graph(
+        commands,
+        GraphFunction::Expression(expression),
+        parameters,
+        domain,
+        style,
+    ))
+}
+
+/// 生成函数图形实体
+fn spawn_function_graph(
+    commands: &mut Commands,
+    function: GraphFunction,
+    parameters: Vec<GraphParameter>,
+    domain: (f32, f32),
+    style: Style,
+) -> Entity {
+    // 采样依赖当前视图，由 update_function_graphs 在下一帧完成
+    let graph = FunctionGraph {
         domain_start: domain.0,
         domain_end: domain.1,
-        sample_count: 100,
-        points: Vec::new(),
+        function: Some(function),
+        parameters,
+        ..default()
     };
 
-    // 采样函数点
-    for i in 0..graph.sample_count {
-        let t = i as f32 / (graph.sample_count - 1) as f32;
-        let x = graph.domain_start + t * (graph.domain_end - graph.domain_start);
-        let y = func(x);
-        graph.points.push(Vec2::new(x, y));
-    }
-
     commands
         .spawn((
             MathObject {
@@ -89,6 +439,7 @@ pub fn create_function_graph(
             Position2D { x: 0.0, y: 0.0 },
             style,
             Transform::default(),
+            Visibility::Inherited,
         ))
         .id()
 }
@@ -100,22 +451,61 @@ pub fn create_parametric_curve(
     y_func: fn(f32) -> f32,
     param_range: (f32, f32),
     style: Style,
+) -> Entity {
+    spawn_parametric_curve(
+        commands,
+        (GraphFunction::Native(x_func), GraphFunction::Native(y_func)),
+        Vec::new(),
+        param_range,
+        style,
+    )
+}
+
+/// 从 x(t)、y(t) 表达式文本创建参数曲线，例如 `cos(t)`、`sin(2*t)`
+///
+/// 除 t 以外的变量成为两条表达式共享的可调参数。
+pub fn create_parametric_curve_from_expressions(
+    commands: &mut Commands,
+    x_source: &str,
+    y_source: &str,
+    param_range: (f32, f32),
+    style: Style,
+) -> Result<Entity, ExpressionError> {
+    let x_expression = Expression::parse(x_source)?;
+    let y_expression = Expression::parse(y_source)?;
+    let parameters = collect_parameters(&[&x_expression, &y_expression], "t");
+
+    Ok(spawn_parametric_curve(
+        commands,
+        (
+            GraphFunction::Expression(x_expression),
+            GraphFunction::Expression(y_expression),
+        ),
+        parameters,
+        param_range,
+        style,
+    ))
+}
+
+/// 采样并生成参数曲线实体
+fn spawn_parametric_curve(
+    commands: &mut Commands,
+    (x_function, y_function): (GraphFunction, GraphFunction),
+    parameters: Vec<GraphParameter>,
+    param_range: (f32, f32),
+    style: Style,
 ) -> Entity {
     let mut curve = ParametricCurve {
         param_start: param_range.0,
         param_end: param_range.1,
-        sample_count: 100,
-        points: Vec::new(),
+        x_function: Some(x_function),
+        y_function: Some(y_function),
+        parameters,
+        ..default()
     };
 
     // 采样参数曲线点
-    for i in 0..curve.sample_count {
-        let t = curve.param_start
-            + (i as f32 / (curve.sample_count - 1) as f32) * (curve.param_end - curve.param_start);
-        let x = x_func(t);
-        let y = y_func(t);
-        curve.points.push(Vec2::new(x, y));
-    }
+    curve.resample();
 
     commands
         .spawn((
@@ -128,29 +518,69 @@ pub fn create_parametric_curve(
             Position2D { x: 0.0, y: 0.0 },
             style,
             Transform::default(),
+            Visibility::Inherited,
         ))
         .id()
 }
 
-/// 更新函数图形的系统
-fn update_function_graphs(mut query: Query<&mut FunctionGraph, Changed<FunctionGraph>>) {
-    for mut graph in query.iter_mut() {
-        // 这里可以添加实时更新函数图形的逻辑
-        // 比如当函数参数改变时重新采样
-        if graph.points.is_empty() {
-            // 重新采样
-            graph.points.clear();
-            for i in 0..graph.sample_count {
-                let t = i as f32 / (graph.sample_count - 1) as f32;
-                let x = graph.domain_start + t * (graph.domain_end - graph.domain_start);
-                // 这里需要一个默认函数，比如 y = x
-                let y = x;
-                graph.points.push(Vec2::new(x, y));
-            }
+/// 更新函数图形的系统：可见范围或缩放变化时重新采样
+fn update_function_graphs(
+    mut query: Query<(&mut FunctionGraph, &Position2D, Option<&Transform>)>,
+    coordinate_system: Res<CoordinateSystem>,
+) {
+    let visible = coordinate_system.visible_rect();
+    let pixel_size = 1.0 / coordinate_system.pixels_per_unit();
+
+    for (mut graph, position, transform) in query.iter_mut() {
+        let transform = transform.copied().unwrap_or_default();
+        let Some(visible_range) = local_x_range(visible, position, &transform) else {
+            continue;
+        };
+        // 放大后局部坐标中的一个像素更短，需要更密的采样
+        let pixel_size = pixel_size / transform.scale.truncate().abs().max_element();
+        if !graph.needs_resample(visible_range, pixel_size) {
+            continue;
         }
+
+        // 可见范围两侧各多采样半个视口，平移时不必每帧重新采样
+        let margin = (visible_range.1 - visible_range.0) * 0.5;
+        graph.resample(
+            (visible_range.0 - margin, visible_range.1 + margin),
+            pixel_size,
+        );
     }
 }
 
+/// 把可见区域换算到图形的局部坐标（应用 Transform 缩放和旋转之前），返回其 x 范围
+///
+/// 与渲染时的变换互逆；x 方向缩放为零时图形退化，返回 None。
+fn local_x_range(
+    visible: Rect,
+    position: &Position2D,
+    transform: &Transform,
+) -> Option<(f32, f32)> {
+    let scale_x = transform.scale.x;
+    if scale_x.abs() <= f32::EPSILON {
+        return None;
+    }
+    let center = Vec2::new(position.x, position.y);
+    let inverse = transform.rotation.inverse();
+    let corners = [
+        visible.min,
+        Vec2::new(visible.max.x, visible.min.y),
+        visible.max,
+        Vec2::new(visible.min.x, visible.max.y),
+    ]
+    .map(|corner| (inverse * (corner - center).extend(0.0)).x / scale_x);
+    Some(
+        corners
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), x| {
+                (min.min(*x), max.max(*x))
+            }),
+    )
+}
+
 /// 常用数学函数
 pub mod functions {
     /// 正弦函数
@@ -182,3 +612,45 @@ pub mod functions {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(transform: Transform) -> (f32, f32) {
+        let visible = Rect::new(-4.0, -2.0, 4.0, 2.0);
+        local_x_range(visible, &Position2D { x: 1.0, y: 0.0 }, &transform).unwrap()
+    }
+
+    fn assert_range(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-4 && (actual.1 - expected.1).abs() < 1e-4,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn local_range_follows_graph_transform() {
+        assert_range(range(Transform::default()), (-5.0, 3.0));
+        // 缩小一半后需要两倍的局部范围才能铺满视口
+        assert_range(range(Transform::from_scale(Vec3::splat(0.5))), (-10.0, 6.0));
+        // 旋转 90° 后局部 x 轴沿屏幕 y 方向
+        assert_range(
+            range(Transform::from_rotation(Quat::from_rotation_z(
+                std::f32::consts::FRAC_PI_2,
+            ))),
+            (-2.0, 2.0),
+        );
+        // 水平翻转时范围仍按从小到大排列
+        assert_range(
+            range(Transform::from_scale(Vec3::new(-1.0, 1.0, 1.0))),
+            (-3.0, 5.0),
+        );
+        let visible = Rect::new(-4.0, -2.0, 4.0, 2.0);
+        let collapsed = Transform::from_scale(Vec3::new(0.0, 1.0, 1.0));
+        assert_eq!(
+            local_x_range(visible, &Position2D { x: 0.0, y: 0.0 }, &collapsed),
+            None
+        );
+    }
+}
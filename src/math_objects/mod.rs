@@ -19,11 +19,17 @@ use bevy::prelude::*;
 
 pub mod axes;
 pub mod basic_shapes;
+pub mod expression;
 pub mod function_graph;
+pub mod outline;
+pub mod path;
 
 pub use axes::*;
 pub use basic_shapes::*;
+pub use expression::*;
 pub use function_graph::*;
+pub use outline::*;
+pub use path::*;
 
 pub struct MathObjectPlugin;
 
@@ -32,7 +38,9 @@ impl Plugin for MathObjectPlugin {
         app.add_plugins((BasicShapesPlugin, FunctionGraphPlugin, AxesPlugin))
             .register_type::<MathObject>()
             .register_type::<Position2D>()
-            .register_type::<Style>();
+            .register_type::<Style>()
+            .register_type::<RevealProgress>()
+            .register_type::<MorphOutline>();
     }
 }
 
@@ -64,7 +72,7 @@ impl Into<Vec2> for Position2D {
 }
 
 /// 样式组件
-#[derive(Component, Reflect, Clone)]
+#[derive(Component, Reflect, Clone, Debug, PartialEq)]
 pub struct Style {
     pub stroke_color: Color,
     pub fill_color: Option<Color>,
@@ -83,6 +91,26 @@ impl Default for Style {
     }
 }
 
+impl Style {
+    /// 在两个样式之间插值，t = 0 时为 self，t = 1 时为 other
+    ///
+    /// 一侧没有填充时按该侧填充完全透明处理。
+    pub fn interpolate(&self, other: &Style, t: f32) -> Style {
+        let fill_color = match (self.fill_color, other.fill_color) {
+            (Some(from), Some(to)) => Some(from.mix(&to, t)),
+            (Some(from), None) => Some(from.mix(&from.with_alpha(0.0), t)),
+            (None, Some(to)) => Some(to.with_alpha(0.0).mix(&to, t)),
+            (None, None) => None,
+        };
+        Style {
+            stroke_color: self.stroke_color.mix(&other.stroke_color, t),
+            fill_color,
+            stroke_width: self.stroke_width + (other.stroke_width - self.stroke_width) * t,
+            opacity: (self.opacity + (other.opacity - self.opacity) * t).clamp(0.0, 1.0),
+        }
+    }
+}
+
 /// 数学对象类型枚举
 #[derive(Debug, Clone, PartialEq)]
 pub enum MathObjectType {
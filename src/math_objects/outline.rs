@@ -0,0 +1,201 @@
+use bevy::prelude::*;
+
+/// 渐进显示进度，由 Draw/Write 动画驱动，取值 0~1
+#[derive(Component, Reflect, Clone, Debug)]
+pub struct RevealProgress {
+    /// 已描出的轮廓比例（按弧长）
+    pub stroke: f32,
+    /// 填充的显示比例（乘到填充透明度上）
+    pub fill: f32,
+}
+
+impl Default for RevealProgress {
+    fn default() -> Self {
+        Self {
+            stroke: 1.0,
+            fill: 1.0,
+        }
+    }
+}
+
+/// 代替对象自身形状绘制的轮廓点（以 Position2D 为原点），由 Morph 动画写入
+#[derive(Component, Reflect, Clone, Debug, Default)]
+pub struct MorphOutline {
+    pub points: Vec<Vec2>,
+}
+
+/// 折线总长度，NaN 或无穷大的点处断开不计
+pub fn polyline_length(points: &[Vec2]) -> f32 {
+    points
+        .windows(2)
+        .filter(|pair| pair[0].is_finite() && pair[1].is_finite())
+        .map(|pair| pair[0].distance(pair[1]))
+        .sum()
+}
+
+/// 按弧长把折线重新采样为 count 个点，在 NaN 或无穷大的点处断开
+///
+/// 各子路径按长度比例分配采样点并各自等距采样，首尾点都会采到；相邻子路径之间
+/// 保留一个 NaN 断点（计入 count），因此结果不会跨越断点连线，断点之间的空隙
+/// 也不占用采样点。子路径多到每条分不到一个点时只保留最长的几条。
+pub fn resample_polyline(points: &[Vec2], count: usize) -> Vec<Vec2> {
+    let mut subpaths: Vec<&[Vec2]> = points
+        .split(|point| !point.is_finite())
+        .filter(|subpath| !subpath.is_empty())
+        .collect();
+    if subpaths.is_empty() || count == 0 {
+        return Vec::new();
+    }
+
+    // 每条子路径至少一个点，再加上子路径之间的断点
+    let keep = subpaths.len().min(count.div_ceil(2));
+    if keep < subpaths.len() {
+        let mut longest: Vec<usize> = (0..subpaths.len()).collect();
+        longest.sort_by(|&a, &b| {
+            polyline_length(subpaths[b]).total_cmp(&polyline_length(subpaths[a]))
+        });
+        longest.truncate(keep);
+        longest.sort_unstable();
+        subpaths = longest.into_iter().map(|i| subpaths[i]).collect();
+    }
+
+    let lengths: Vec<f32> = subpaths
+        .iter()
+        .map(|subpath| polyline_length(subpath))
+        .collect();
+    let samples = allocate_samples(count - (subpaths.len() - 1), &lengths);
+    let mut result = Vec::with_capacity(count);
+    for (i, (subpath, samples)) in subpaths.iter().zip(samples).enumerate() {
+        if i > 0 {
+            result.push(Vec2::NAN);
+        }
+        resample_subpath(subpath, samples, &mut result);
+    }
+    result
+}
+
+/// 按长度比例把 count 个采样点分给各子路径，每条至少一个（最大余数法）
+fn allocate_samples(count: usize, lengths: &[f32]) -> Vec<usize> {
+    let total: f32 = lengths.iter().sum();
+    let spare = (count - lengths.len()) as f32;
+    let shares: Vec<f32> = lengths
+        .iter()
+        .map(|length| {
+            if total > f32::EPSILON {
+                spare * length / total
+            } else {
+                spare / lengths.len() as f32
+            }
+        })
+        .collect();
+    let mut samples: Vec<usize> = shares.iter().map(|share| 1 + *share as usize).collect();
+    let mut by_remainder: Vec<usize> = (0..lengths.len()).collect();
+    by_remainder.sort_by(|&a, &b| shares[b].fract().total_cmp(&shares[a].fract()));
+    let assigned: usize = samples.iter().sum();
+    for i in by_remainder
+        .into_iter()
+        .take(count.saturating_sub(assigned))
+    {
+        samples[i] += 1;
+    }
+    samples
+}
+
+/// 把一条不含断点的折线按弧长等距采样 count 个点（含首尾点），追加到 result
+fn resample_subpath(points: &[Vec2], count: usize, result: &mut Vec<Vec2>) {
+    let total = polyline_length(points);
+    if points.len() == 1 || total <= f32::EPSILON || count == 1 {
+        result.extend(std::iter::repeat_n(points[0], count));
+        return;
+    }
+
+    let mut segment = 0;
+    let mut segment_start = 0.0;
+    for i in 0..count {
+        let target = total * i as f32 / (count - 1) as f32;
+        // 前进到包含目标弧长的线段
+        while segment < points.len() - 2
+            && segment_start + points[segment].distance(points[segment + 1]) < target
+        {
+            segment_start += points[segment].distance(points[segment + 1]);
+            segment += 1;
+        }
+
+        let length = points[segment].distance(points[segment + 1]);
+        let t = if length > 0.0 {
+            ((target - segment_start) / length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        result.push(points[segment].lerp(points[segment + 1], t));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resample_keeps_subpaths_apart() {
+        let points = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::NAN,
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 1.0),
+        ];
+        let samples = resample_polyline(&points, 10);
+        assert_eq!(samples.len(), 10);
+
+        // 断点只有一个，两侧分别是两条子路径的采样点
+        let parts: Vec<&[Vec2]> = samples.split(|point| !point.is_finite()).collect();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].len() + parts[1].len(), 9);
+        // 长度 3 与 1 的子路径按比例分配采样点
+        assert_eq!((parts[0].len(), parts[1].len()), (6, 3));
+        assert_eq!(parts[0].first(), Some(&Vec2::new(0.0, 0.0)));
+        assert_eq!(parts[0].last(), Some(&Vec2::new(3.0, 0.0)));
+        assert_eq!(parts[1].first(), Some(&Vec2::new(10.0, 0.0)));
+        assert_eq!(parts[1].last(), Some(&Vec2::new(10.0, 1.0)));
+        assert!(samples
+            .iter()
+            .filter(|point| point.is_finite())
+            .all(|point| point.x <= 3.0 || point.x >= 10.0));
+        // 空隙不计入弧长
+        assert!((polyline_length(&samples) - 4.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn resample_drops_shortest_subpaths_when_samples_run_out() {
+        let points = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::NAN,
+            Vec2::new(5.0, 0.0),
+            Vec2::NAN,
+            Vec2::new(7.0, 0.0),
+            Vec2::new(9.0, 0.0),
+        ];
+        let samples = resample_polyline(&points, 3);
+        assert_eq!(samples.len(), 3);
+        assert_eq!(samples[0], Vec2::new(0.0, 0.0));
+        assert!(samples[1].is_nan());
+        assert_eq!(samples[2], Vec2::new(7.0, 0.0));
+    }
+
+    #[test]
+    fn resample_single_subpath_is_evenly_spaced() {
+        let points = [Vec2::ZERO, Vec2::new(1.0, 0.0), Vec2::new(1.0, 1.0)];
+        let samples = resample_polyline(&points, 5);
+        let expected = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.5, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 0.5),
+            Vec2::new(1.0, 1.0),
+        ];
+        for (sample, expected) in samples.iter().zip(expected) {
+            assert!(sample.distance(expected) < 1e-5, "{sample} != {expected}");
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use super::basic_shapes::{Line, MathCircle, Rectangle};
+use super::function_graph::{FunctionGraph, ParametricCurve};
+use super::outline::resample_polyline;
+use bevy::prelude::*;
+use lyon::math::{point, Box2D};
+use lyon::path::iterator::PathIterator;
+use lyon::path::{Path, PathEvent, Winding};
+
+/// 路径展平为折线时允许的最大误差（数学单位）
+const FLATTEN_TOLERANCE: f32 = 0.002;
+
+/// 各类数学对象共用的路径表示（lyon 路径，以 Position2D 为原点）
+#[derive(Clone, Debug, Default)]
+pub struct MathPath(pub Path);
+
+impl PartialEq for MathPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.iter().eq(other.0.iter())
+    }
+}
+
+impl MathPath {
+    /// 由折线创建路径，NaN 或无穷大的点处断开为多条子路径
+    pub fn from_polyline(points: &[Vec2], closed: bool) -> Self {
+        let mut builder = Path::builder();
+        for run in points.split(|point| !point.is_finite()) {
+            let Some((first, rest)) = run.split_first() else {
+                continue;
+            };
+            builder.begin(point(first.x, first.y));
+            for next in rest {
+                builder.line_to(point(next.x, next.y));
+            }
+            builder.end(closed);
+        }
+        Self(builder.build())
+    }
+
+    /// 以原点为圆心的圆
+    pub fn circle(radius: f32) -> Self {
+        let mut builder = Path::builder();
+        builder.add_circle(point(0.0, 0.0), radius, Winding::Positive);
+        Self(builder.build())
+    }
+
+    /// 以原点为中心的矩形
+    pub fn rectangle(width: f32, height: f32) -> Self {
+        let half = Vec2::new(width, height) * 0.5;
+        let mut builder = Path::builder();
+        builder.add_rectangle(
+            &Box2D::new(point(-half.x, -half.y), point(half.x, half.y)),
+            Winding::Positive,
+        );
+        Self(builder.build())
+    }
+
+    /// 展平后的子路径折线，闭合子路径以起点结尾
+    pub fn subpaths(&self) -> Vec<Vec<Vec2>> {
+        let mut subpaths = Vec::new();
+        let mut current = Vec::new();
+        for event in self.0.iter().flattened(FLATTEN_TOLERANCE) {
+            match event {
+                PathEvent::Begin { at } => {
+                    current = vec![Vec2::new(at.x, at.y)];
+                }
+                PathEvent::Line { to, .. } => current.push(Vec2::new(to.x, to.y)),
+                PathEvent::End { first, close, .. } => {
+                    if close {
+                        current.push(Vec2::new(first.x, first.y));
+                    }
+                    subpaths.push(std::mem::take(&mut current));
+                }
+                // 展平后只剩直线段
+                PathEvent::Quadratic { .. } | PathEvent::Cubic { .. } => {}
+            }
+        }
+        subpaths
+    }
+
+    /// 展平为一条折线，子路径之间以 NaN 断开
+    pub fn polyline(&self) -> Vec<Vec2> {
+        let mut points = Vec::new();
+        for subpath in self.subpaths() {
+            if !points.is_empty() {
+                points.push(Vec2::NAN);
+            }
+            points.extend(subpath);
+        }
+        points
+    }
+
+    /// 按弧长等距采样 count 个点
+    ///
+    /// 任意两条路径采样后点数一致，可以直接逐点插值；多条子路径按长度分配采样点，
+    /// 子路径之间以 NaN 断开，插值结果同样不会跨越断点连线。
+    pub fn resample(&self, count: usize) -> Vec<Vec2> {
+        resample_polyline(&self.polyline(), count)
+    }
+}
+
+/// 可以转换为 MathPath 的数学对象
+pub trait ToMathPath {
+    /// 以对象的 Position2D 为原点的路径
+    fn to_path(&self) -> MathPath;
+}
+
+impl ToMathPath for MathCircle {
+    fn to_path(&self) -> MathPath {
+        MathPath::circle(self.radius)
+    }
+}
+
+impl ToMathPath for Rectangle {
+    fn to_path(&self) -> MathPath {
+        MathPath::rectangle(self.width, self.height)
+    }
+}
+
+impl ToMathPath for Line {
+    fn to_path(&self) -> MathPath {
+        MathPath::from_polyline(&self.outline(), false)
+    }
+}
+
+impl ToMathPath for FunctionGraph {
+    fn to_path(&self) -> MathPath {
+        MathPath::from_polyline(&self.points, false)
+    }
+}
+
+impl ToMathPath for ParametricCurve {
+    fn to_path(&self) -> MathPath {
+        MathPath::from_polyline(&self.points, false)
+    }
+}
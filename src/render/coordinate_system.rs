@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+
+/// 默认单位长度：缩放为 1 时每个数学单位对应的像素数
+pub const DEFAULT_UNIT_SIZE: f32 = 50.0;
+
+/// 数学坐标与屏幕像素之间的映射资源
+///
+/// 相机固定在世界原点，屏幕像素坐标即世界坐标（原点在屏幕中心，Y轴向上）。
+/// 所有渲染和交互系统都通过该资源换算坐标，缩放和平移只需修改这里。
+#[derive(Resource, Reflect, Clone, Debug)]
+#[reflect(Resource)]
+pub struct CoordinateSystem {
+    /// 缩放为 1 时每个数学单位对应的像素数
+    pub unit_size: f32,
+    /// 当前缩放级别
+    pub zoom: f32,
+    /// 屏幕中心对应的数学坐标
+    pub center: Vec2,
+    /// 视口尺寸（像素）
+    pub viewport_size: Vec2,
+}
+
+impl Default for CoordinateSystem {
+    fn default() -> Self {
+        Self {
+            unit_size: DEFAULT_UNIT_SIZE,
+            zoom: 1.0,
+            center: Vec2::ZERO,
+            viewport_size: Vec2::new(1200.0, 800.0),
+        }
+    }
+}
+
+impl CoordinateSystem {
+    /// 当前每个数学单位对应的像素数
+    pub fn pixels_per_unit(&self) -> f32 {
+        self.unit_size * self.zoom
+    }
+
+    /// 数学坐标 -> 屏幕像素坐标
+    pub fn to_screen(&self, point: Vec2) -> Vec2 {
+        (point - self.center) * self.pixels_per_unit()
+    }
+
+    /// 屏幕像素坐标 -> 数学坐标
+    pub fn to_math(&self, screen: Vec2) -> Vec2 {
+        screen / self.pixels_per_unit() + self.center
+    }
+
+    /// 数学长度 -> 像素长度
+    pub fn to_screen_length(&self, length: f32) -> f32 {
+        length * self.pixels_per_unit()
+    }
+
+    /// 窗口光标坐标（左上角为原点，Y轴向下）-> 屏幕像素坐标
+    pub fn cursor_to_screen(&self, cursor: Vec2) -> Vec2 {
+        Vec2::new(
+            cursor.x - self.viewport_size.x * 0.5,
+            self.viewport_size.y * 0.5 - cursor.y,
+        )
+    }
+
+    /// 窗口光标坐标 -> 数学坐标
+    pub fn cursor_to_math(&self, cursor: Vec2) -> Vec2 {
+        self.to_math(self.cursor_to_screen(cursor))
+    }
+
+    /// 当前视口可见的数学坐标范围
+    pub fn visible_rect(&self) -> Rect {
+        Rect::from_center_size(self.center, self.viewport_size / self.pixels_per_unit())
+    }
+}
+
+/// 根据窗口尺寸同步视口大小
+pub fn sync_viewport_size(
+    mut coordinate_system: ResMut<CoordinateSystem>,
+    windows: Query<&Window>,
+) {
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let size = Vec2::new(window.width(), window.height());
+    if coordinate_system.viewport_size != size {
+        coordinate_system.viewport_size = size;
+    }
+}
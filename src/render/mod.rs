@@ -1,11 +1,39 @@
-use crate::math_objects::{Axes, Grid, MathCircle, MathObject, Position2D, Style as MathStyle};
+use crate::math_objects::basic_shapes::Rectangle as MathRectangle;
+use crate::math_objects::{
+    polyline_length, Axes, FunctionGraph, Grid, Line, MathCircle, MathObject, MorphOutline,
+    ParametricCurve, Position2D, RevealProgress, Style as MathStyle,
+};
 use bevy::prelude::*;
 
+pub mod coordinate_system;
+
+pub use coordinate_system::*;
+
+/// Gizmos 默认线宽（像素），用于按样式线宽叠加绘制粗线
+pub const GIZMO_LINE_WIDTH: f32 = 2.0;
+
 pub struct RenderPlugin;
 
 impl Plugin for RenderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (render_axes, render_grid, render_circles))
+        app.init_resource::<CoordinateSystem>()
+            .register_type::<CoordinateSystem>()
+            .add_systems(PreUpdate, sync_viewport_size)
+            .add_systems(
+                Update,
+                (
+                    render_axes,
+                    render_grid,
+                    render_circles,
+                    render_lines,
+                    render_rectangles,
+                    render_function_graphs,
+                    render_parametric_curves,
+                    render_morph_outlines,
+                )
+                    // 绘制动画求值之后的状态，截取的帧与播放头一致
+                    .after(crate::animation::apply_updaters),
+            )
             .add_systems(PostUpdate, (spawn_axis_labels, update_axis_labels));
     }
 }
@@ -23,205 +51,239 @@ pub struct AxisNameLabel {
     pub axis: String, // "x" 或 "y"
 }
 
+/// 屏幕坐标线段列表
+pub type ScreenSegments = Vec<(Vec2, Vec2)>;
+
+/// 坐标轴原点标记的半径（像素）
+pub const AXES_ORIGIN_RADIUS: f32 = 4.0;
+
+/// 坐标轴在屏幕上的线段：两条轴、箭头和刻度，原点标记另行绘制
+pub fn axes_segments(
+    axes: &Axes,
+    position: &Position2D,
+    coordinate_system: &CoordinateSystem,
+) -> ScreenSegments {
+    let viewport_half = coordinate_system.viewport_size * 0.5;
+    let position_vec = Vec2::new(position.x, position.y);
+    // 坐标轴原点在屏幕上的位置
+    let origin = coordinate_system.to_screen(position_vec);
+
+    let mut segments = vec![
+        // X 轴 - 延伸到窗口边界
+        (
+            Vec2::new(-viewport_half.x, origin.y),
+            Vec2::new(viewport_half.x, origin.y),
+        ),
+        // Y 轴 - 延伸到窗口边界
+        (
+            Vec2::new(origin.x, -viewport_half.y),
+            Vec2::new(origin.x, viewport_half.y),
+        ),
+    ];
+
+    // 箭头（X轴和Y轴）
+    if axes.show_arrows {
+        let arrow_size = 15.0; // 增大箭头使其更明显
+
+        // 箭头固定在视口边缘，随坐标轴一起平移
+        let arrow_margin = 30.0; // 箭头距离视窗边缘的边距
+
+        // X轴正向箭头 - 固定在屏幕右边缘
+        let x_arrow_tip = Vec2::new(viewport_half.x - arrow_margin, origin.y);
+        segments.push((
+            x_arrow_tip,
+            x_arrow_tip - Vec2::new(arrow_size, arrow_size * 0.5),
+        ));
+        segments.push((
+            x_arrow_tip,
+            x_arrow_tip - Vec2::new(arrow_size, -arrow_size * 0.5),
+        ));
+
+        // Y轴正向箭头 - 固定在屏幕上边缘
+        let y_arrow_tip = Vec2::new(origin.x, viewport_half.y - arrow_margin);
+        segments.push((
+            y_arrow_tip,
+            y_arrow_tip - Vec2::new(arrow_size * 0.5, arrow_size),
+        ));
+        segments.push((
+            y_arrow_tip,
+            y_arrow_tip - Vec2::new(-arrow_size * 0.5, arrow_size),
+        ));
+    }
+
+    // 刻度线
+    if axes.show_numbers {
+        // X轴刻度
+        let mut x = (axes.x_range.0 / axes.tick_spacing).ceil() * axes.tick_spacing;
+        while x <= axes.x_range.1 {
+            if (x - 0.0f32).abs() > 0.01 {
+                // 不在原点处画刻度
+                let tick_pos = coordinate_system.to_screen(position_vec + Vec2::new(x, 0.0));
+                // 增大刻度线
+                segments.push((
+                    tick_pos - Vec2::new(0.0, 8.0),
+                    tick_pos + Vec2::new(0.0, 8.0),
+                ));
+            }
+            x += axes.tick_spacing;
+        }
+
+        // Y轴刻度
+        let mut y = (axes.y_range.0 / axes.tick_spacing).ceil() * axes.tick_spacing;
+        while y <= axes.y_range.1 {
+            if (y - 0.0f32).abs() > 0.01 {
+                // 不在原点处画刻度
+                let tick_pos = coordinate_system.to_screen(position_vec + Vec2::new(0.0, y));
+                segments.push((
+                    tick_pos - Vec2::new(8.0, 0.0),
+                    tick_pos + Vec2::new(8.0, 0.0),
+                ));
+            }
+            y += axes.tick_spacing;
+        }
+    }
+
+    segments
+}
+
 /// 渲染坐标轴的系统
 fn render_axes(
     mut gizmos: Gizmos,
-    query: Query<(&Axes, &Position2D, &MathStyle, &Visibility), With<MathObject>>,
-    windows: Query<&Window>,
+    query: Query<(&Axes, &Position2D, &MathStyle, &InheritedVisibility), With<MathObject>>,
+    coordinate_system: Res<CoordinateSystem>,
 ) {
-    let Ok(window) = windows.single() else {
-        return;
-    };
-    let window_width = window.width();
-    let window_height = window.height();
-
     for (axes, position, style, visibility) in query.iter() {
         // 检查可见性 - 只有当实体可见时才渲染
-        if *visibility == Visibility::Hidden {
+        if !visibility.get() {
             continue;
         }
 
-        let scale = 50.0; // 单位长度对应的像素数
-        let position_vec = Vec3::new(position.x, position.y, 0.0);
-
-        // 计算视窗范围（让坐标轴延伸到窗口边界）
-        let viewport_half_width = window_width * 0.6; // 稍微留一些边距
-        let viewport_half_height = window_height * 0.6;
-
-        // 绘制 X 轴 - 延伸到窗口边界
-        let x_start = Vec3::new(-viewport_half_width, 0.0, 0.0) + position_vec;
-        let x_end = Vec3::new(viewport_half_width, 0.0, 0.0) + position_vec;
-        gizmos.line(x_start, x_end, style.stroke_color);
-
-        // 绘制 Y 轴 - 延伸到窗口边界
-        let y_start = Vec3::new(0.0, -viewport_half_height, 0.0) + position_vec;
-        let y_end = Vec3::new(0.0, viewport_half_height, 0.0) + position_vec;
-        gizmos.line(y_start, y_end, style.stroke_color);
-
-        // 绘制箭头（X轴和Y轴）
-        if axes.show_arrows {
-            let arrow_size = 15.0; // 增大箭头使其更明显
-
-            // 箭头固定在屏幕绝对位置，不受坐标轴位置和缩放影响
-            let arrow_margin = 30.0; // 箭头距离视窗边缘的边距
-
-            // X轴正向箭头 - 固定在屏幕右边缘（不加position_vec）
-            let x_arrow_tip = Vec3::new(viewport_half_width - arrow_margin, 0.0, 0.0);
-            let x_arrow_left = x_arrow_tip - Vec3::new(arrow_size, arrow_size * 0.5, 0.0);
-            let x_arrow_right = x_arrow_tip - Vec3::new(arrow_size, -arrow_size * 0.5, 0.0);
+        for (start, end) in axes_segments(axes, position, &coordinate_system) {
+            gizmos.line_2d(start, end, style.stroke_color);
+        }
 
-            gizmos.line(x_arrow_tip, x_arrow_left, style.stroke_color);
-            gizmos.line(x_arrow_tip, x_arrow_right, style.stroke_color);
+        // 绘制原点标记
+        let origin = coordinate_system.to_screen(Vec2::new(position.x, position.y));
+        gizmos.circle_2d(origin, AXES_ORIGIN_RADIUS, style.stroke_color); // 稍微增大原点
+    }
+}
 
-            // Y轴正向箭头 - 固定在屏幕上边缘（不加position_vec）
-            let y_arrow_tip = Vec3::new(0.0, viewport_half_height - arrow_margin, 0.0);
-            let y_arrow_left = y_arrow_tip - Vec3::new(arrow_size * 0.5, arrow_size, 0.0);
-            let y_arrow_right = y_arrow_tip - Vec3::new(-arrow_size * 0.5, arrow_size, 0.0);
+/// 网格在屏幕上的线段，分别为主网格线和次网格线
+pub fn grid_segments(
+    grid: &Grid,
+    position: &Position2D,
+    coordinate_system: &CoordinateSystem,
+) -> (ScreenSegments, ScreenSegments) {
+    let position_vec = Vec2::new(position.x, position.y);
+
+    // 网格覆盖整个可见区域（相对网格原点的数学坐标）
+    let visible = coordinate_system.visible_rect();
+    let grid_x_range = (visible.min.x - position.x, visible.max.x - position.x);
+    let grid_y_range = (visible.min.y - position.y, visible.max.y - position.y);
+    let to_screen = |x: f32, y: f32| coordinate_system.to_screen(position_vec + Vec2::new(x, y));
+
+    let mut major = Vec::new();
+    // 垂直网格线
+    let mut x = (grid_x_range.0 / grid.spacing).ceil() * grid.spacing;
+    while x <= grid_x_range.1 {
+        major.push((to_screen(x, grid_y_range.0), to_screen(x, grid_y_range.1)));
+        x += grid.spacing;
+    }
 
-            gizmos.line(y_arrow_tip, y_arrow_left, style.stroke_color);
-            gizmos.line(y_arrow_tip, y_arrow_right, style.stroke_color);
-        }
+    // 水平网格线
+    let mut y = (grid_y_range.0 / grid.spacing).ceil() * grid.spacing;
+    while y <= grid_y_range.1 {
+        major.push((to_screen(grid_x_range.0, y), to_screen(grid_x_range.1, y)));
+        y += grid.spacing;
+    }
 
-        // 绘制刻度线
-        if axes.show_numbers {
-            // X轴刻度
-            let mut x = (axes.x_range.0 / axes.tick_spacing).ceil() * axes.tick_spacing;
-            while x <= axes.x_range.1 {
-                if (x - 0.0f32).abs() > 0.01 {
-                    // 不在原点处画刻度
-                    let tick_pos = Vec3::new(x * scale, 0.0, 0.0) + position_vec;
-                    let tick_start = tick_pos - Vec3::new(0.0, 8.0, 0.0); // 增大刻度线
-                    let tick_end = tick_pos + Vec3::new(0.0, 8.0, 0.0);
-                    gizmos.line(tick_start, tick_end, style.stroke_color);
-                }
-                x += axes.tick_spacing;
+    // 次网格线（更细的网格）
+    let mut minor = Vec::new();
+    if grid.show_minor_grid && grid.minor_spacing > 0.0 {
+        // 垂直次网格线
+        let mut x = (grid_x_range.0 / grid.minor_spacing).ceil() * grid.minor_spacing;
+        while x <= grid_x_range.1 {
+            // 只绘制不与主网格重叠的线
+            if (x % grid.spacing).abs() > 0.01 {
+                minor.push((to_screen(x, grid_y_range.0), to_screen(x, grid_y_range.1)));
             }
+            x += grid.minor_spacing;
+        }
 
-            // Y轴刻度
-            let mut y = (axes.y_range.0 / axes.tick_spacing).ceil() * axes.tick_spacing;
-            while y <= axes.y_range.1 {
-                if (y - 0.0f32).abs() > 0.01 {
-                    // 不在原点处画刻度
-                    let tick_pos = Vec3::new(0.0, y * scale, 0.0) + position_vec;
-                    let tick_start = tick_pos - Vec3::new(8.0, 0.0, 0.0); // 增大刻度线
-                    let tick_end = tick_pos + Vec3::new(8.0, 0.0, 0.0);
-                    gizmos.line(tick_start, tick_end, style.stroke_color);
-                }
-                y += axes.tick_spacing;
+        // 水平次网格线
+        let mut y = (grid_y_range.0 / grid.minor_spacing).ceil() * grid.minor_spacing;
+        while y <= grid_y_range.1 {
+            // 只绘制不与主网格重叠的线
+            if (y % grid.spacing).abs() > 0.01 {
+                minor.push((to_screen(grid_x_range.0, y), to_screen(grid_x_range.1, y)));
             }
+            y += grid.minor_spacing;
         }
-
-        // 绘制原点标记
-        let origin = position_vec;
-        gizmos.circle_2d(origin.truncate(), 4.0, style.stroke_color); // 稍微增大原点
     }
+
+    (major, minor)
+}
+
+/// 网格主线和次线的颜色（带透明度）
+pub fn grid_colors(grid: &Grid, style: &MathStyle) -> (Color, Color) {
+    let color = style.stroke_color;
+    // 次网格更透明
+    (
+        color.with_alpha(grid.opacity),
+        color.with_alpha(grid.opacity * 0.3),
+    )
 }
 
 /// 渲染网格的系统
 fn render_grid(
     mut gizmos: Gizmos,
-    query: Query<(&Grid, &Position2D, &MathStyle, &Visibility), With<MathObject>>,
-    _axes_query: Query<&Axes>,
-    windows: Query<&Window>,
+    query: Query<(&Grid, &Position2D, &MathStyle, &InheritedVisibility), With<MathObject>>,
+    coordinate_system: Res<CoordinateSystem>,
 ) {
-    let Ok(window) = windows.single() else {
-        return;
-    };
-    let window_width = window.width();
-    let window_height = window.height();
-
     for (grid, position, style, visibility) in query.iter() {
         // 检查可见性 - 只有当实体可见时才渲染
-        if *visibility == Visibility::Hidden {
+        if !visibility.get() {
             continue;
         }
 
-        let scale = 50.0;
-        let position_vec = Vec3::new(position.x, position.y, 0.0);
-
-        // 计算视窗范围来确定网格绘制范围（覆盖整个可见区域）
-        let viewport_half_width = window_width * 0.7; // 稍微扩展一些确保覆盖全部
-        let viewport_half_height = window_height * 0.7;
-
-        let grid_x_range = (-viewport_half_width / scale, viewport_half_width / scale);
-        let grid_y_range = (-viewport_half_height / scale, viewport_half_height / scale);
-
-        // 创建网格颜色（带透明度）
-        let grid_color = Color::srgba(
-            style.stroke_color.to_srgba().red,
-            style.stroke_color.to_srgba().green,
-            style.stroke_color.to_srgba().blue,
-            grid.opacity,
-        );
-
-        // 垂直网格线
-        let mut x = (grid_x_range.0 / grid.spacing).ceil() * grid.spacing;
-        while x <= grid_x_range.1 {
-            let line_start = Vec3::new(x * scale, grid_y_range.0 * scale, 0.0) + position_vec;
-            let line_end = Vec3::new(x * scale, grid_y_range.1 * scale, 0.0) + position_vec;
-            gizmos.line(line_start, line_end, grid_color);
-            x += grid.spacing;
+        let (major, minor) = grid_segments(grid, position, &coordinate_system);
+        let (major_color, minor_color) = grid_colors(grid, style);
+        for (start, end) in major {
+            gizmos.line_2d(start, end, major_color);
         }
-
-        // 水平网格线
-        let mut y = (grid_y_range.0 / grid.spacing).ceil() * grid.spacing;
-        while y <= grid_y_range.1 {
-            let line_start = Vec3::new(grid_x_range.0 * scale, y * scale, 0.0) + position_vec;
-            let line_end = Vec3::new(grid_x_range.1 * scale, y * scale, 0.0) + position_vec;
-            gizmos.line(line_start, line_end, grid_color);
-            y += grid.spacing;
-        }
-
-        // 次网格线（更细的网格）
-        if grid.show_minor_grid && grid.minor_spacing > 0.0 {
-            let minor_color = Color::srgba(
-                style.stroke_color.to_srgba().red,
-                style.stroke_color.to_srgba().green,
-                style.stroke_color.to_srgba().blue,
-                grid.opacity * 0.3, // 次网格更透明
-            );
-
-            // 垂直次网格线
-            let mut x = (grid_x_range.0 / grid.minor_spacing).ceil() * grid.minor_spacing;
-            while x <= grid_x_range.1 {
-                // 只绘制不与主网格重叠的线
-                if (x % grid.spacing).abs() > 0.01 {
-                    let line_start =
-                        Vec3::new(x * scale, grid_y_range.0 * scale, 0.0) + position_vec;
-                    let line_end = Vec3::new(x * scale, grid_y_range.1 * scale, 0.0) + position_vec;
-                    gizmos.line(line_start, line_end, minor_color);
-                }
-                x += grid.minor_spacing;
-            }
-
-            // 水平次网格线
-            let mut y = (grid_y_range.0 / grid.minor_spacing).ceil() * grid.minor_spacing;
-            while y <= grid_y_range.1 {
-                // 只绘制不与主网格重叠的线
-                if (y % grid.spacing).abs() > 0.01 {
-                    let line_start =
-                        Vec3::new(grid_x_range.0 * scale, y * scale, 0.0) + position_vec;
-                    let line_end = Vec3::new(grid_x_range.1 * scale, y * scale, 0.0) + position_vec;
-                    gizmos.line(line_start, line_end, minor_color);
-                }
-                y += grid.minor_spacing;
-            }
+        for (start, end) in minor {
+            gizmos.line_2d(start, end, minor_color);
         }
     }
 }
 
+/// 渲染形状所需的组件：形状、位置、变换（旋转和缩放）、样式、可见性和显示进度
+type ShapeComponents<'a, T> = (
+    &'a T,
+    &'a Position2D,
+    &'a Transform,
+    &'a MathStyle,
+    &'a InheritedVisibility,
+    Option<&'a RevealProgress>,
+);
+
+/// 变形中的对象由 render_morph_outlines 绘制
+type ShapeFilter = (With<MathObject>, Without<MorphOutline>);
+
 /// 渲染圆形的系统
-pub fn render_circles(mut gizmos: Gizmos, circles: Query<(&MathCircle, &Transform, &Visibility)>) {
-    for (circle, transform, visibility) in circles.iter() {
-        if *visibility == Visibility::Hidden {
+pub fn render_circles(
+    mut gizmos: Gizmos,
+    circles: Query<ShapeComponents<MathCircle>, ShapeFilter>,
+    coordinate_system: Res<CoordinateSystem>,
+) {
+    for (circle, position, transform, style, visibility, reveal) in circles.iter() {
+        if !visibility.get() {
             continue;
         }
 
-        let position = transform.translation.truncate();
-        // 应用与坐标轴相同的缩放因子，保持坐标系一致性
-        let scale = 50.0;
-        let scaled_position = position * scale;
-        let scaled_radius = circle.radius * scale;
+        // 通过坐标映射换算，保持与坐标轴一致的缩放和平移
+        let scaled_position = coordinate_system.to_screen(Vec2::new(position.x, position.y));
+        let scaled_radius = coordinate_system.to_screen_length(circle.radius * transform.scale.x);
 
         // 使用用户指定的分辨率，或根据圆形大小动态调整分辨率
         let resolution = circle.resolution.unwrap_or_else(|| {
@@ -238,34 +300,289 @@ pub fn render_circles(mut gizmos: Gizmos, circles: Query<(&MathCircle, &Transfor
             }
         });
 
-        if circle.filled {
+        let reveal = reveal.cloned().unwrap_or_default();
+        // 与其它图形一致使用样式的描边颜色，变形动画可以让颜色随之过渡
+        let color = stroke_color(style);
+
+        if circle.filled && reveal.fill > 0.0 {
             // 使用Bevy原生的circle_2d绘制填充圆形
             // 先绘制填充部分（使用稍微透明的颜色）
-            let fill_color = circle.color.with_alpha(0.6);
+            let fill_color = color.with_alpha(color.alpha() * 0.6 * reveal.fill);
             gizmos
                 .circle_2d(scaled_position, scaled_radius, fill_color)
                 .resolution(resolution);
+        }
 
+        if reveal.stroke >= 1.0 {
             // 再绘制边框以增强视觉效果
             gizmos
-                .circle_2d(scaled_position, scaled_radius, circle.color)
+                .circle_2d(scaled_position, scaled_radius, color)
                 .resolution(resolution);
         } else {
-            // 只绘制轮廓
-            gizmos
-                .circle_2d(scaled_position, scaled_radius, circle.color)
-                .resolution(resolution);
+            // 描边动画中只绘制部分轮廓
+            let points = screen_points(
+                &circle.outline(resolution),
+                position,
+                transform,
+                &coordinate_system,
+            );
+            draw_polyline(&mut gizmos, &points, color, GIZMO_LINE_WIDTH, reveal.stroke);
         }
     }
 }
 
+/// 渲染直线的系统（直线以 Position2D 为中点，移动 Position2D 即平移直线）
+pub fn render_lines(
+    mut gizmos: Gizmos,
+    lines: Query<ShapeComponents<Line>, ShapeFilter>,
+    coordinate_system: Res<CoordinateSystem>,
+) {
+    for (line, position, transform, style, visibility, reveal) in lines.iter() {
+        if !visibility.get() {
+            continue;
+        }
+
+        let points = screen_points(&line.outline(), position, transform, &coordinate_system);
+        draw_polyline(
+            &mut gizmos,
+            &points,
+            stroke_color(style),
+            style.stroke_width,
+            reveal.map_or(1.0, |reveal| reveal.stroke),
+        );
+    }
+}
+
+/// 渲染矩形的系统（矩形以 Position2D 为中心）
+pub fn render_rectangles(
+    mut gizmos: Gizmos,
+    rectangles: Query<ShapeComponents<MathRectangle>, ShapeFilter>,
+    coordinate_system: Res<CoordinateSystem>,
+) {
+    for (rectangle, position, transform, style, visibility, reveal) in rectangles.iter() {
+        if !visibility.get() {
+            continue;
+        }
+
+        let reveal = reveal.cloned().unwrap_or_default();
+        let outline = screen_points(
+            &rectangle.outline(),
+            position,
+            transform,
+            &coordinate_system,
+        );
+
+        // 先绘制填充，再绘制边框，保证边框在最上层
+        if let Some(fill) = fill_color(style) {
+            if reveal.fill > 0.0 {
+                let fill = fill.with_alpha(fill.alpha() * reveal.fill);
+                fill_quad(
+                    &mut gizmos,
+                    [outline[0], outline[1], outline[2], outline[3]],
+                    fill,
+                );
+            }
+        }
+
+        draw_polyline(
+            &mut gizmos,
+            &outline,
+            stroke_color(style),
+            style.stroke_width,
+            reveal.stroke,
+        );
+    }
+}
+
+/// 渲染函数图形的系统
+pub fn render_function_graphs(
+    mut gizmos: Gizmos,
+    graphs: Query<ShapeComponents<FunctionGraph>, ShapeFilter>,
+    coordinate_system: Res<CoordinateSystem>,
+) {
+    for (graph, position, transform, style, visibility, reveal) in graphs.iter() {
+        if !visibility.get() {
+            continue;
+        }
+
+        let points = screen_points(&graph.points, position, transform, &coordinate_system);
+        draw_polyline(
+            &mut gizmos,
+            &points,
+            stroke_color(style),
+            style.stroke_width,
+            reveal.map_or(1.0, |reveal| reveal.stroke),
+        );
+    }
+}
+
+/// 渲染参数曲线的系统
+pub fn render_parametric_curves(
+    mut gizmos: Gizmos,
+    curves: Query<ShapeComponents<ParametricCurve>, ShapeFilter>,
+    coordinate_system: Res<CoordinateSystem>,
+) {
+    for (curve, position, transform, style, visibility, reveal) in curves.iter() {
+        if !visibility.get() {
+            continue;
+        }
+
+        let points = screen_points(&curve.points, position, transform, &coordinate_system);
+        draw_polyline(
+            &mut gizmos,
+            &points,
+            stroke_color(style),
+            style.stroke_width,
+            reveal.map_or(1.0, |reveal| reveal.stroke),
+        );
+    }
+}
+
+/// 渲染变形中对象的系统：绘制 MorphOutline 代替对象自身的形状
+pub fn render_morph_outlines(
+    mut gizmos: Gizmos,
+    outlines: Query<ShapeComponents<MorphOutline>, With<MathObject>>,
+    coordinate_system: Res<CoordinateSystem>,
+) {
+    for (outline, position, transform, style, visibility, reveal) in outlines.iter() {
+        if !visibility.get() {
+            continue;
+        }
+
+        let points = screen_points(&outline.points, position, transform, &coordinate_system);
+        draw_polyline(
+            &mut gizmos,
+            &points,
+            stroke_color(style),
+            style.stroke_width,
+            reveal.map_or(1.0, |reveal| reveal.stroke),
+        );
+    }
+}
+
+/// 把以 Position2D 为原点的局部坐标换算为屏幕坐标
+///
+/// 先应用 Transform 的缩放和旋转，再平移到 Position2D。NaN 断点原样保留。
+pub fn screen_points(
+    points: &[Vec2],
+    position: &Position2D,
+    transform: &Transform,
+    coordinate_system: &CoordinateSystem,
+) -> Vec<Vec2> {
+    let center = Vec2::new(position.x, position.y);
+    let scale = transform.scale.truncate();
+    points
+        .iter()
+        .map(|point| {
+            let local = (transform.rotation * (*point * scale).extend(0.0)).truncate();
+            coordinate_system.to_screen(center + local)
+        })
+        .collect()
+}
+
+/// 绘制屏幕坐标折线，只绘制前 progress 比例的弧长
+///
+/// 遇到 NaN 或无穷大的点时断开折线，例如 ln(x) 在 x ≤ 0 处的采样。
+fn draw_polyline(gizmos: &mut Gizmos, points: &[Vec2], color: Color, width: f32, progress: f32) {
+    let mut remaining = if progress >= 1.0 {
+        f32::INFINITY
+    } else {
+        polyline_length(points) * progress.max(0.0)
+    };
+    let mut previous: Option<Vec2> = None;
+
+    for point in points {
+        if !point.is_finite() {
+            previous = None;
+            continue;
+        }
+
+        if let Some(start) = previous {
+            let length = start.distance(*point);
+            if length >= remaining {
+                if remaining > 0.0 {
+                    let end = start.lerp(*point, remaining / length);
+                    draw_stroke_segment(gizmos, start, end, color, width);
+                }
+                return;
+            }
+            remaining -= length;
+            draw_stroke_segment(gizmos, start, *point, color, width);
+        }
+        previous = Some(*point);
+    }
+}
+
+/// 应用样式透明度后的描边颜色
+pub fn stroke_color(style: &MathStyle) -> Color {
+    style
+        .stroke_color
+        .with_alpha(style.stroke_color.alpha() * style.opacity)
+}
+
+/// 应用样式透明度后的填充颜色，无填充时返回 None
+pub fn fill_color(style: &MathStyle) -> Option<Color> {
+    style
+        .fill_color
+        .map(|color| color.with_alpha(color.alpha() * style.opacity))
+}
+
+/// 按指定线宽绘制线段
+///
+/// Gizmos 的线宽是全局配置，这里用沿法线方向平移的平行线叠加出更粗的线。
+fn draw_stroke_segment(gizmos: &mut Gizmos, start: Vec2, end: Vec2, color: Color, width: f32) {
+    let passes = (width / GIZMO_LINE_WIDTH).ceil().max(1.0) as i32;
+    if passes == 1 {
+        gizmos.line_2d(start, end, color);
+        return;
+    }
+
+    let normal = (end - start).perp().normalize_or_zero();
+    let half = (passes - 1) as f32 * 0.5;
+    for i in 0..passes {
+        let offset = normal * (i as f32 - half) * GIZMO_LINE_WIDTH;
+        gizmos.line_2d(start + offset, end + offset, color);
+    }
+}
+
+/// 用平行扫描线填充四边形（角点按顺序排列，支持旋转后的矩形）
+fn fill_quad(gizmos: &mut Gizmos, corners: [Vec2; 4], color: Color) {
+    let height = corners[0]
+        .distance(corners[3])
+        .max(corners[1].distance(corners[2]));
+    let steps = (height / GIZMO_LINE_WIDTH).ceil().max(1.0) as u32;
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        gizmos.line_2d(
+            corners[0].lerp(corners[3], t),
+            corners[1].lerp(corners[2], t),
+            color,
+        );
+    }
+}
+
+/// 计算坐标轴名称标签的屏幕位置（x、y 标签贴近视口边缘的箭头，原点标签贴近原点）
+fn axis_name_label_translation(
+    axis: &str,
+    origin: Vec2,
+    coordinate_system: &CoordinateSystem,
+) -> Vec3 {
+    let viewport_half = coordinate_system.viewport_size * 0.5;
+    match axis {
+        "x" => Vec3::new(viewport_half.x - 45.0, origin.y - 20.0, 1.0),
+        "y" => Vec3::new(origin.x - 20.0, viewport_half.y - 45.0, 1.0),
+        _ => Vec3::new(origin.x - 15.0, origin.y - 15.0, 1.0),
+    }
+}
+
 /// 生成坐标轴标签的系统
 fn spawn_axis_labels(
     mut commands: Commands,
     query: Query<(Entity, &Axes, &Position2D), Added<Axes>>,
+    coordinate_system: Res<CoordinateSystem>,
 ) {
     for (axes_entity, axes, position) in query.iter() {
-        let scale = 50.0;
+        let origin = coordinate_system.to_screen(Vec2::new(position.x, position.y));
 
         // 为坐标轴实体添加子实体来显示文本标签
         commands.entity(axes_entity).with_children(|parent| {
@@ -277,7 +594,11 @@ fn spawn_axis_labels(
                     ..default()
                 },
                 TextColor(Color::WHITE),
-                Transform::from_translation(Vec3::new(axes.x_range.1 * scale + 25.0, -15.0, 1.0)),
+                Transform::from_translation(axis_name_label_translation(
+                    "x",
+                    origin,
+                    &coordinate_system,
+                )),
                 Visibility::Inherited,
                 AxisNameLabel {
                     axis: "x".to_string(),
@@ -292,7 +613,11 @@ fn spawn_axis_labels(
                     ..default()
                 },
                 TextColor(Color::WHITE),
-                Transform::from_translation(Vec3::new(-15.0, axes.y_range.1 * scale + 25.0, 1.0)),
+                Transform::from_translation(axis_name_label_translation(
+                    "y",
+                    origin,
+                    &coordinate_system,
+                )),
                 Visibility::Inherited,
                 AxisNameLabel {
                     axis: "y".to_string(),
@@ -307,7 +632,11 @@ fn spawn_axis_labels(
                     ..default()
                 },
                 TextColor(Color::WHITE),
-                Transform::from_translation(Vec3::new(-15.0, -15.0, 1.0)),
+                Transform::from_translation(axis_name_label_translation(
+                    "origin",
+                    origin,
+                    &coordinate_system,
+                )),
                 Visibility::Inherited,
                 AxisNameLabel {
                     axis: "origin".to_string(),
@@ -323,9 +652,11 @@ fn update_axis_labels(
     axes_query: Query<(Entity, &Axes, &Position2D), Changed<Axes>>,
     label_query: Query<Entity, With<AxisLabel>>,
     mut name_label_query: Query<(&mut Transform, &AxisNameLabel), Without<Axes>>,
+    coordinate_system: Res<CoordinateSystem>,
 ) {
-    for (axes_entity, axes, _position) in axes_query.iter() {
-        let scale = 50.0;
+    for (axes_entity, axes, position) in axes_query.iter() {
+        let position_vec = Vec2::new(position.x, position.y);
+        let origin = coordinate_system.to_screen(position_vec);
 
         // 删除旧的数字标签
         for label_entity in label_query.iter() {
@@ -334,19 +665,8 @@ fn update_axis_labels(
 
         // 更新坐标轴名称标签的位置
         for (mut transform, name_label) in name_label_query.iter_mut() {
-            match name_label.axis.as_str() {
-                "x" => {
-                    transform.translation = Vec3::new(axes.x_range.1 * scale + 25.0, -15.0, 1.0);
-                }
-                "y" => {
-                    transform.translation = Vec3::new(-15.0, axes.y_range.1 * scale + 25.0, 1.0);
-                }
-                "origin" => {
-                    // 原点标签位置保持不变
-                    transform.translation = Vec3::new(-15.0, -15.0, 1.0);
-                }
-                _ => {}
-            }
+            transform.translation =
+                axis_name_label_translation(&name_label.axis, origin, &coordinate_system);
         }
 
         // 创建新的数字标签
@@ -372,7 +692,11 @@ fn update_axis_labels(
                                 ..default()
                             },
                             TextColor(Color::srgba(0.8, 0.8, 0.8, 1.0)),
-                            Transform::from_translation(Vec3::new(x * scale, -25.0, 1.0)),
+                            Transform::from_translation(
+                                (coordinate_system.to_screen(position_vec + Vec2::new(x, 0.0))
+                                    + Vec2::new(0.0, -25.0))
+                                .extend(1.0),
+                            ),
                             Visibility::Inherited,
                             AxisLabel {
                                 axis: "x".to_string(),
@@ -403,7 +727,11 @@ fn update_axis_labels(
                                 ..default()
                             },
                             TextColor(Color::srgba(0.8, 0.8, 0.8, 1.0)),
-                            Transform::from_translation(Vec3::new(-30.0, y * scale, 1.0)),
+                            Transform::from_translation(
+                                (coordinate_system.to_screen(position_vec + Vec2::new(0.0, y))
+                                    + Vec2::new(-30.0, 0.0))
+                                .extend(1.0),
+                            ),
                             Visibility::Inherited,
                             AxisLabel {
                                 axis: "y".to_string(),
@@ -0,0 +1,1910 @@
+use super::script::{is_script_path, parse_script, ScriptError};
+use super::{in_scene, MathScene, SceneContent};
+use crate::animation::{
+    create_animation, create_value_label, create_value_tracker, AnimationBase, AnimationType,
+    MathAnimation, RateFunction, Timeline, TimelineBlock, Updater, ValueTracker,
+};
+use crate::interaction::CameraState;
+use crate::math_objects::basic_shapes::Rectangle as MathRectangle;
+use crate::math_objects::{
+    create_axes_with_labels, create_circle_with_resolution, create_function_graph_from_expression,
+    create_grid, create_line, create_parametric_curve_from_expressions, create_rectangle, Axes,
+    Expression, FunctionGraph, GraphParameter, Grid, Line, MathCircle, MathObject, MathPath,
+    ParametricCurve, Position2D, Style as MathStyle,
+};
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::SystemTime;
+use thiserror::Error;
+
+/// 场景文件格式版本，格式发生不兼容的变化时递增
+pub const SCENE_FILE_VERSION: u32 = 1;
+
+/// 场景文件读写错误
+#[derive(Debug, Error)]
+pub enum SceneFileError {
+    #[error("无法读写场景文件 {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("{path}: {message}（第 {line} 行第 {column} 列）")]
+    Json {
+        /// 出错位置的 JSON 路径，例如 `$.objects[2].style.opacity`
+        path: String,
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    #[error("不支持的场景文件版本 {found}，当前版本为 {SCENE_FILE_VERSION}")]
+    UnsupportedVersion { found: u32 },
+    #[error("{path}: {message}")]
+    Invalid { path: String, message: String },
+    #[error("场景序列化失败: {0}")]
+    Serialize(#[from] serde_json::Error),
+    #[error("场景脚本错误，{0}")]
+    Script(#[from] ScriptError),
+    #[error("{path} 是场景脚本，只能加载；请保存为 .json 文件")]
+    ReadOnlyScript { path: String },
+}
+
+/// 场景文件：场景设置、相机、全部数学对象、动画和时间轴
+///
+/// 对象之间通过 MathObject 的 id 引用，数值追踪器与对象共用 id 空间，
+/// 时间轴通过 animations 中的下标引用动画。自定义更新器（闭包）无法保存。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SceneFile {
+    pub version: u32,
+    pub scene: SceneSettings,
+    pub camera: CameraSettings,
+    pub objects: Vec<ObjectData>,
+    #[serde(default)]
+    pub trackers: Vec<TrackerData>,
+    #[serde(default)]
+    pub updaters: Vec<UpdaterData>,
+    #[serde(default)]
+    pub animations: Vec<AnimationData>,
+    #[serde(default)]
+    pub timeline: TimelineData,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SceneSettings {
+    pub name: String,
+    /// sRGBA
+    pub background_color: [f32; 4],
+    pub unit_size: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CameraSettings {
+    pub zoom: f32,
+    /// 屏幕中心对应的数学坐标
+    pub translation: [f32; 2],
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct StyleData {
+    /// sRGBA
+    pub stroke_color: [f32; 4],
+    pub fill_color: Option<[f32; 4]>,
+    pub stroke_width: f32,
+    pub opacity: f32,
+}
+
+/// 一个数学对象；动画开始前的状态保存在这里，动画效果由播放头重新求出
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ObjectData {
+    pub id: String,
+    pub layer: i32,
+    pub visible: bool,
+    pub position: [f32; 2],
+    #[serde(default = "unit_scale")]
+    pub scale: [f32; 2],
+    /// 绕 Z 轴的旋转角（弧度）
+    #[serde(default)]
+    pub rotation: f32,
+    pub style: StyleData,
+    pub shape: ShapeData,
+}
+
+/// 对象的几何数据
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ShapeData {
+    Circle {
+        radius: f32,
+        filled: bool,
+        resolution: Option<u32>,
+    },
+    Line {
+        start: [f32; 2],
+        end: [f32; 2],
+    },
+    Rectangle {
+        width: f32,
+        height: f32,
+    },
+    FunctionGraph {
+        expression: String,
+        domain: [f32; 2],
+        sample_count: u32,
+        tolerance: f32,
+        max_depth: u32,
+        parameters: Vec<ParameterData>,
+    },
+    ParametricCurve {
+        x: String,
+        y: String,
+        range: [f32; 2],
+        sample_count: u32,
+        parameters: Vec<ParameterData>,
+    },
+    Axes {
+        x_range: [f32; 2],
+        y_range: [f32; 2],
+        tick_spacing: f32,
+        show_numbers: bool,
+        show_arrows: bool,
+        x_label: String,
+        y_label: String,
+        base_range: [f32; 2],
+    },
+    Grid {
+        spacing: f32,
+        opacity: f32,
+        show_minor_grid: bool,
+        minor_spacing: f32,
+        base_spacing: f32,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ParameterData {
+    pub name: String,
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// 数值追踪器，可作为 SetValue 动画的目标
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TrackerData {
+    pub id: String,
+    pub value: f32,
+}
+
+/// 内置更新器，引用字段为对象或追踪器的 id
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum UpdaterData {
+    /// object 移动到函数图形 graph 上 x = 追踪值 的点
+    PointOnGraph {
+        object: String,
+        graph: String,
+        tracker: String,
+    },
+    /// 直线 object 变为 graph 在 x = 追踪值 处的切线
+    TangentLine {
+        object: String,
+        graph: String,
+        tracker: String,
+        length: f32,
+    },
+    /// 数值标签不是数学对象，加载时由更新器生成
+    ValueLabel {
+        tracker: String,
+        prefix: String,
+        decimals: usize,
+        anchor: Option<String>,
+        /// 屏幕像素偏移
+        offset: [f32; 2],
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AnimationData {
+    /// 目标对象或追踪器的 id
+    pub target: String,
+    pub animation: AnimationTypeData,
+    pub rate_function: RateFunction,
+    pub duration: f32,
+    #[serde(default)]
+    pub elapsed: f32,
+    #[serde(default)]
+    pub is_playing: bool,
+    #[serde(default)]
+    pub loop_animation: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AnimationTypeData {
+    Transform {
+        shift: [f32; 2],
+        scale: f32,
+        rotation: f32,
+    },
+    Fade {
+        from: f32,
+        to: f32,
+    },
+    Draw,
+    Write,
+    Morph {
+        /// 展平后的子路径
+        target: Vec<Vec<[f32; 2]>>,
+        style: Option<StyleData>,
+    },
+    SetValue {
+        to: f32,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TimelineData {
+    pub blocks: Vec<BlockData>,
+    pub playhead: f32,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+}
+
+impl Default for TimelineData {
+    fn default() -> Self {
+        Self {
+            blocks: Vec::new(),
+            playhead: 0.0,
+            speed: default_speed(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BlockData {
+    /// animations 中的下标
+    Play {
+        animation: usize,
+    },
+    Wait {
+        seconds: f32,
+    },
+    Sequence {
+        blocks: Vec<BlockData>,
+    },
+    Group {
+        blocks: Vec<BlockData>,
+    },
+    LaggedStart {
+        lag: f32,
+        blocks: Vec<BlockData>,
+    },
+}
+
+impl TimelineData {
+    /// 转换为时间轴，animations 为按文件顺序生成的动画实体
+    pub fn to_timeline(&self, animations: &[Entity]) -> Timeline {
+        let mut timeline = Timeline::default();
+        for block in &self.blocks {
+            timeline.push(block.to_block(animations));
+        }
+        timeline.playhead = self.playhead;
+        timeline.speed = self.speed;
+        timeline
+    }
+}
+
+fn unit_scale() -> [f32; 2] {
+    [1.0, 1.0]
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+impl From<&MathStyle> for StyleData {
+    fn from(style: &MathStyle) -> Self {
+        Self {
+            stroke_color: style.stroke_color.to_srgba().to_f32_array(),
+            fill_color: style
+                .fill_color
+                .map(|color| color.to_srgba().to_f32_array()),
+            stroke_width: style.stroke_width,
+            opacity: style.opacity,
+        }
+    }
+}
+
+impl From<&StyleData> for MathStyle {
+    fn from(style: &StyleData) -> Self {
+        Self {
+            stroke_color: Srgba::from_f32_array(style.stroke_color).into(),
+            fill_color: style
+                .fill_color
+                .map(|color| Srgba::from_f32_array(color).into()),
+            stroke_width: style.stroke_width,
+            opacity: style.opacity,
+        }
+    }
+}
+
+impl AnimationTypeData {
+    fn from_animation_type(animation_type: &AnimationType) -> Self {
+        match animation_type {
+            AnimationType::Transform {
+                shift,
+                scale,
+                rotation,
+            } => AnimationTypeData::Transform {
+                shift: shift.to_array(),
+                scale: *scale,
+                rotation: *rotation,
+            },
+            AnimationType::Fade { from, to } => AnimationTypeData::Fade {
+                from: *from,
+                to: *to,
+            },
+            AnimationType::Draw => AnimationTypeData::Draw,
+            AnimationType::Write => AnimationTypeData::Write,
+            AnimationType::Morph { target, style } => AnimationTypeData::Morph {
+                target: target
+                    .subpaths()
+                    .iter()
+                    .map(|subpath| subpath.iter().map(|point| point.to_array()).collect())
+                    .collect(),
+                style: style.as_ref().map(StyleData::from),
+            },
+            AnimationType::SetValue { to } => AnimationTypeData::SetValue { to: *to },
+        }
+    }
+
+    fn to_animation_type(&self) -> AnimationType {
+        match self {
+            AnimationTypeData::Transform {
+                shift,
+                scale,
+                rotation,
+            } => AnimationType::Transform {
+                shift: Vec2::from_array(*shift),
+                scale: *scale,
+                rotation: *rotation,
+            },
+            AnimationTypeData::Fade { from, to } => AnimationType::Fade {
+                from: *from,
+                to: *to,
+            },
+            AnimationTypeData::Draw => AnimationType::Draw,
+            AnimationTypeData::Write => AnimationType::Write,
+            AnimationTypeData::Morph { target, style } => {
+                // 子路径之间以 NaN 断开
+                let mut points = Vec::new();
+                for subpath in target {
+                    if !points.is_empty() {
+                        points.push(Vec2::NAN);
+                    }
+                    points.extend(subpath.iter().map(|point| Vec2::from_array(*point)));
+                }
+                AnimationType::Morph {
+                    target: MathPath::from_polyline(&points, false),
+                    style: style.as_ref().map(MathStyle::from),
+                }
+            }
+            AnimationTypeData::SetValue { to } => AnimationType::SetValue { to: *to },
+        }
+    }
+}
+
+impl BlockData {
+    /// 由时间轴块转换，引用未保存动画的 Play 块被丢弃
+    fn from_block(block: &TimelineBlock, indices: &HashMap<Entity, usize>) -> Option<Self> {
+        let blocks = |blocks: &[TimelineBlock]| {
+            blocks
+                .iter()
+                .filter_map(|block| BlockData::from_block(block, indices))
+                .collect()
+        };
+        Some(match block {
+            TimelineBlock::Play(entity) => BlockData::Play {
+                animation: *indices.get(entity)?,
+            },
+            TimelineBlock::Wait(seconds) => BlockData::Wait { seconds: *seconds },
+            TimelineBlock::Sequence(children) => BlockData::Sequence {
+                blocks: blocks(children),
+            },
+            TimelineBlock::Group(children) => BlockData::Group {
+                blocks: blocks(children),
+            },
+            TimelineBlock::LaggedStart {
+                lag,
+                blocks: children,
+            } => BlockData::LaggedStart {
+                lag: *lag,
+                blocks: blocks(children),
+            },
+        })
+    }
+
+    /// 检查动画下标是否有效，path 为该块的 JSON 路径
+    fn validate(&self, path: &str, animation_count: usize) -> Result<(), SceneFileError> {
+        let children = match self {
+            BlockData::Play { animation } if *animation >= animation_count => {
+                return Err(SceneFileError::Invalid {
+                    path: format!("{path}.animation"),
+                    message: format!(
+                        "动画下标 {} 超出范围（共 {} 个动画）",
+                        animation, animation_count
+                    ),
+                });
+            }
+            BlockData::Play { .. } | BlockData::Wait { .. } => return Ok(()),
+            BlockData::Sequence { blocks }
+            | BlockData::Group { blocks }
+            | BlockData::LaggedStart { blocks, .. } => blocks,
+        };
+        for (i, block) in children.iter().enumerate() {
+            block.validate(&format!("{path}.blocks[{i}]"), animation_count)?;
+        }
+        Ok(())
+    }
+
+    fn to_block(&self, animations: &[Entity]) -> TimelineBlock {
+        let blocks = |blocks: &[BlockData]| {
+            blocks
+                .iter()
+                .map(|block| block.to_block(animations))
+                .collect()
+        };
+        match self {
+            BlockData::Play { animation } => TimelineBlock::Play(animations[*animation]),
+            BlockData::Wait { seconds } => TimelineBlock::Wait(*seconds),
+            BlockData::Sequence { blocks: children } => TimelineBlock::Sequence(blocks(children)),
+            BlockData::Group { blocks: children } => TimelineBlock::Group(blocks(children)),
+            BlockData::LaggedStart {
+                lag,
+                blocks: children,
+            } => TimelineBlock::LaggedStart {
+                lag: *lag,
+                blocks: blocks(children),
+            },
+        }
+    }
+}
+
+impl SceneFile {
+    /// 解析场景文件，错误信息包含出错位置的 JSON 路径
+    pub fn parse(source: &str) -> Result<Self, SceneFileError> {
+        // 先只读取版本号，避免旧版本文件报出令人费解的字段错误
+        #[derive(Deserialize)]
+        struct VersionProbe {
+            version: u32,
+        }
+        let probe: VersionProbe =
+            serde_json::from_str(source).map_err(|error| json_error(source, error))?;
+        if probe.version != SCENE_FILE_VERSION {
+            return Err(SceneFileError::UnsupportedVersion {
+                found: probe.version,
+            });
+        }
+
+        let file: SceneFile =
+            serde_json::from_str(source).map_err(|error| json_error(source, error))?;
+        file.validate()?;
+        Ok(file)
+    }
+
+    /// 序列化为带缩进的 JSON
+    pub fn to_json(&self) -> Result<String, SceneFileError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// 检查缩放和单位长度的取值范围、id 引用、表达式和时间轴下标
+    fn validate(&self) -> Result<(), SceneFileError> {
+        for (path, value) in [
+            ("$.scene.unit_size", self.scene.unit_size),
+            ("$.camera.zoom", self.camera.zoom),
+        ] {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(SceneFileError::Invalid {
+                    path: path.to_string(),
+                    message: format!("取值 {} 必须是正数", value),
+                });
+            }
+        }
+
+        let mut ids = HashSet::new();
+        for (i, object) in self.objects.iter().enumerate() {
+            if !ids.insert(object.id.as_str()) {
+                return Err(SceneFileError::Invalid {
+                    path: format!("$.objects[{i}].id"),
+                    message: format!("对象 id '{}' 重复", object.id),
+                });
+            }
+
+            let expressions: &[(&str, &String)] = match &object.shape {
+                ShapeData::FunctionGraph { expression, .. } => &[("expression", expression)],
+                ShapeData::ParametricCurve { x, y, .. } => &[("x", x), ("y", y)],
+                _ => &[],
+            };
+            for (field, source) in expressions {
+                if let Err(error) = Expression::parse(source) {
+                    return Err(SceneFileError::Invalid {
+                        path: format!("$.objects[{i}].shape.{field}"),
+                        message: error.to_string(),
+                    });
+                }
+            }
+        }
+
+        let graphs: HashSet<&str> = self
+            .objects
+            .iter()
+            .filter(|object| matches!(object.shape, ShapeData::FunctionGraph { .. }))
+            .map(|object| object.id.as_str())
+            .collect();
+        let mut trackers = HashSet::new();
+        for (i, tracker) in self.trackers.iter().enumerate() {
+            if ids.contains(tracker.id.as_str()) || !trackers.insert(tracker.id.as_str()) {
+                return Err(SceneFileError::Invalid {
+                    path: format!("$.trackers[{i}].id"),
+                    message: format!("追踪器 id '{}' 重复", tracker.id),
+                });
+            }
+        }
+
+        for (i, updater) in self.updaters.iter().enumerate() {
+            let (object, graph, tracker, anchor) = match updater {
+                UpdaterData::PointOnGraph {
+                    object,
+                    graph,
+                    tracker,
+                }
+                | UpdaterData::TangentLine {
+                    object,
+                    graph,
+                    tracker,
+                    ..
+                } => (Some(object), Some(graph), tracker, None),
+                UpdaterData::ValueLabel {
+                    tracker, anchor, ..
+                } => (None, None, tracker, anchor.as_ref()),
+            };
+            for (field, id) in [("object", object), ("anchor", anchor)] {
+                if let Some(id) = id.filter(|id| !ids.contains(id.as_str())) {
+                    return Err(SceneFileError::Invalid {
+                        path: format!("$.updaters[{i}].{field}"),
+                        message: format!("找不到 id 为 '{}' 的对象", id),
+                    });
+                }
+            }
+            if let Some(graph) = graph.filter(|graph| !graphs.contains(graph.as_str())) {
+                return Err(SceneFileError::Invalid {
+                    path: format!("$.updaters[{i}].graph"),
+                    message: format!("找不到 id 为 '{}' 的函数图形", graph),
+                });
+            }
+            if !trackers.contains(tracker.as_str()) {
+                return Err(SceneFileError::Invalid {
+                    path: format!("$.updaters[{i}].tracker"),
+                    message: format!("找不到 id 为 '{}' 的追踪器", tracker),
+                });
+            }
+        }
+
+        for (i, animation) in self.animations.iter().enumerate() {
+            if !ids.contains(animation.target.as_str())
+                && !trackers.contains(animation.target.as_str())
+            {
+                return Err(SceneFileError::Invalid {
+                    path: format!("$.animations[{i}].target"),
+                    message: format!("找不到 id 为 '{}' 的对象", animation.target),
+                });
+            }
+        }
+
+        for (i, block) in self.timeline.blocks.iter().enumerate() {
+            block.validate(&format!("$.timeline.blocks[{i}]"), self.animations.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// 把 serde_json 的错误转换为带 JSON 路径的错误
+fn json_error(source: &str, error: serde_json::Error) -> SceneFileError {
+    let (line, column) = (error.line(), error.column());
+    let suffix = format!(" at line {} column {}", line, column);
+    let message = error.to_string();
+    let message = message
+        .strip_suffix(&suffix)
+        .unwrap_or(&message)
+        .to_string();
+    // 缺少字段的错误位于对象的右括号处，此时路径指向该对象；shape、时间轴块等带标签
+    // 枚举的内容先缓冲再解析，其中的错误只能定位到所在的数组元素或外层对象
+    SceneFileError::Json {
+        path: json_path_at(source, line, column),
+        message,
+        line,
+        column,
+    }
+}
+
+/// JSON 路径中的一层
+enum PathFrame {
+    Object { key: Option<String> },
+    Array { index: usize },
+}
+
+/// 扫描 source 到第 line 行第 column 列的字符之前（均从 1 开始），求出该位置所在的 JSON 路径
+fn json_path_at(source: &str, line: usize, column: usize) -> String {
+    let line_start: usize = source
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    let end = (line_start + column.saturating_sub(1)).min(source.len());
+    let bytes = &source.as_bytes()[..end];
+
+    let mut stack: Vec<PathFrame> = Vec::new();
+    let mut expect_key = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => {
+                stack.push(PathFrame::Object { key: None });
+                expect_key = true;
+            }
+            b'[' => stack.push(PathFrame::Array { index: 0 }),
+            b'}' | b']' => {
+                stack.pop();
+                expect_key = false;
+            }
+            b',' => match stack.last_mut() {
+                Some(PathFrame::Array { index }) => *index += 1,
+                Some(PathFrame::Object { .. }) => expect_key = true,
+                None => {}
+            },
+            b'"' => {
+                // 读取字符串（跳过转义），对象中冒号前的字符串是键
+                let start = i + 1;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                if expect_key {
+                    if let Some(PathFrame::Object { key }) = stack.last_mut() {
+                        let end = i.min(bytes.len());
+                        *key = Some(String::from_utf8_lossy(&bytes[start..end]).into_owned());
+                    }
+                    expect_key = false;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    let next = source.as_bytes()[end..]
+        .iter()
+        .find(|byte| !byte.is_ascii_whitespace());
+    match (next, stack.last_mut()) {
+        // 位于右括号处时指向对象本身，而不是其中最后一个键
+        (Some(b'}'), Some(PathFrame::Object { key })) => *key = None,
+        // 带标签枚举的错误位于下一个元素的左括号处，归到刚结束的元素
+        (Some(b'{'), Some(PathFrame::Array { index })) => {
+            if let Some(before) = bytes.trim_ascii_end().strip_suffix(b",") {
+                if before.trim_ascii_end().ends_with(b"}") {
+                    *index = index.saturating_sub(1);
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let mut path = "$".to_string();
+    for frame in &stack {
+        match frame {
+            PathFrame::Object { key: Some(key) } => {
+                path.push('.');
+                path.push_str(key);
+            }
+            PathFrame::Object { key: None } => {}
+            PathFrame::Array { index } => path.push_str(&format!("[{index}]")),
+        }
+    }
+    path
+}
+
+/// 保存场景请求，参数为文件路径
+#[derive(Event)]
+pub struct SaveSceneRequest(pub PathBuf);
+
+/// 加载场景请求
+#[derive(Event)]
+pub struct LoadSceneRequest {
+    pub path: PathBuf,
+    /// 保留当前的相机和时间轴位置，用于热重载
+    pub keep_view: bool,
+}
+
+impl LoadSceneRequest {
+    /// 打开场景，相机和时间轴使用文件中的设置
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            keep_view: false,
+        }
+    }
+
+    /// 重新加载修改后的场景，相机和播放头保持不变
+    pub fn reload(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            keep_view: true,
+        }
+    }
+}
+
+/// 场景内容整体替换事件：加载场景文件或切换、新建、复制场景后发送，此时新的实体已经生成
+#[derive(Event)]
+pub struct SceneLoaded;
+
+/// 场景文件状态：默认路径、最近一次读写结果和热重载监视的文件
+#[derive(Resource)]
+pub struct SceneFileState {
+    pub path: String,
+    /// Ok 为成功提示，Err 为错误信息
+    pub last_result: Option<Result<String, String>>,
+    /// 加载失败时显示错误浮层，关闭或下次加载成功后隐藏
+    pub show_error: bool,
+    /// 监视最近加载的文件，修改后自动重新加载
+    pub hot_reload: bool,
+    /// 最近加载的文件及当时的修改时间
+    pub watched: Option<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Default for SceneFileState {
+    fn default() -> Self {
+        Self {
+            path: "scene.json".to_string(),
+            last_result: None,
+            show_error: false,
+            hot_reload: true,
+            watched: None,
+        }
+    }
+}
+
+/// 保存时读取的对象组件
+type SavedObject<'a> = (
+    Entity,
+    &'a MathObject,
+    &'a Position2D,
+    &'a Transform,
+    &'a MathStyle,
+    &'a Visibility,
+    Option<&'a AnimationBase>,
+    Option<&'a ChildOf>,
+    SavedShape<'a>,
+);
+
+/// 截取场景内容时读取的对象、追踪器、更新器和动画
+pub type CaptureQueries<'w, 's> = (
+    Query<'w, 's, SavedObject<'static>>,
+    Query<'w, 's, (Entity, &'static ValueTracker, Option<&'static ChildOf>)>,
+    Query<'w, 's, (Entity, &'static Updater, Option<&'static ChildOf>)>,
+    Query<'w, 's, (Entity, &'static MathAnimation)>,
+);
+
+type SavedShape<'a> = (
+    Option<&'a MathCircle>,
+    Option<&'a Line>,
+    Option<&'a MathRectangle>,
+    Option<&'a FunctionGraph>,
+    Option<&'a ParametricCurve>,
+    Option<&'a Axes>,
+    Option<&'a Grid>,
+);
+
+fn parameter_data(parameters: &[GraphParameter]) -> Vec<ParameterData> {
+    parameters
+        .iter()
+        .map(|parameter| ParameterData {
+            name: parameter.name.clone(),
+            value: parameter.value,
+            min: parameter.min,
+            max: parameter.max,
+        })
+        .collect()
+}
+
+/// 对象的几何数据，无法保存的对象（例如原生函数图形）返回 None
+fn shape_data(
+    (circle, line, rectangle, graph, curve, axes, grid): SavedShape,
+) -> Option<ShapeData> {
+    if let Some(circle) = circle {
+        return Some(ShapeData::Circle {
+            radius: circle.radius,
+            filled: circle.filled,
+            resolution: circle.resolution,
+        });
+    }
+    if let Some(line) = line {
+        return Some(ShapeData::Line {
+            start: line.start.to_array(),
+            end: line.end.to_array(),
+        });
+    }
+    if let Some(rectangle) = rectangle {
+        return Some(ShapeData::Rectangle {
+            width: rectangle.width,
+            height: rectangle.height,
+        });
+    }
+    if let Some(graph) = graph {
+        return Some(ShapeData::FunctionGraph {
+            expression: graph.function.as_ref()?.source()?.to_string(),
+            domain: [graph.domain_start, graph.domain_end],
+            sample_count: graph.sample_count,
+            tolerance: graph.tolerance,
+            max_depth: graph.max_depth,
+            parameters: parameter_data(&graph.parameters),
+        });
+    }
+    if let Some(curve) = curve {
+        return Some(ShapeData::ParametricCurve {
+            x: curve.x_function.as_ref()?.source()?.to_string(),
+            y: curve.y_function.as_ref()?.source()?.to_string(),
+            range: [curve.param_start, curve.param_end],
+            sample_count: curve.sample_count,
+            parameters: parameter_data(&curve.parameters),
+        });
+    }
+    if let Some(axes) = axes {
+        return Some(ShapeData::Axes {
+            x_range: [axes.x_range.0, axes.x_range.1],
+            y_range: [axes.y_range.0, axes.y_range.1],
+            tick_spacing: axes.tick_spacing,
+            show_numbers: axes.show_numbers,
+            show_arrows: axes.show_arrows,
+            x_label: axes.x_label.clone(),
+            y_label: axes.y_label.clone(),
+            base_range: [axes.base_range.0, axes.base_range.1],
+        });
+    }
+    grid.map(|grid| ShapeData::Grid {
+        spacing: grid.spacing,
+        opacity: grid.opacity,
+        show_minor_grid: grid.show_minor_grid,
+        minor_spacing: grid.minor_spacing,
+        base_spacing: grid.base_spacing,
+    })
+}
+
+/// 内置更新器的数据，引用了未保存的对象或为自定义更新器时返回 None
+fn builtin_updater_data(
+    entity: Entity,
+    updater: &Updater,
+    ids: &HashMap<Entity, String>,
+) -> Option<UpdaterData> {
+    let id = |entity: &Entity| ids.get(entity).cloned();
+    Some(match updater {
+        Updater::PointOnGraph { graph, tracker } => UpdaterData::PointOnGraph {
+            object: id(&entity)?,
+            graph: id(graph)?,
+            tracker: id(tracker)?,
+        },
+        Updater::TangentLine {
+            graph,
+            tracker,
+            length,
+        } => UpdaterData::TangentLine {
+            object: id(&entity)?,
+            graph: id(graph)?,
+            tracker: id(tracker)?,
+            length: *length,
+        },
+        Updater::ValueLabel {
+            tracker,
+            prefix,
+            decimals,
+            anchor,
+            offset,
+        } => UpdaterData::ValueLabel {
+            tracker: id(tracker)?,
+            prefix: prefix.clone(),
+            decimals: *decimals,
+            anchor: match anchor {
+                Some(anchor) => Some(id(anchor)?),
+                None => None,
+            },
+            offset: offset.to_array(),
+        },
+        Updater::Custom(_) => return None,
+    })
+}
+
+/// 截取场景的可保存内容，包括对象、动画、时间轴和相机
+pub fn capture_scene(
+    scene: Entity,
+    settings: &MathScene,
+    (objects, trackers, updaters, animations): &CaptureQueries,
+    timeline: &Timeline,
+    camera_state: &CameraState,
+) -> SceneFile {
+    // 按图层和 id 排序，保证同一场景每次保存的内容一致
+    let mut saved: Vec<_> = objects
+        .iter()
+        .filter(|(.., child_of, _)| in_scene(*child_of, scene, settings.active))
+        .collect();
+    saved.sort_by(|a, b| (a.1.layer, &a.1.id).cmp(&(b.1.layer, &b.1.id)));
+
+    let mut ids = HashMap::new();
+    let mut object_data = Vec::new();
+    for (entity, object, position, transform, style, visibility, base, _, shape) in saved {
+        let Some(shape) = shape_data(shape) else {
+            warn!("对象 {} 无法保存，已跳过", object.id);
+            continue;
+        };
+        // 有动画的对象保存动画开始前的状态
+        let (position, scale, rotation, style) = match base {
+            Some(AnimationBase(state)) => (
+                state.position,
+                state.scale.truncate(),
+                state.rotation,
+                state.style.clone().unwrap_or_else(|| style.clone()),
+            ),
+            None => (
+                Vec2::new(position.x, position.y),
+                transform.scale.truncate(),
+                transform.rotation.to_euler(EulerRot::ZYX).0,
+                style.clone(),
+            ),
+        };
+        ids.insert(entity, object.id.clone());
+        object_data.push(ObjectData {
+            id: object.id.clone(),
+            layer: object.layer,
+            visible: *visibility != Visibility::Hidden,
+            position: position.to_array(),
+            scale: scale.to_array(),
+            rotation,
+            style: StyleData::from(&style),
+            shape,
+        });
+    }
+
+    // 追踪器没有 id，按生成顺序命名并避开对象 id
+    let mut saved_trackers: Vec<_> = trackers
+        .iter()
+        .filter(|(.., child_of)| in_scene(*child_of, scene, settings.active))
+        .collect();
+    saved_trackers.sort_by_key(|(entity, ..)| *entity);
+    let mut tracker_data = Vec::new();
+    let mut next = 1;
+    for (entity, tracker, _) in saved_trackers {
+        let id = loop {
+            let id = format!("tracker_{next}");
+            next += 1;
+            if !ids.values().any(|taken| *taken == id) {
+                break id;
+            }
+        };
+        ids.insert(entity, id.clone());
+        tracker_data.push(TrackerData {
+            id,
+            value: tracker.value,
+        });
+    }
+
+    let mut saved_updaters: Vec<_> = updaters
+        .iter()
+        .filter(|(.., child_of)| in_scene(*child_of, scene, settings.active))
+        .collect();
+    saved_updaters.sort_by_key(|(entity, ..)| *entity);
+    let mut updater_data = Vec::new();
+    for (entity, updater, _) in saved_updaters {
+        if let Updater::Custom(_) = updater {
+            let owner = ids
+                .get(&entity)
+                .cloned()
+                .unwrap_or_else(|| entity.to_string());
+            warn!("{} 的自定义更新器无法保存，已跳过", owner);
+            continue;
+        }
+        match builtin_updater_data(entity, updater, &ids) {
+            Some(data) => updater_data.push(data),
+            None => warn!("{} 的更新器引用了未保存的对象，已跳过", entity),
+        }
+    }
+
+    // 只保留目标属于该场景的动画
+    let mut saved_animations: Vec<_> = animations
+        .iter()
+        .filter_map(|(entity, animation)| {
+            let target = ids.get(&animation.target.unwrap_or(entity))?;
+            Some((entity, target.clone(), animation))
+        })
+        .collect();
+    saved_animations.sort_by_key(|(entity, ..)| *entity);
+    let indices: HashMap<Entity, usize> = saved_animations
+        .iter()
+        .enumerate()
+        .map(|(i, (entity, ..))| (*entity, i))
+        .collect();
+    let animation_data = saved_animations
+        .into_iter()
+        .map(|(_, target, animation)| AnimationData {
+            target,
+            animation: AnimationTypeData::from_animation_type(&animation.animation_type),
+            rate_function: animation.rate_function,
+            duration: animation.duration,
+            elapsed: animation.elapsed,
+            is_playing: animation.is_playing,
+            loop_animation: animation.loop_animation,
+        })
+        .collect();
+
+    SceneFile {
+        version: SCENE_FILE_VERSION,
+        scene: SceneSettings {
+            name: settings.name.clone(),
+            background_color: settings.background_color.to_srgba().to_f32_array(),
+            unit_size: settings.unit_size,
+        },
+        camera: CameraSettings {
+            zoom: camera_state.target_zoom,
+            translation: camera_state.target_translation.to_array(),
+        },
+        objects: object_data,
+        trackers: tracker_data,
+        updaters: updater_data,
+        animations: animation_data,
+        timeline: TimelineData {
+            blocks: timeline
+                .blocks
+                .iter()
+                .filter_map(|block| BlockData::from_block(block, &indices))
+                .collect(),
+            playhead: timeline.playhead,
+            speed: timeline.speed,
+        },
+    }
+}
+
+/// 处理保存场景请求，保存当前活动场景
+pub fn handle_save_requests(
+    mut requests: EventReader<SaveSceneRequest>,
+    mut state: ResMut<SceneFileState>,
+    capture: CaptureQueries,
+    timeline: Res<Timeline>,
+    camera_state: Res<CameraState>,
+    scenes: Query<(Entity, &MathScene)>,
+) {
+    for SaveSceneRequest(path) in requests.read() {
+        let Some((scene, settings)) = scenes.iter().find(|(_, scene)| scene.active) else {
+            warn!("没有活动场景，无法保存");
+            continue;
+        };
+        let file = capture_scene(scene, settings, &capture, &timeline, &camera_state);
+
+        let result = if is_script_path(path) {
+            Err(SceneFileError::ReadOnlyScript {
+                path: path.display().to_string(),
+            })
+        } else {
+            file.to_json().and_then(|json| {
+                std::fs::write(path, json).map_err(|source| SceneFileError::Io {
+                    path: path.display().to_string(),
+                    source,
+                })
+            })
+        };
+        state.last_result = Some(match result {
+            Ok(()) => {
+                // 保存到正在监视的文件时更新修改时间，避免热重载把刚保存的场景重新加载
+                if let Some((watched, modified)) = &mut state.watched {
+                    if same_file(watched, path) {
+                        *modified = modified_time(path);
+                    }
+                }
+                info!("场景已保存到 {}", path.display());
+                Ok(format!("已保存到 {}", path.display()))
+            }
+            Err(error) => {
+                warn!("保存场景失败: {}", error);
+                Err(error.to_string())
+            }
+        });
+    }
+}
+
+/// 读取并解析场景文件，`.rim` 文件按场景脚本编译
+pub fn read_scene_file(path: &std::path::Path) -> Result<SceneFile, SceneFileError> {
+    let source = std::fs::read_to_string(path).map_err(|source| SceneFileError::Io {
+        path: path.display().to_string(),
+        source,
+    })?;
+    if is_script_path(path) {
+        Ok(parse_script(&source)?)
+    } else {
+        SceneFile::parse(&source)
+    }
+}
+
+/// 处理加载场景请求：解析成功后替换活动场景的全部内容
+pub fn handle_load_requests(
+    mut commands: Commands,
+    mut requests: EventReader<LoadSceneRequest>,
+    mut state: ResMut<SceneFileState>,
+    content: Query<(Entity, Option<&ChildOf>), SceneContent>,
+    mut timeline: ResMut<Timeline>,
+    mut camera_state: ResMut<CameraState>,
+    mut scenes: Query<(Entity, &mut MathScene)>,
+) {
+    // 同一帧多次请求时只加载最后一个
+    let Some(LoadSceneRequest { path, keep_view }) = requests.read().last() else {
+        return;
+    };
+
+    // 无论成功与否都监视该文件，修正错误并保存后会自动重新加载
+    state.watched = Some((path.clone(), modified_time(path)));
+    let file = match read_scene_file(path) {
+        Ok(file) => file,
+        Err(error) => {
+            // 加载失败时保留当前场景
+            warn!("加载场景失败: {}", error);
+            state.last_result = Some(Err(error.to_string()));
+            state.show_error = true;
+            return;
+        }
+    };
+
+    let Some((scene, mut settings)) = scenes.iter_mut().find(|(_, scene)| scene.active) else {
+        warn!("没有活动场景，无法加载");
+        return;
+    };
+    for (entity, child_of) in content.iter() {
+        if in_scene(child_of, scene, true) {
+            commands.entity(entity).despawn();
+        }
+    }
+    let animations = spawn_scene_content(&mut commands, &file, scene);
+
+    let mut loaded = file.timeline.to_timeline(&animations);
+    // 时长在 drive_timeline 重新展开后更新，播放头随后按时长截断
+    if *keep_view {
+        loaded.playhead = timeline.playhead;
+        loaded.speed = timeline.speed;
+        loaded.is_playing = timeline.is_playing;
+    } else {
+        let translation = Vec2::from_array(file.camera.translation);
+        let zoom = file
+            .camera
+            .zoom
+            .clamp(camera_state.min_zoom, camera_state.max_zoom);
+        camera_state.zoom = zoom;
+        camera_state.target_zoom = zoom;
+        camera_state.translation = translation;
+        camera_state.target_translation = translation;
+        camera_state.zoom_anchor = None;
+    }
+    *timeline = loaded;
+
+    settings.name = file.scene.name.clone();
+    settings.background_color = Srgba::from_f32_array(file.scene.background_color).into();
+    settings.unit_size = file.scene.unit_size;
+
+    commands.send_event(SceneLoaded);
+    let action = if *keep_view { "重新加载" } else { "加载" };
+    info!("已从 {} {}场景", path.display(), action);
+    state.last_result = Some(Ok(format!("已从 {} {}", path.display(), action)));
+    state.show_error = false;
+}
+
+/// 文件的修改时间，文件不存在或平台不支持时为 None
+fn modified_time(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// 两个路径是否指向同一个文件
+fn same_file(a: &std::path::Path, b: &std::path::Path) -> bool {
+    a == b
+        || matches!(
+            (std::fs::canonicalize(a), std::fs::canonicalize(b)),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
+
+/// 热重载检查文件修改时间的间隔（秒）
+const WATCH_INTERVAL: f32 = 0.5;
+
+/// 定期检查最近加载的场景文件，修改后请求重新加载
+pub fn watch_scene_file(
+    mut state: ResMut<SceneFileState>,
+    mut requests: EventWriter<LoadSceneRequest>,
+    mut since_check: Local<f32>,
+    time: Res<Time>,
+) {
+    *since_check += time.delta_secs();
+    if !state.hot_reload || *since_check < WATCH_INTERVAL {
+        return;
+    }
+    *since_check = 0.0;
+
+    let Some((path, modified)) = &mut state.watched else {
+        return;
+    };
+    let current = modified_time(path);
+    // 编辑器保存时可能先删除再写入，文件暂时不存在时等待下次检查
+    if current.is_none() || current == *modified {
+        return;
+    }
+    *modified = current;
+    info!("场景文件已修改，重新加载: {}", path.display());
+    requests.write(LoadSceneRequest::reload(path.clone()));
+}
+
+/// 在 scene 下生成场景文件中的对象和动画，返回按文件顺序排列的动画实体
+///
+/// 文件已通过校验，表达式和 id 引用都有效。追踪器先于对象生成，更新器在对象之后挂载。
+pub fn spawn_scene_content(
+    commands: &mut Commands,
+    file: &SceneFile,
+    scene: Entity,
+) -> Vec<Entity> {
+    let mut entities = HashMap::new();
+    for tracker in &file.trackers {
+        let entity = create_value_tracker(commands, tracker.value);
+        commands.entity(entity).insert(ChildOf(scene));
+        entities.insert(tracker.id.as_str(), entity);
+    }
+    for object in &file.objects {
+        let style = MathStyle::from(&object.style);
+        let position = Vec2::from_array(object.position);
+        let entity = match &object.shape {
+            ShapeData::Circle {
+                radius,
+                filled,
+                resolution,
+            } => {
+                let entity =
+                    create_circle_with_resolution(commands, position, *radius, style, *resolution);
+                let filled = *filled;
+                commands
+                    .entity(entity)
+                    .entry::<MathCircle>()
+                    .and_modify(move |mut circle| circle.filled = filled);
+                entity
+            }
+            ShapeData::Line { start, end } => create_line(
+                commands,
+                Vec2::from_array(*start),
+                Vec2::from_array(*end),
+                style,
+            ),
+            ShapeData::Rectangle { width, height } => {
+                create_rectangle(commands, position, *width, *height, style)
+            }
+            ShapeData::FunctionGraph {
+                expression,
+                domain,
+                sample_count,
+                tolerance,
+                max_depth,
+                parameters,
+            } => {
+                let Ok(entity) = create_function_graph_from_expression(
+                    commands,
+                    expression,
+                    (domain[0], domain[1]),
+                    style,
+                ) else {
+                    continue;
+                };
+                let (sample_count, tolerance, max_depth) = (*sample_count, *tolerance, *max_depth);
+                let parameters = parameters.clone();
+                commands
+                    .entity(entity)
+                    .entry::<FunctionGraph>()
+                    .and_modify(move |mut graph| {
+                        graph.sample_count = sample_count;
+                        graph.tolerance = tolerance;
+                        graph.max_depth = max_depth;
+                        apply_parameters(&mut graph.parameters, &parameters);
+                        graph.invalidate();
+                    });
+                entity
+            }
+            ShapeData::ParametricCurve {
+                x,
+                y,
+                range,
+                sample_count,
+                parameters,
+            } => {
+                let Ok(entity) = create_parametric_curve_from_expressions(
+                    commands,
+                    x,
+                    y,
+                    (range[0], range[1]),
+                    style,
+                ) else {
+                    continue;
+                };
+                let sample_count = *sample_count;
+                let parameters = parameters.clone();
+                commands
+                    .entity(entity)
+                    .entry::<ParametricCurve>()
+                    .and_modify(move |mut curve| {
+                        curve.sample_count = sample_count;
+                        apply_parameters(&mut curve.parameters, &parameters);
+                        curve.resample();
+                    });
+                entity
+            }
+            ShapeData::Axes {
+                x_range,
+                y_range,
+                tick_spacing,
+                show_numbers,
+                show_arrows,
+                x_label,
+                y_label,
+                base_range,
+            } => {
+                let entity = create_axes_with_labels(
+                    commands,
+                    (x_range[0], x_range[1]),
+                    (y_range[0], y_range[1]),
+                    x_label.clone(),
+                    y_label.clone(),
+                    style,
+                );
+                let (tick_spacing, show_numbers, show_arrows, base_range) =
+                    (*tick_spacing, *show_numbers, *show_arrows, *base_range);
+                commands
+                    .entity(entity)
+                    .entry::<Axes>()
+                    .and_modify(move |mut axes| {
+                        axes.tick_spacing = tick_spacing;
+                        axes.show_numbers = show_numbers;
+                        axes.show_arrows = show_arrows;
+                        axes.base_range = (base_range[0], base_range[1]);
+                    });
+                entity
+            }
+            ShapeData::Grid {
+                spacing,
+                opacity,
+                show_minor_grid,
+                minor_spacing,
+                base_spacing,
+            } => {
+                let entity = create_grid(commands, *spacing, style);
+                let grid = Grid {
+                    spacing: *spacing,
+                    opacity: *opacity,
+                    show_minor_grid: *show_minor_grid,
+                    minor_spacing: *minor_spacing,
+                    base_spacing: *base_spacing,
+                };
+                commands.entity(entity).insert(grid);
+                entity
+            }
+        };
+
+        let visibility = if object.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        commands.entity(entity).insert((
+            ChildOf(scene),
+            MathObject {
+                id: object.id.clone(),
+                visible: object.visible,
+                layer: object.layer,
+            },
+            Position2D::from(position),
+            Transform {
+                translation: position.extend(0.0),
+                rotation: Quat::from_rotation_z(object.rotation),
+                scale: Vec2::from_array(object.scale).extend(1.0),
+            },
+            visibility,
+        ));
+        entities.insert(object.id.as_str(), entity);
+    }
+
+    for updater in &file.updaters {
+        match updater {
+            UpdaterData::PointOnGraph {
+                object,
+                graph,
+                tracker,
+            } => {
+                commands
+                    .entity(entities[object.as_str()])
+                    .insert(Updater::PointOnGraph {
+                        graph: entities[graph.as_str()],
+                        tracker: entities[tracker.as_str()],
+                    });
+            }
+            UpdaterData::TangentLine {
+                object,
+                graph,
+                tracker,
+                length,
+            } => {
+                commands
+                    .entity(entities[object.as_str()])
+                    .insert(Updater::TangentLine {
+                        graph: entities[graph.as_str()],
+                        tracker: entities[tracker.as_str()],
+                        length: *length,
+                    });
+            }
+            UpdaterData::ValueLabel {
+                tracker,
+                prefix,
+                decimals,
+                anchor,
+                offset,
+            } => {
+                let label = create_value_label(
+                    commands,
+                    entities[tracker.as_str()],
+                    prefix.clone(),
+                    *decimals,
+                    anchor.as_ref().map(|anchor| entities[anchor.as_str()]),
+                    Vec2::from_array(*offset),
+                );
+                commands.entity(label).insert(ChildOf(scene));
+            }
+        }
+    }
+
+    file.animations
+        .iter()
+        .map(|animation| {
+            let entity = create_animation(
+                commands,
+                entities[animation.target.as_str()],
+                MathAnimation {
+                    rate_function: animation.rate_function,
+                    elapsed: animation.elapsed,
+                    is_playing: animation.is_playing,
+                    loop_animation: animation.loop_animation,
+                    ..MathAnimation::new(
+                        animation.animation.to_animation_type(),
+                        animation.duration,
+                    )
+                },
+            );
+            commands.entity(entity).insert(ChildOf(scene));
+            entity
+        })
+        .collect()
+}
+
+/// 按名称恢复参数的值和范围
+fn apply_parameters(parameters: &mut [GraphParameter], saved: &[ParameterData]) {
+    for parameter in parameters {
+        if let Some(data) = saved.iter().find(|data| data.name == parameter.name) {
+            parameter.value = data.value;
+            parameter.min = data.min;
+            parameter.max = data.max;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style() -> StyleData {
+        StyleData {
+            stroke_color: [0.2, 0.4, 0.8, 1.0],
+            fill_color: Some([1.0, 0.5, 0.25, 0.5]),
+            stroke_width: 2.5,
+            opacity: 0.75,
+        }
+    }
+
+    fn object(id: &str, shape: ShapeData) -> ObjectData {
+        ObjectData {
+            id: id.to_string(),
+            layer: 1,
+            visible: true,
+            position: [0.5, -1.25],
+            scale: [1.0, 2.0],
+            rotation: 0.3,
+            style: style(),
+            shape,
+        }
+    }
+
+    fn sample_file() -> SceneFile {
+        let parameters = vec![ParameterData {
+            name: "a".to_string(),
+            value: 1.5,
+            min: -2.0,
+            max: 2.0,
+        }];
+        SceneFile {
+            version: SCENE_FILE_VERSION,
+            scene: SceneSettings {
+                name: "测试".to_string(),
+                background_color: [0.1, 0.1, 0.12, 1.0],
+                unit_size: 80.0,
+            },
+            camera: CameraSettings {
+                zoom: 1.5,
+                translation: [0.25, -0.5],
+            },
+            objects: vec![
+                object(
+                    "dot",
+                    ShapeData::Circle {
+                        radius: 0.08,
+                        filled: true,
+                        resolution: Some(48),
+                    },
+                ),
+                object(
+                    "tangent",
+                    ShapeData::Line {
+                        start: [-1.0, 0.0],
+                        end: [1.0, 0.5],
+                    },
+                ),
+                object(
+                    "box",
+                    ShapeData::Rectangle {
+                        width: 2.0,
+                        height: 1.0,
+                    },
+                ),
+                object(
+                    "graph",
+                    ShapeData::FunctionGraph {
+                        expression: "a sin(x)".to_string(),
+                        domain: [-3.0, 3.0],
+                        sample_count: 200,
+                        tolerance: 0.01,
+                        max_depth: 8,
+                        parameters: parameters.clone(),
+                    },
+                ),
+                object(
+                    "curve",
+                    ShapeData::ParametricCurve {
+                        x: "cos(t)".to_string(),
+                        y: "a sin(t)".to_string(),
+                        range: [0.0, 6.0],
+                        sample_count: 100,
+                        parameters,
+                    },
+                ),
+                object(
+                    "axes",
+                    ShapeData::Axes {
+                        x_range: [-5.0, 5.0],
+                        y_range: [-3.0, 3.0],
+                        tick_spacing: 1.0,
+                        show_numbers: true,
+                        show_arrows: false,
+                        x_label: "x".to_string(),
+                        y_label: "y".to_string(),
+                        base_range: [-5.0, 5.0],
+                    },
+                ),
+                object(
+                    "grid",
+                    ShapeData::Grid {
+                        spacing: 1.0,
+                        opacity: 0.3,
+                        show_minor_grid: true,
+                        minor_spacing: 0.25,
+                        base_spacing: 1.0,
+                    },
+                ),
+            ],
+            trackers: vec![TrackerData {
+                id: "tracker_1".to_string(),
+                value: -2.0,
+            }],
+            updaters: vec![
+                UpdaterData::PointOnGraph {
+                    object: "dot".to_string(),
+                    graph: "graph".to_string(),
+                    tracker: "tracker_1".to_string(),
+                },
+                UpdaterData::TangentLine {
+                    object: "tangent".to_string(),
+                    graph: "graph".to_string(),
+                    tracker: "tracker_1".to_string(),
+                    length: 3.0,
+                },
+                UpdaterData::ValueLabel {
+                    tracker: "tracker_1".to_string(),
+                    prefix: "x = ".to_string(),
+                    decimals: 2,
+                    anchor: Some("dot".to_string()),
+                    offset: [0.0, 24.0],
+                },
+            ],
+            animations: vec![
+                AnimationData {
+                    target: "tracker_1".to_string(),
+                    animation: AnimationTypeData::SetValue { to: 2.0 },
+                    rate_function: RateFunction::Linear,
+                    duration: 3.0,
+                    elapsed: 0.5,
+                    is_playing: true,
+                    loop_animation: false,
+                },
+                AnimationData {
+                    target: "box".to_string(),
+                    animation: AnimationTypeData::Morph {
+                        target: vec![vec![[0.0, 0.0], [1.0, 0.0]], vec![[0.0, 1.0], [1.0, 1.0]]],
+                        style: Some(style()),
+                    },
+                    rate_function: RateFunction::Smooth,
+                    duration: 1.0,
+                    elapsed: 0.0,
+                    is_playing: false,
+                    loop_animation: true,
+                },
+                AnimationData {
+                    target: "axes".to_string(),
+                    animation: AnimationTypeData::Transform {
+                        shift: [1.0, -1.0],
+                        scale: 1.5,
+                        rotation: 0.5,
+                    },
+                    rate_function: RateFunction::EaseInOutCubic,
+                    duration: 2.0,
+                    elapsed: 0.0,
+                    is_playing: false,
+                    loop_animation: false,
+                },
+            ],
+            timeline: TimelineData {
+                blocks: vec![
+                    BlockData::Play { animation: 0 },
+                    BlockData::Wait { seconds: 0.5 },
+                    BlockData::LaggedStart {
+                        lag: 0.25,
+                        blocks: vec![
+                            BlockData::Group {
+                                blocks: vec![BlockData::Play { animation: 1 }],
+                            },
+                            BlockData::Sequence {
+                                blocks: vec![BlockData::Play { animation: 2 }],
+                            },
+                        ],
+                    },
+                ],
+                playhead: 1.25,
+                speed: 2.0,
+            },
+        }
+    }
+
+    /// 解析错误的 JSON 路径
+    fn error_path(source: &str) -> String {
+        match SceneFile::parse(source) {
+            Err(SceneFileError::Json { path, .. } | SceneFileError::Invalid { path, .. }) => path,
+            other => panic!("期望带路径的错误，实际为 {:?}", other),
+        }
+    }
+
+    /// 把 sample_file 的 JSON 中第一个 from 替换为 to
+    fn edited(from: &str, to: &str) -> String {
+        let json = sample_file().to_json().unwrap();
+        assert!(json.contains(from), "JSON 中没有 {from}");
+        json.replacen(from, to, 1)
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let file = sample_file();
+        assert_eq!(SceneFile::parse(&file.to_json().unwrap()).unwrap(), file);
+    }
+
+    #[test]
+    fn files_without_trackers_still_load() {
+        let mut file = sample_file();
+        file.trackers.clear();
+        file.updaters.clear();
+        file.animations.remove(0);
+        file.timeline.blocks.remove(0);
+        for block in &mut file.timeline.blocks {
+            if let BlockData::LaggedStart { blocks, .. } = block {
+                *blocks = vec![BlockData::Play { animation: 0 }];
+            }
+        }
+        let json = file.to_json().unwrap();
+        let json = json
+            .replace("  \"trackers\": [],\n", "")
+            .replace("  \"updaters\": [],\n", "");
+        assert!(!json.contains("trackers"));
+        assert_eq!(SceneFile::parse(&json).unwrap(), file);
+    }
+
+    #[test]
+    fn json_path_at_tracks_keys_and_indices() {
+        let source = "{\n  \"a\": [1, {\"b\\\"c\": [true, \n  null]}],\n  \"d\": 1\n}";
+        assert_eq!(json_path_at(source, 1, 1), "$");
+        assert_eq!(json_path_at(source, 2, 9), "$.a[0]");
+        assert_eq!(json_path_at(source, 2, 12), "$.a[1]");
+        assert_eq!(json_path_at(source, 2, 22), "$.a[1].b\\\"c[0]");
+        assert_eq!(json_path_at(source, 3, 3), "$.a[1].b\\\"c[1]");
+        assert_eq!(json_path_at(source, 4, 8), "$.d");
+    }
+
+    #[test]
+    fn parse_errors_report_json_paths() {
+        assert_eq!(
+            error_path(&edited("\"opacity\": 0.75", "\"opacity\": \"x\"")),
+            "$.objects[0].style.opacity"
+        );
+        assert_eq!(
+            error_path(&edited("\"radius\": 0.08", "\"radius\": 0.08, \"size\": 1")),
+            "$.objects[0]"
+        );
+        assert_eq!(
+            error_path(&edited("\"stroke_width\": 2.5,", "")),
+            "$.objects[0].style"
+        );
+        assert_eq!(
+            error_path(&edited("\"type\": \"set_value\"", "\"type\": \"spin\"")),
+            "$.animations[0].animation.type"
+        );
+        assert_eq!(
+            error_path(&edited("\"seconds\": 0.5", "\"seconds\": null")),
+            "$.timeline.blocks[1]"
+        );
+        assert_eq!(
+            error_path(&edited("\"lag\": 0.25", "\"lag\": \"x\"")),
+            "$.timeline.blocks[2]"
+        );
+    }
+
+    #[test]
+    fn invalid_references_report_json_paths() {
+        assert_eq!(
+            error_path(&edited("\"a sin(x)\"", "\"a sin(\"")),
+            "$.objects[3].shape.expression"
+        );
+        assert_eq!(
+            error_path(&edited("\"id\": \"tracker_1\"", "\"id\": \"dot\"")),
+            "$.trackers[0].id"
+        );
+        assert_eq!(
+            error_path(&edited("\"graph\": \"graph\"", "\"graph\": \"box\"")),
+            "$.updaters[0].graph"
+        );
+        assert_eq!(
+            error_path(&edited("\"anchor\": \"dot\"", "\"anchor\": \"ghost\"")),
+            "$.updaters[2].anchor"
+        );
+        assert_eq!(
+            error_path(&edited(
+                "\"target\": \"tracker_1\"",
+                "\"target\": \"ghost\""
+            )),
+            "$.animations[0].target"
+        );
+        assert_eq!(
+            error_path(&edited("\"animation\": 2", "\"animation\": 3")),
+            "$.timeline.blocks[2].blocks[1].blocks[0].animation"
+        );
+        assert_eq!(
+            error_path(&edited("\"zoom\": 1.5", "\"zoom\": 0.0")),
+            "$.camera.zoom"
+        );
+        assert_eq!(
+            error_path(&edited("\"zoom\": 1.5", "\"zoom\": -2.0")),
+            "$.camera.zoom"
+        );
+        assert_eq!(
+            error_path(&edited("\"unit_size\": 80.0", "\"unit_size\": -5.0")),
+            "$.scene.unit_size"
+        );
+    }
+
+    #[test]
+    fn trackers_and_updaters_survive_respawn() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        let scene = world.spawn(MathScene::default()).id();
+        let file = sample_file();
+        let loaded = file.clone();
+        world
+            .run_system_once(move |mut commands: Commands| {
+                spawn_scene_content(&mut commands, &loaded, scene);
+            })
+            .unwrap();
+        let saved = world
+            .run_system_once(move |capture: CaptureQueries, scenes: Query<&MathScene>| {
+                let timeline = Timeline::default();
+                let camera_state = CameraState::default();
+                capture_scene(
+                    scene,
+                    scenes.get(scene).unwrap(),
+                    &capture,
+                    &timeline,
+                    &camera_state,
+                )
+            })
+            .unwrap();
+
+        let ids = |file: &SceneFile| -> Vec<String> {
+            file.objects
+                .iter()
+                .map(|object| object.id.clone())
+                .collect()
+        };
+        let mut expected = ids(&file);
+        expected.sort();
+        let mut actual = ids(&saved);
+        actual.sort();
+        assert_eq!(actual, expected);
+        assert_eq!(saved.trackers, file.trackers);
+        assert_eq!(saved.updaters, file.updaters);
+        assert_eq!(saved.animations[0], file.animations[0]);
+    }
+
+    #[test]
+    fn saving_the_watched_file_does_not_trigger_a_reload() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let path = std::env::temp_dir().join(format!("rim_watch_{}.json", std::process::id()));
+        std::fs::write(&path, "{}").unwrap();
+        let mut world = World::new();
+        world.spawn(MathScene::default());
+        world.init_resource::<Timeline>();
+        world.init_resource::<CameraState>();
+        world.init_resource::<Events<SaveSceneRequest>>();
+        world.insert_resource(SceneFileState {
+            // 早于保存时间的修改时间
+            watched: Some((path.clone(), Some(SystemTime::UNIX_EPOCH))),
+            ..default()
+        });
+
+        world.send_event(SaveSceneRequest(path.clone()));
+        world.run_system_once(handle_save_requests).unwrap();
+        let state = world.resource::<SceneFileState>();
+        assert!(matches!(state.last_result, Some(Ok(_))));
+        assert_eq!(state.watched, Some((path.clone(), modified_time(&path))));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let json = edited("\"version\": 1", "\"version\": 99");
+        assert!(matches!(
+            SceneFile::parse(&json),
+            Err(SceneFileError::UnsupportedVersion { found: 99 })
+        ));
+    }
+}
@@ -1,20 +1,50 @@
+use crate::animation::{MathAnimation, Timeline, Updater, ValueTracker};
+use crate::interaction::CameraState;
+use crate::math_objects::{create_default_coordinate_system, MathObject};
+use crate::render::{CoordinateSystem, DEFAULT_UNIT_SIZE};
 use bevy::prelude::*;
 
+pub mod file;
+pub mod script;
+
+pub use file::*;
+
 pub struct ScenePlugin;
 
 impl Plugin for ScenePlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<MathScene>()
-            .add_systems(Update, manage_scenes);
+            .init_resource::<SceneFileState>()
+            .add_event::<SaveSceneRequest>()
+            .add_event::<LoadSceneRequest>()
+            .add_event::<SceneLoaded>()
+            .add_event::<SceneAction>()
+            .add_systems(
+                Update,
+                (
+                    adopt_scene_content,
+                    handle_scene_actions.before(crate::animation::drive_timeline),
+                    manage_scenes.after(handle_scene_actions),
+                    handle_save_requests,
+                    watch_scene_file.before(handle_load_requests),
+                    handle_load_requests.before(crate::animation::drive_timeline),
+                ),
+            );
     }
 }
 
 /// 数学场景组件
+///
+/// 场景的内容（数学对象、动画、追踪器和更新器）通过 ChildOf 归属于场景实体，
+/// 同一时刻只有一个活动场景，其余场景整体隐藏。
 #[derive(Component, Reflect, Clone)]
+#[require(Transform, Visibility)]
 pub struct MathScene {
     pub name: String,
     pub active: bool,
     pub background_color: Color,
+    /// 缩放为 1 时每个数学单位对应的像素数
+    pub unit_size: f32,
 }
 
 impl Default for MathScene {
@@ -23,13 +53,214 @@ impl Default for MathScene {
             name: "Default Scene".to_string(),
             active: true,
             background_color: Color::BLACK,
+            unit_size: DEFAULT_UNIT_SIZE,
+        }
+    }
+}
+
+/// 非活动场景暂存的时间轴，切换回该场景时恢复为 Timeline 资源
+#[derive(Component, Default)]
+pub struct SceneTimeline(pub Timeline);
+
+/// 场景列表操作请求
+#[derive(Event, Clone, Debug)]
+pub enum SceneAction {
+    /// 新建带默认网格和坐标轴的场景并切换过去
+    Create { name: String },
+    /// 复制场景中可保存的内容（与保存到文件相同）并切换到副本
+    Duplicate(Entity),
+    /// 删除场景及其全部内容，至少保留一个场景
+    Delete(Entity),
+    /// 切换到场景
+    Activate(Entity),
+}
+
+/// 场景内容：数学对象、动画、数值追踪器和更新器
+pub type SceneContent = Or<(
+    With<MathObject>,
+    With<MathAnimation>,
+    With<ValueTracker>,
+    With<Updater>,
+)>;
+
+/// 内容是否属于场景，尚未归入场景的新内容视为属于活动场景
+pub fn in_scene(child_of: Option<&ChildOf>, scene: Entity, active: bool) -> bool {
+    child_of.map_or(active, |child_of| child_of.parent() == scene)
+}
+
+/// 新生成、尚未归属任何场景的内容归入当前活动场景
+fn adopt_scene_content(
+    mut commands: Commands,
+    orphans: Query<Entity, (SceneContent, Without<ChildOf>)>,
+    scenes: Query<(Entity, &MathScene)>,
+) {
+    let Some((scene, _)) = scenes.iter().find(|(_, scene)| scene.active) else {
+        return;
+    };
+    // 同一帧加载场景时内容可能已被删除
+    for entity in orphans.iter() {
+        commands.entity(entity).try_insert(ChildOf(scene));
+    }
+}
+
+/// 场景管理系统：活动场景决定背景色和单位长度，其余场景隐藏
+fn manage_scenes(
+    mut query: Query<(&MathScene, &mut Visibility), Changed<MathScene>>,
+    mut coordinate_system: ResMut<CoordinateSystem>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    for (scene, mut visibility) in query.iter_mut() {
+        visibility.set_if_neq(if scene.active {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+        if !scene.active {
+            continue;
+        }
+
+        // 活动场景决定坐标映射的单位长度
+        if coordinate_system.unit_size != scene.unit_size {
+            coordinate_system.unit_size = scene.unit_size;
+        }
+        if clear_color.0 != scene.background_color {
+            clear_color.0 = scene.background_color;
+        }
+    }
+}
+
+type SceneEntry<'a> = (Entity, &'a mut MathScene, Option<&'a mut SceneTimeline>);
+
+/// 处理场景列表操作
+///
+/// 时间轴属于场景：切换时当前时间轴暂存到原场景的 SceneTimeline，目标场景的时间轴恢复为资源。
+fn handle_scene_actions(
+    mut commands: Commands,
+    mut actions: EventReader<SceneAction>,
+    mut scenes: Query<SceneEntry>,
+    mut timeline: ResMut<Timeline>,
+    camera_state: Res<CameraState>,
+    capture: CaptureQueries,
+) {
+    for action in actions.read() {
+        match action {
+            SceneAction::Create { name } => {
+                let scene = commands
+                    .spawn(MathScene {
+                        name: name.clone(),
+                        ..default()
+                    })
+                    .id();
+                let (grid, axes) = create_default_coordinate_system(&mut commands);
+                commands.entity(scene).add_children(&[grid, axes]);
+                switch_scene(
+                    &mut commands,
+                    &mut scenes,
+                    &mut timeline,
+                    scene,
+                    Timeline::default(),
+                );
+                info!("新建场景: {}", name);
+            }
+            SceneAction::Duplicate(source) => {
+                let Ok((_, settings, saved)) = scenes.get(*source) else {
+                    continue;
+                };
+                // 非活动场景的时间轴暂存在场景上
+                let empty = Timeline::default();
+                let source_timeline = match (settings.active, saved) {
+                    (true, _) => &*timeline,
+                    (false, Some(saved)) => &saved.0,
+                    (false, None) => &empty,
+                };
+                let file =
+                    capture_scene(*source, settings, &capture, source_timeline, &camera_state);
+                let copy = commands
+                    .spawn(MathScene {
+                        name: format!("{} 副本", settings.name),
+                        active: true,
+                        ..settings.clone()
+                    })
+                    .id();
+                let animations = spawn_scene_content(&mut commands, &file, copy);
+                let copy_timeline = file.timeline.to_timeline(&animations);
+                switch_scene(
+                    &mut commands,
+                    &mut scenes,
+                    &mut timeline,
+                    copy,
+                    copy_timeline,
+                );
+                info!("复制场景: {}", file.scene.name);
+            }
+            SceneAction::Delete(scene) => {
+                if scenes.iter().count() <= 1 {
+                    warn!("至少需要保留一个场景");
+                    continue;
+                }
+                let Ok((_, mut deleted, _)) = scenes.get_mut(*scene) else {
+                    continue;
+                };
+                // 删除活动场景时切换到下一个场景，被删除的场景不再暂存时间轴
+                let was_active = std::mem::replace(&mut deleted.active, false);
+                info!("删除场景: {}", deleted.name);
+                commands.entity(*scene).despawn();
+                if was_active {
+                    let next = scenes
+                        .iter()
+                        .map(|(entity, ..)| entity)
+                        .find(|entity| entity != scene);
+                    if let Some(next) = next {
+                        activate_scene(&mut commands, &mut scenes, &mut timeline, next);
+                    }
+                }
+            }
+            SceneAction::Activate(scene) => {
+                activate_scene(&mut commands, &mut scenes, &mut timeline, *scene);
+            }
         }
     }
 }
 
-/// 场景管理系统
-fn manage_scenes(mut query: Query<&mut MathScene>) {
-    for _scene in query.iter_mut() {
-        // 场景管理逻辑
+/// 切换到已存在的场景，恢复其暂存的时间轴
+fn activate_scene(
+    commands: &mut Commands,
+    scenes: &mut Query<SceneEntry>,
+    timeline: &mut Timeline,
+    scene: Entity,
+) {
+    let Ok((_, target, saved)) = scenes.get_mut(scene) else {
+        return;
+    };
+    if target.active {
+        return;
+    }
+    let restored = saved.map_or_else(Timeline::default, |mut saved| std::mem::take(&mut saved.0));
+    switch_scene(commands, scenes, timeline, scene, restored);
+}
+
+/// 把活动场景换成 scene，原活动场景暂存当前时间轴
+fn switch_scene(
+    commands: &mut Commands,
+    scenes: &mut Query<SceneEntry>,
+    timeline: &mut Timeline,
+    scene: Entity,
+    restored: Timeline,
+) {
+    let mut previous = std::mem::replace(timeline, restored);
+    previous.is_playing = false;
+    for (entity, mut other, _) in scenes.iter_mut() {
+        if entity != scene && other.active {
+            other.active = false;
+            commands
+                .entity(entity)
+                .insert(SceneTimeline(std::mem::take(&mut previous)));
+        }
+    }
+    // 新建的场景本帧尚未进入查询，生成时已设为活动
+    if let Ok((_, mut target, _)) = scenes.get_mut(scene) {
+        target.active = true;
     }
+    commands.entity(scene).remove::<SceneTimeline>();
+    commands.send_event(SceneLoaded);
 }
@@ -0,0 +1,1214 @@
+//! 场景脚本：用纯文本描述对象、样式和动画时间轴
+//!
+//! 每行一条语句，`#` 开始注释；只有紧跟在 `=` 之后的 `#` 是颜色值，如 `fill=#3366ff`，
+//! 因此 `radius=1 # 稍后调整` 与 `radius=1 #add later` 都是注释：
+//!
+//! ```text
+//! scene name="第一课" background=#101018 unit=100
+//! camera zoom=1 center=(0, 0)
+//! grid spacing=1
+//! axes x=(-10, 10) y=(-8, 8) labels=("x", "y")
+//! circle c1 center=(0, 0) radius=1.5 stroke=green width=3
+//! line l1 from=(-2, -1) to=(2, 1) stroke=orange
+//! rect r1 center=(3, 1) size=(2, 1) fill=#3366ff80
+//! graph f1 "a*sin(x)" domain=(-5, 5) a=2
+//! curve p1 x="cos(t)" y="sin(2*t)" range=(0, 6.283)
+//!
+//! play Create(c1) 1.5s smooth
+//! play FadeIn(r1), Shift(c1, (2, 0)) 1s lag=0.2
+//! wait 1
+//! play Morph(c1, r1) 2s ease_in_out_cubic
+//! ```
+//!
+//! - 对象：`circle line rect graph curve axes grid`，名称可省略；
+//!   通用选项 `stroke fill width opacity layer visible`
+//! - 颜色：`#rrggbb`、`#rrggbbaa` 或 `white black gray red green blue yellow orange purple pink cyan`
+//! - 动画：`Create Write FadeIn FadeOut Fade(id, from, to) Shift(id, (x, y)) Scale(id, k)
+//!   Rotate(id, 90deg) Morph(id, target)`，同一行的多个动画同时播放，`lag=` 时错开开始
+//! - 速率函数：`linear smooth ease_in_quad … there_and_back wiggle(n) ease_in_back
+//!   ease_out_back bezier(x1, y1, x2, y2)`
+
+use super::file::{
+    AnimationData, AnimationTypeData, BlockData, CameraSettings, ObjectData, ParameterData,
+    SceneFile, SceneSettings, ShapeData, StyleData, TimelineData, SCENE_FILE_VERSION,
+};
+use super::MathScene;
+use crate::animation::RateFunction;
+use crate::math_objects::{Expression, FunctionGraph, MathPath, ParametricCurve};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use std::fmt;
+use thiserror::Error;
+
+/// 场景脚本文件的扩展名
+pub const SCRIPT_EXTENSION: &str = "rim";
+
+/// 脚本错误，line 与 column 均从 1 开始（列号按字符计）
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("第 {line} 行第 {column} 列: {message}")]
+pub struct ScriptError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// 词法单元
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Identifier(String),
+    /// 数字及紧跟的单位，如 `1.5s`、`90deg`
+    Number(f32, Option<String>),
+    Text(String),
+    Color([f32; 4]),
+    LeftParen,
+    RightParen,
+    Comma,
+    Equals,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Identifier(name) => f.write_str(name),
+            TokenKind::Number(value, unit) => {
+                write!(f, "{}{}", value, unit.as_deref().unwrap_or(""))
+            }
+            TokenKind::Text(text) => write!(f, "\"{}\"", text),
+            TokenKind::Color(_) => f.write_str("颜色"),
+            TokenKind::LeftParen => f.write_str("("),
+            TokenKind::RightParen => f.write_str(")"),
+            TokenKind::Comma => f.write_str(","),
+            TokenKind::Equals => f.write_str("="),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    column: usize,
+}
+
+/// 将一行脚本切分为词法单元
+fn tokenize(source: &str, line: usize) -> Result<Vec<Token>, ScriptError> {
+    let chars: Vec<char> = source.chars().collect();
+    let error = |column: usize, message: String| ScriptError {
+        line,
+        column,
+        message,
+    };
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == '#' {
+            // 选项值位置（'=' 之后）的 # 为颜色 #rrggbb，其余位置开始注释
+            let is_value = tokens
+                .last()
+                .is_some_and(|token: &Token| token.kind == TokenKind::Equals);
+            if !is_value {
+                break;
+            }
+            let digits = chars[i + 1..]
+                .iter()
+                .take_while(|c| c.is_ascii_hexdigit())
+                .count();
+            let hex: String = chars[i + 1..i + 1 + digits].iter().collect();
+            let color =
+                Srgba::hex(&hex).map_err(|_| error(column, format!("颜色 '#{}' 无效", hex)))?;
+            tokens.push(Token {
+                kind: TokenKind::Color(color.to_f32_array()),
+                column,
+            });
+            i += 1 + digits;
+            continue;
+        }
+
+        if c.is_ascii_digit()
+            || c == '.'
+            || (c == '-'
+                && chars
+                    .get(i + 1)
+                    .is_some_and(|next| next.is_ascii_digit() || *next == '.'))
+        {
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[column - 1..i].iter().collect();
+            let value = text
+                .parse::<f32>()
+                .map_err(|_| error(column, format!("数字 '{}' 无效", text)))?;
+            let unit_start = i;
+            while i < chars.len() && chars[i].is_alphabetic() {
+                i += 1;
+            }
+            let unit = (i > unit_start).then(|| chars[unit_start..i].iter().collect());
+            tokens.push(Token {
+                kind: TokenKind::Number(value, unit),
+                column,
+            });
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Identifier(chars[column - 1..i].iter().collect()),
+                column,
+            });
+            continue;
+        }
+
+        if c == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(error(column, "字符串缺少结尾的引号".to_string())),
+                    Some('"') => break,
+                    Some('\\') if chars.get(i + 1).is_some() => {
+                        text.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(c) => {
+                        text.push(*c);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            tokens.push(Token {
+                kind: TokenKind::Text(text),
+                column,
+            });
+            continue;
+        }
+
+        let kind = match c {
+            '(' => TokenKind::LeftParen,
+            ')' => TokenKind::RightParen,
+            ',' => TokenKind::Comma,
+            '=' => TokenKind::Equals,
+            _ => return Err(error(column, format!("无法识别的字符 '{}'", c))),
+        };
+        tokens.push(Token { kind, column });
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+/// 选项或动画参数的值
+#[derive(Debug, Clone)]
+struct Value {
+    kind: ValueKind,
+    column: usize,
+}
+
+#[derive(Debug, Clone)]
+enum ValueKind {
+    Number(f32, Option<String>),
+    Text(String),
+    Color([f32; 4]),
+    Identifier(String),
+    Tuple(Vec<Value>),
+}
+
+/// 一行脚本的语法分析器
+struct LineParser {
+    tokens: Vec<Token>,
+    index: usize,
+    line: usize,
+    /// 行尾的列号，用于报告“意外结束”
+    end_column: usize,
+}
+
+impl LineParser {
+    fn error(&self, column: usize, message: impl Into<String>) -> ScriptError {
+        ScriptError {
+            line: self.line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn peek_kind(&self, offset: usize) -> Option<&TokenKind> {
+        self.tokens
+            .get(self.index + offset)
+            .map(|token| &token.kind)
+    }
+
+    fn next_token(&mut self, expected: &str) -> Result<Token, ScriptError> {
+        match self.tokens.get(self.index) {
+            Some(token) => {
+                self.index += 1;
+                Ok(token.clone())
+            }
+            None => Err(self.error(self.end_column, format!("语句意外结束，期望{}", expected))),
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> Result<Token, ScriptError> {
+        let token = self.next_token(expected)?;
+        if token.kind != kind {
+            return Err(self.error(
+                token.column,
+                format!("期望{}，实际为 '{}'", expected, token.kind),
+            ));
+        }
+        Ok(token)
+    }
+
+    fn identifier(&mut self, expected: &str) -> Result<(String, usize), ScriptError> {
+        let token = self.next_token(expected)?;
+        match token.kind {
+            TokenKind::Identifier(name) => Ok((name, token.column)),
+            kind => Err(self.error(token.column, format!("期望{}，实际为 '{}'", expected, kind))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ScriptError> {
+        let token = self.next_token("值")?;
+        let kind = match token.kind {
+            TokenKind::Number(value, unit) => ValueKind::Number(value, unit),
+            TokenKind::Text(text) => ValueKind::Text(text),
+            TokenKind::Color(color) => ValueKind::Color(color),
+            TokenKind::Identifier(name) => ValueKind::Identifier(name),
+            TokenKind::LeftParen => {
+                let mut items = vec![self.value()?];
+                while self.peek_kind(0) == Some(&TokenKind::Comma) {
+                    self.index += 1;
+                    items.push(self.value()?);
+                }
+                self.expect(TokenKind::RightParen, "')'")?;
+                ValueKind::Tuple(items)
+            }
+            kind => {
+                return Err(self.error(token.column, format!("期望值，实际为 '{}'", kind)));
+            }
+        };
+        Ok(Value {
+            kind,
+            column: token.column,
+        })
+    }
+
+    /// 读取剩余的 `key=value` 选项
+    fn options(&mut self) -> Result<Options, ScriptError> {
+        let mut entries = Vec::new();
+        while self.peek().is_some() {
+            let (key, column) = self.identifier("选项名")?;
+            self.expect(TokenKind::Equals, "'='")?;
+            if entries.iter().any(|(name, _, _)| *name == key) {
+                return Err(self.error(column, format!("选项 '{}' 重复", key)));
+            }
+            entries.push((key, column, self.value()?));
+        }
+        Ok(Options {
+            line: self.line,
+            entries,
+        })
+    }
+}
+
+/// 一条语句的选项，读取后移除，剩余的选项视为未知选项
+struct Options {
+    line: usize,
+    entries: Vec<(String, usize, Value)>,
+}
+
+impl Options {
+    fn take(&mut self, name: &str) -> Option<Value> {
+        let index = self.entries.iter().position(|(key, _, _)| key == name)?;
+        Some(self.entries.remove(index).2)
+    }
+
+    fn number(&mut self, name: &str, default: f32) -> Result<f32, ScriptError> {
+        self.take(name)
+            .map_or(Ok(default), |value| value.number(self.line))
+    }
+
+    /// 必须为正数的选项，如缩放和单位长度
+    fn positive(&mut self, name: &str, default: f32) -> Result<f32, ScriptError> {
+        let Some(value) = self.take(name) else {
+            return Ok(default);
+        };
+        let number = value.number(self.line)?;
+        if number.is_finite() && number > 0.0 {
+            Ok(number)
+        } else {
+            Err(value.error(
+                self.line,
+                format!("选项 '{}' 必须是正数，实际为 {}", name, number),
+            ))
+        }
+    }
+
+    fn point(&mut self, name: &str, default: [f32; 2]) -> Result<[f32; 2], ScriptError> {
+        self.take(name)
+            .map_or(Ok(default), |value| value.point(self.line))
+    }
+
+    fn boolean(&mut self, name: &str, default: bool) -> Result<bool, ScriptError> {
+        self.take(name)
+            .map_or(Ok(default), |value| value.boolean(self.line))
+    }
+
+    fn text(&mut self, name: &str, default: &str) -> Result<String, ScriptError> {
+        self.take(name)
+            .map_or(Ok(default.to_string()), |value| value.text(self.line))
+    }
+
+    /// 所有选项都已读取，否则报告第一个未知选项
+    fn finish(self) -> Result<(), ScriptError> {
+        match self.entries.first() {
+            Some((key, column, _)) => Err(ScriptError {
+                line: self.line,
+                column: *column,
+                message: format!("未知的选项 '{}'", key),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Value {
+    fn error(&self, line: usize, message: String) -> ScriptError {
+        ScriptError {
+            line,
+            column: self.column,
+            message,
+        }
+    }
+
+    fn number(&self, line: usize) -> Result<f32, ScriptError> {
+        match &self.kind {
+            ValueKind::Number(value, None) => Ok(*value),
+            ValueKind::Number(_, Some(unit)) => {
+                Err(self.error(line, format!("此处的数字不能带单位 '{}'", unit)))
+            }
+            _ => Err(self.error(line, "期望数字".to_string())),
+        }
+    }
+
+    /// 时长（秒），可带单位 s
+    fn seconds(&self, line: usize) -> Result<f32, ScriptError> {
+        match &self.kind {
+            ValueKind::Number(value, None) => Ok(*value),
+            ValueKind::Number(value, Some(unit)) if unit == "s" => Ok(*value),
+            ValueKind::Number(_, Some(unit)) => {
+                Err(self.error(line, format!("时长的单位应为 s，实际为 '{}'", unit)))
+            }
+            _ => Err(self.error(line, "期望时长，如 1.5s".to_string())),
+        }
+    }
+
+    /// 角度（弧度），可带单位 deg 或 rad
+    fn angle(&self, line: usize) -> Result<f32, ScriptError> {
+        match &self.kind {
+            ValueKind::Number(value, None) => Ok(*value),
+            ValueKind::Number(value, Some(unit)) if unit == "rad" => Ok(*value),
+            ValueKind::Number(value, Some(unit)) if unit == "deg" => Ok(value.to_radians()),
+            ValueKind::Number(_, Some(unit)) => Err(self.error(
+                line,
+                format!("角度的单位应为 deg 或 rad，实际为 '{}'", unit),
+            )),
+            _ => Err(self.error(line, "期望角度，如 90deg".to_string())),
+        }
+    }
+
+    fn point(&self, line: usize) -> Result<[f32; 2], ScriptError> {
+        match &self.kind {
+            ValueKind::Tuple(items) if items.len() == 2 => {
+                Ok([items[0].number(line)?, items[1].number(line)?])
+            }
+            _ => Err(self.error(line, "期望坐标，如 (1, 2)".to_string())),
+        }
+    }
+
+    fn text(&self, line: usize) -> Result<String, ScriptError> {
+        match &self.kind {
+            ValueKind::Text(text) => Ok(text.clone()),
+            _ => Err(self.error(line, "期望带引号的字符串".to_string())),
+        }
+    }
+
+    fn boolean(&self, line: usize) -> Result<bool, ScriptError> {
+        match &self.kind {
+            ValueKind::Identifier(name) if name == "true" => Ok(true),
+            ValueKind::Identifier(name) if name == "false" => Ok(false),
+            _ => Err(self.error(line, "期望 true 或 false".to_string())),
+        }
+    }
+
+    fn color(&self, line: usize) -> Result<[f32; 4], ScriptError> {
+        match &self.kind {
+            ValueKind::Color(color) => Ok(*color),
+            ValueKind::Identifier(name) => named_color(name)
+                .map(|color| color.to_f32_array())
+                .ok_or_else(|| self.error(line, format!("未知的颜色 '{}'", name))),
+            _ => Err(self.error(line, "期望颜色，如 #33cc33 或 green".to_string())),
+        }
+    }
+}
+
+/// 脚本中可用的颜色名称
+fn named_color(name: &str) -> Option<Srgba> {
+    Some(match name {
+        "white" => Srgba::WHITE,
+        "black" => Srgba::BLACK,
+        "gray" | "grey" => Srgba::rgb(0.5, 0.5, 0.5),
+        "red" => Srgba::rgb(0.9, 0.2, 0.2),
+        "green" => Srgba::rgb(0.2, 0.8, 0.2),
+        "blue" => Srgba::rgb(0.3, 0.6, 1.0),
+        "yellow" => Srgba::rgb(1.0, 0.85, 0.2),
+        "orange" => Srgba::rgb(0.9, 0.6, 0.2),
+        "purple" => Srgba::rgb(0.6, 0.4, 0.9),
+        "pink" => Srgba::rgb(1.0, 0.5, 0.7),
+        "cyan" => Srgba::rgb(0.2, 0.8, 0.9),
+        _ => return None,
+    })
+}
+
+/// 不带参数的速率函数名称
+const RATE_FUNCTIONS: [(&str, RateFunction); 14] = [
+    ("linear", RateFunction::Linear),
+    ("smooth", RateFunction::Smooth),
+    ("ease_in_quad", RateFunction::EaseInQuad),
+    ("ease_out_quad", RateFunction::EaseOutQuad),
+    ("ease_in_out_quad", RateFunction::EaseInOutQuad),
+    ("ease_in_cubic", RateFunction::EaseInCubic),
+    ("ease_out_cubic", RateFunction::EaseOutCubic),
+    ("ease_in_out_cubic", RateFunction::EaseInOutCubic),
+    ("ease_in_expo", RateFunction::EaseInExpo),
+    ("ease_out_expo", RateFunction::EaseOutExpo),
+    ("ease_in_out_expo", RateFunction::EaseInOutExpo),
+    ("there_and_back", RateFunction::ThereAndBack),
+    ("ease_in_back", RateFunction::EaseInBack),
+    ("ease_out_back", RateFunction::EaseOutBack),
+];
+
+/// 已声明的对象，供动画引用
+struct Declared {
+    shape: ShapeData,
+    style: StyleData,
+}
+
+/// 把脚本语句逐条编译为场景文件
+struct Compiler {
+    scene: SceneSettings,
+    camera: CameraSettings,
+    objects: Vec<ObjectData>,
+    declared: HashMap<String, Declared>,
+    animations: Vec<AnimationData>,
+    blocks: Vec<BlockData>,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        let scene = MathScene::default();
+        Self {
+            scene: SceneSettings {
+                name: scene.name,
+                background_color: scene.background_color.to_srgba().to_f32_array(),
+                unit_size: scene.unit_size,
+            },
+            camera: CameraSettings {
+                zoom: 1.0,
+                translation: [0.0, 0.0],
+            },
+            objects: Vec::new(),
+            declared: HashMap::new(),
+            animations: Vec::new(),
+            blocks: Vec::new(),
+        }
+    }
+}
+
+impl Compiler {
+    fn statement(&mut self, parser: &mut LineParser) -> Result<(), ScriptError> {
+        let (keyword, column) = parser.identifier("语句")?;
+        match keyword.as_str() {
+            "scene" => {
+                let mut options = parser.options()?;
+                self.scene.name = options.text("name", &self.scene.name)?;
+                if let Some(value) = options.take("background") {
+                    self.scene.background_color = value.color(parser.line)?;
+                }
+                self.scene.unit_size = options.positive("unit", self.scene.unit_size)?;
+                options.finish()
+            }
+            "camera" => {
+                let mut options = parser.options()?;
+                self.camera.zoom = options.positive("zoom", self.camera.zoom)?;
+                self.camera.translation = options.point("center", self.camera.translation)?;
+                options.finish()
+            }
+            "circle" | "line" | "rect" | "graph" | "curve" | "axes" | "grid" => {
+                self.object(&keyword, parser)
+            }
+            "play" => self.play(parser),
+            "wait" => {
+                let seconds = match parser.peek() {
+                    Some(_) => parser.value()?.seconds(parser.line)?,
+                    None => 1.0,
+                };
+                self.blocks.push(BlockData::Wait { seconds });
+                match parser.peek() {
+                    Some(token) => Err(parser.error(
+                        token.column,
+                        format!("wait 之后多余的内容 '{}'", token.kind),
+                    )),
+                    None => Ok(()),
+                }
+            }
+            _ => Err(parser.error(
+                column,
+                format!(
+                    "未知的语句 '{}'，可用: scene camera circle line rect graph curve axes grid play wait",
+                    keyword
+                ),
+            )),
+        }
+    }
+
+    /// 对象声明：`<kind> [名称] [表达式] key=value ...`
+    fn object(&mut self, kind: &str, parser: &mut LineParser) -> Result<(), ScriptError> {
+        let line = parser.line;
+        // 后面不跟 '=' 的标识符是对象名称
+        let (id, id_column) = match (parser.peek(), parser.peek_kind(1)) {
+            (Some(token), next)
+                if matches!(token.kind, TokenKind::Identifier(_))
+                    && next != Some(&TokenKind::Equals) =>
+            {
+                parser.identifier("对象名称")?
+            }
+            _ => (
+                format!("{}{}", kind, self.objects.len() + 1),
+                parser.end_column,
+            ),
+        };
+        if self.declared.contains_key(&id) {
+            return Err(parser.error(id_column, format!("对象 '{}' 重复声明", id)));
+        }
+        // 函数图形的表达式可以直接写在名称之后
+        let positional = match parser.peek() {
+            Some(Token {
+                kind: TokenKind::Text(_),
+                ..
+            }) if kind == "graph" => Some(parser.value()?),
+            _ => None,
+        };
+        let mut options = parser.options()?;
+
+        let mut style = match kind {
+            "grid" => StyleData {
+                stroke_color: [0.3, 0.3, 0.3, 1.0],
+                fill_color: None,
+                stroke_width: 1.0,
+                opacity: 0.3,
+            },
+            _ => StyleData {
+                stroke_color: [1.0, 1.0, 1.0, 1.0],
+                fill_color: None,
+                stroke_width: 2.0,
+                opacity: 1.0,
+            },
+        };
+        if let Some(value) = options.take("stroke") {
+            style.stroke_color = value.color(line)?;
+        }
+        if let Some(value) = options.take("fill") {
+            style.fill_color = Some(value.color(line)?);
+        }
+        style.stroke_width = options.number("width", style.stroke_width)?;
+        style.opacity = options.number("opacity", style.opacity)?;
+        let layer = match kind {
+            "axes" => -1,
+            "grid" => -2,
+            _ => 0,
+        };
+        let layer = options.number("layer", layer as f32)? as i32;
+        let visible = options.boolean("visible", true)?;
+
+        let (shape, position) = match kind {
+            "circle" => {
+                let center = options.point("center", [0.0, 0.0])?;
+                let resolution = match options.take("resolution") {
+                    Some(value) => Some(value.number(line)?.max(3.0) as u32),
+                    None => None,
+                };
+                let shape = ShapeData::Circle {
+                    radius: options.number("radius", 1.0)?,
+                    filled: style.fill_color.is_some(),
+                    resolution,
+                };
+                (shape, center)
+            }
+            "line" => {
+                let start = options.point("from", [-1.0, 0.0])?;
+                let end = options.point("to", [1.0, 0.0])?;
+                // 直线的位置为中点
+                let midpoint = [(start[0] + end[0]) * 0.5, (start[1] + end[1]) * 0.5];
+                (ShapeData::Line { start, end }, midpoint)
+            }
+            "rect" => {
+                let center = options.point("center", [0.0, 0.0])?;
+                let [width, height] = options.point("size", [2.0, 1.0])?;
+                (ShapeData::Rectangle { width, height }, center)
+            }
+            "graph" => {
+                let expression = match positional.or_else(|| options.take("f")) {
+                    Some(value) => value,
+                    None => {
+                        return Err(
+                            parser.error(id_column, "函数图形缺少表达式，如 graph f1 \"sin(x)\"")
+                        )
+                    }
+                };
+                let source = expression.text(line)?;
+                let variables = parse_expression(&source, &expression, line)?;
+                let defaults = FunctionGraph::default();
+                let domain =
+                    options.point("domain", [defaults.domain_start, defaults.domain_end])?;
+                let shape = ShapeData::FunctionGraph {
+                    expression: source,
+                    domain,
+                    sample_count: defaults.sample_count,
+                    tolerance: defaults.tolerance,
+                    max_depth: defaults.max_depth,
+                    parameters: parameter_options(&mut options, &variables, "x")?,
+                };
+                (shape, [0.0, 0.0])
+            }
+            "curve" => {
+                let mut expression = |name: &str| match options.take(name) {
+                    Some(value) => {
+                        let source = value.text(line)?;
+                        let variables = parse_expression(&source, &value, line)?;
+                        Ok((source, variables))
+                    }
+                    None => Err(parser.error(id_column, format!("参数曲线缺少 {}=\"...\"", name))),
+                };
+                let (x, mut variables) = expression("x")?;
+                let (y, y_variables) = expression("y")?;
+                variables.extend(y_variables);
+                let defaults = ParametricCurve::default();
+                let range = options.point("range", [defaults.param_start, defaults.param_end])?;
+                let shape = ShapeData::ParametricCurve {
+                    x,
+                    y,
+                    range,
+                    sample_count: defaults.sample_count,
+                    parameters: parameter_options(&mut options, &variables, "t")?,
+                };
+                (shape, [0.0, 0.0])
+            }
+            "axes" => {
+                let x_range = options.point("x", [-10.0, 10.0])?;
+                let y_range = options.point("y", [-8.0, 8.0])?;
+                let (x_label, y_label) = match options.take("labels") {
+                    Some(Value {
+                        kind: ValueKind::Tuple(items),
+                        ..
+                    }) if items.len() == 2 => (items[0].text(line)?, items[1].text(line)?),
+                    Some(value) => {
+                        return Err(value.error(line, "期望两个标签，如 (\"x\", \"y\")".to_string()))
+                    }
+                    None => ("x".to_string(), "y".to_string()),
+                };
+                let shape = ShapeData::Axes {
+                    x_range,
+                    y_range,
+                    tick_spacing: options.number("ticks", 1.0)?,
+                    show_numbers: options.boolean("numbers", true)?,
+                    show_arrows: options.boolean("arrows", true)?,
+                    x_label,
+                    y_label,
+                    base_range: [
+                        (x_range[1] - x_range[0]).abs(),
+                        (y_range[1] - y_range[0]).abs(),
+                    ],
+                };
+                (shape, [0.0, 0.0])
+            }
+            _ => {
+                let spacing = options.number("spacing", 1.0)?;
+                let shape = ShapeData::Grid {
+                    spacing,
+                    opacity: style.opacity,
+                    show_minor_grid: options.boolean("minor", true)?,
+                    minor_spacing: spacing / 5.0,
+                    base_spacing: spacing,
+                };
+                (shape, [0.0, 0.0])
+            }
+        };
+        options.finish()?;
+
+        self.declared.insert(
+            id.clone(),
+            Declared {
+                shape: shape.clone(),
+                style: style.clone(),
+            },
+        );
+        self.objects.push(ObjectData {
+            id,
+            layer,
+            visible,
+            position,
+            scale: [1.0, 1.0],
+            rotation: 0.0,
+            style,
+            shape,
+        });
+        Ok(())
+    }
+
+    /// `play 动画(对象, ...)[, ...] [时长] [速率函数] [lag=秒]`
+    fn play(&mut self, parser: &mut LineParser) -> Result<(), ScriptError> {
+        let line = parser.line;
+        let mut animations = vec![self.animation(parser)?];
+        while parser.peek_kind(0) == Some(&TokenKind::Comma) {
+            parser.index += 1;
+            animations.push(self.animation(parser)?);
+        }
+
+        let mut duration = 1.0;
+        let mut rate_function = RateFunction::Smooth;
+        let mut lag = None;
+        while let Some(token) = parser.peek().cloned() {
+            match &token.kind {
+                TokenKind::Number(..) => duration = parser.value()?.seconds(line)?,
+                TokenKind::Identifier(name) if name == "lag" => {
+                    parser.index += 1;
+                    parser.expect(TokenKind::Equals, "'='")?;
+                    lag = Some(parser.value()?.seconds(line)?);
+                }
+                TokenKind::Identifier(_) => rate_function = rate_function_value(parser)?,
+                kind => {
+                    return Err(parser.error(
+                        token.column,
+                        format!("期望时长、速率函数或 lag=，实际为 '{}'", kind),
+                    ))
+                }
+            }
+        }
+        if duration < 0.0 {
+            return Err(parser.error(parser.end_column, "动画时长不能为负数"));
+        }
+
+        let first = self.animations.len();
+        for (target, animation) in animations {
+            self.animations.push(AnimationData {
+                target,
+                animation,
+                rate_function,
+                duration,
+                elapsed: 0.0,
+                is_playing: false,
+                loop_animation: false,
+            });
+        }
+        let mut plays: Vec<_> = (first..self.animations.len())
+            .map(|animation| BlockData::Play { animation })
+            .collect();
+        self.blocks.push(match (plays.len(), lag) {
+            (1, _) => plays.remove(0),
+            (_, Some(lag)) => BlockData::LaggedStart { lag, blocks: plays },
+            (_, None) => BlockData::Group { blocks: plays },
+        });
+        Ok(())
+    }
+
+    /// 单个动画，如 `Create(c1)`、`Shift(c1, (2, 0))`，返回目标名称和动画
+    fn animation(
+        &self,
+        parser: &mut LineParser,
+    ) -> Result<(String, AnimationTypeData), ScriptError> {
+        let line = parser.line;
+        let (name, name_column) = parser.identifier("动画，如 Create(c1)")?;
+        parser.expect(TokenKind::LeftParen, "'('")?;
+        let (target, target_column) = parser.identifier("对象名称")?;
+        if !self.declared.contains_key(&target) {
+            return Err(parser.error(target_column, format!("对象 '{}' 未声明", target)));
+        }
+        let mut arguments = Vec::new();
+        while parser.peek_kind(0) == Some(&TokenKind::Comma) {
+            parser.index += 1;
+            arguments.push(parser.value()?);
+        }
+        let close = parser.expect(TokenKind::RightParen, "')'")?;
+
+        let expected = match name.as_str() {
+            "Create" | "Write" | "FadeIn" | "FadeOut" => 0,
+            "Shift" | "Scale" | "Rotate" | "Morph" => 1,
+            "Fade" => 2,
+            _ => {
+                return Err(parser.error(
+                    name_column,
+                    format!(
+                        "未知的动画 '{}'，可用: Create Write FadeIn FadeOut Fade Shift Scale Rotate Morph",
+                        name
+                    ),
+                ))
+            }
+        };
+        if arguments.len() != expected {
+            return Err(parser.error(
+                close.column,
+                format!(
+                    "{} 需要 {} 个参数，实际为 {} 个",
+                    name,
+                    expected,
+                    arguments.len()
+                ),
+            ));
+        }
+
+        let animation = match name.as_str() {
+            "Create" => AnimationTypeData::Draw,
+            "Write" => AnimationTypeData::Write,
+            "FadeIn" => AnimationTypeData::Fade { from: 0.0, to: 1.0 },
+            "FadeOut" => AnimationTypeData::Fade { from: 1.0, to: 0.0 },
+            "Fade" => AnimationTypeData::Fade {
+                from: arguments[0].number(line)?,
+                to: arguments[1].number(line)?,
+            },
+            "Shift" => AnimationTypeData::Transform {
+                shift: arguments[0].point(line)?,
+                scale: 1.0,
+                rotation: 0.0,
+            },
+            "Scale" => AnimationTypeData::Transform {
+                shift: [0.0, 0.0],
+                scale: arguments[0].number(line)?,
+                rotation: 0.0,
+            },
+            "Rotate" => AnimationTypeData::Transform {
+                shift: [0.0, 0.0],
+                scale: 1.0,
+                rotation: arguments[0].angle(line)?,
+            },
+            _ => {
+                let argument = &arguments[0];
+                let other = match &argument.kind {
+                    ValueKind::Identifier(other) => other,
+                    _ => return Err(argument.error(line, "期望变形目标的对象名称".to_string())),
+                };
+                let Some(declared) = self.declared.get(other) else {
+                    return Err(argument.error(line, format!("对象 '{}' 未声明", other)));
+                };
+                let path = match &declared.shape {
+                    ShapeData::Circle { radius, .. } => MathPath::circle(*radius),
+                    ShapeData::Rectangle { width, height } => MathPath::rectangle(*width, *height),
+                    ShapeData::Line { start, end } => {
+                        let (start, end) = (Vec2::from_array(*start), Vec2::from_array(*end));
+                        let midpoint = (start + end) * 0.5;
+                        MathPath::from_polyline(&[start - midpoint, end - midpoint], false)
+                    }
+                    _ => {
+                        return Err(
+                            argument.error(line, "变形目标必须是圆形、直线或矩形".to_string())
+                        )
+                    }
+                };
+                AnimationTypeData::Morph {
+                    target: path
+                        .subpaths()
+                        .iter()
+                        .map(|subpath| subpath.iter().map(|point| point.to_array()).collect())
+                        .collect(),
+                    style: Some(declared.style.clone()),
+                }
+            }
+        };
+        Ok((target, animation))
+    }
+
+    fn finish(self) -> SceneFile {
+        SceneFile {
+            version: SCENE_FILE_VERSION,
+            scene: self.scene,
+            camera: self.camera,
+            objects: self.objects,
+            trackers: Vec::new(),
+            updaters: Vec::new(),
+            animations: self.animations,
+            timeline: TimelineData {
+                blocks: self.blocks,
+                playhead: 0.0,
+                speed: 1.0,
+            },
+        }
+    }
+}
+
+/// 解析表达式字符串，错误定位到脚本中的字符；返回表达式中的变量
+fn parse_expression(source: &str, value: &Value, line: usize) -> Result<Vec<String>, ScriptError> {
+    match Expression::parse(source) {
+        Ok(expression) => Ok(expression.variables().to_vec()),
+        // 列号从引号之后开始计算，无法定位到字符的错误指向结尾的引号
+        Err(error) => Err(ScriptError {
+            line,
+            column: value.column + error.column().unwrap_or(source.chars().count() + 1),
+            message: error.to_string(),
+        }),
+    }
+}
+
+/// 以选项形式给出的参数初值，如 `a=2`
+fn parameter_options(
+    options: &mut Options,
+    variables: &[String],
+    variable: &str,
+) -> Result<Vec<ParameterData>, ScriptError> {
+    let mut parameters = Vec::new();
+    for name in variables {
+        if name == variable
+            || parameters
+                .iter()
+                .any(|parameter: &ParameterData| &parameter.name == name)
+        {
+            continue;
+        }
+        if let Some(value) = options.take(name) {
+            let value = value.number(options.line)?;
+            parameters.push(ParameterData {
+                name: name.clone(),
+                value,
+                min: value.min(-10.0),
+                max: value.max(10.0),
+            });
+        }
+    }
+    Ok(parameters)
+}
+
+/// 速率函数，如 `smooth`、`wiggle(3)`、`bezier(0.25, 0.1, 0.25, 1)`
+fn rate_function_value(parser: &mut LineParser) -> Result<RateFunction, ScriptError> {
+    let line = parser.line;
+    let (name, column) = parser.identifier("速率函数")?;
+    let mut arguments = Vec::new();
+    if parser.peek_kind(0) == Some(&TokenKind::LeftParen) {
+        parser.index += 1;
+        arguments.push(parser.value()?.number(line)?);
+        while parser.peek_kind(0) == Some(&TokenKind::Comma) {
+            parser.index += 1;
+            arguments.push(parser.value()?.number(line)?);
+        }
+        parser.expect(TokenKind::RightParen, "')'")?;
+    }
+
+    let rate_function = match (name.as_str(), arguments.as_slice()) {
+        ("wiggle", []) => RateFunction::Wiggle { wiggles: 2.0 },
+        ("wiggle", [wiggles]) => RateFunction::Wiggle { wiggles: *wiggles },
+        ("bezier", [x1, y1, x2, y2]) => RateFunction::CubicBezier {
+            x1: *x1,
+            y1: *y1,
+            x2: *x2,
+            y2: *y2,
+        },
+        (name, []) => match RATE_FUNCTIONS.iter().find(|(key, _)| *key == name) {
+            Some((_, rate_function)) => *rate_function,
+            None => {
+                return Err(parser.error(column, format!("未知的速率函数 '{}'", name)));
+            }
+        },
+        (name, _) => {
+            return Err(parser.error(column, format!("速率函数 '{}' 的参数个数不正确", name)));
+        }
+    };
+    Ok(rate_function)
+}
+
+/// 把场景脚本编译为场景文件，生成的实体与加载 JSON 场景完全相同
+pub fn parse_script(source: &str) -> Result<SceneFile, ScriptError> {
+    let mut compiler = Compiler::default();
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let tokens = tokenize(text, line)?;
+        if tokens.is_empty() {
+            continue;
+        }
+        let mut parser = LineParser {
+            tokens,
+            index: 0,
+            line,
+            end_column: text.chars().count() + 1,
+        };
+        compiler.statement(&mut parser)?;
+    }
+    Ok(compiler.finish())
+}
+
+/// 路径是否为场景脚本（按扩展名判断）
+pub fn is_script_path(path: &std::path::Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == SCRIPT_EXTENSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 编译 source，返回错误的行号、列号和信息
+    fn error(source: &str) -> (usize, usize, String) {
+        let error = parse_script(source).unwrap_err();
+        (error.line, error.column, error.message)
+    }
+
+    #[test]
+    fn compiles_intro_scene() {
+        let file = parse_script(include_str!("../../scenes/intro.rim")).unwrap();
+        assert_eq!(file.scene.name, "第一课");
+        let background = Srgba::hex("101018").unwrap().to_f32_array();
+        assert_eq!(file.scene.background_color, background);
+
+        let ids: Vec<&str> = file
+            .objects
+            .iter()
+            .map(|object| object.id.as_str())
+            .collect();
+        assert_eq!(ids, ["grid1", "axes2", "c1", "r1", "f1"]);
+        let rect = &file.objects[3];
+        assert_eq!(rect.position, [3.0, 1.0]);
+        assert_eq!(
+            rect.style.fill_color,
+            Some(Srgba::hex("3366ff40").unwrap().to_f32_array())
+        );
+
+        let targets: Vec<&str> = file
+            .animations
+            .iter()
+            .map(|animation| animation.target.as_str())
+            .collect();
+        assert_eq!(targets, ["c1", "r1", "c1", "f1", "c1", "r1"]);
+        assert!(matches!(
+            file.timeline.blocks[1],
+            BlockData::LaggedStart { lag, ref blocks } if lag == 0.3 && blocks.len() == 2
+        ));
+        assert_eq!(file.timeline.blocks[2], BlockData::Wait { seconds: 0.5 });
+        assert_eq!(file.timeline.blocks.len(), 6);
+
+        // 编译结果与 JSON 场景文件一样通过校验
+        assert_eq!(SceneFile::parse(&file.to_json().unwrap()).unwrap(), file);
+    }
+
+    #[test]
+    fn hash_is_a_colour_only_after_equals() {
+        let file = parse_script(
+            "# 注释行\ncircle c1 radius=1 #add later\nrect r1 fill=#abc # 半透明\ncircle c2 fill= #ff000080",
+        )
+        .unwrap();
+        assert_eq!(file.objects.len(), 3);
+        assert_eq!(
+            file.objects[0].shape,
+            ShapeData::Circle {
+                radius: 1.0,
+                filled: false,
+                resolution: None,
+            }
+        );
+        assert_eq!(file.objects[0].style.fill_color, None);
+        assert_eq!(
+            file.objects[1].style.fill_color,
+            Some(Srgba::hex("abc").unwrap().to_f32_array())
+        );
+        assert_eq!(
+            file.objects[2].style.fill_color,
+            Some([1.0, 0.0, 0.0, 128.0 / 255.0])
+        );
+
+        assert_eq!(
+            error("rect r1 fill=#xyz"),
+            (1, 14, "颜色 '#' 无效".to_string())
+        );
+    }
+
+    #[test]
+    fn errors_point_at_line_and_column() {
+        let (line, column, _) = error("\nfoo 1");
+        assert_eq!((line, column), (2, 1));
+        assert_eq!(
+            error("circle c1\nplay Create(c2)"),
+            (2, 13, "对象 'c2' 未声明".to_string())
+        );
+        assert_eq!(
+            error("circle c1 size=1"),
+            (1, 11, "未知的选项 'size'".to_string())
+        );
+        assert_eq!(
+            error("circle c1 radius="),
+            (1, 18, "语句意外结束，期望值".to_string())
+        );
+        assert_eq!(
+            error("circle c1\ncircle c1"),
+            (2, 8, "对象 'c1' 重复声明".to_string())
+        );
+        assert_eq!(error("graph f1 \"sin(x)").1, 10);
+        assert_eq!(error("graph f1 \"sin(x) +\"").1, 19);
+        assert_eq!(error("circle c1 center=(1, 2 radius=3").1, 24);
+        assert_eq!(error("circle c1\nplay Create(c1) 1min").1, 17);
+        assert_eq!(error("circle c1\nplay Create(c1) bouncy").1, 17);
+        assert_eq!(error("circle c1 width=@").1, 17);
+        assert_eq!(
+            error("camera zoom=0"),
+            (1, 13, "选项 'zoom' 必须是正数，实际为 0".to_string())
+        );
+        assert_eq!(
+            error("scene name=\"s\"\nscene unit=-5"),
+            (2, 12, "选项 'unit' 必须是正数，实际为 -5".to_string())
+        );
+    }
+
+    #[test]
+    fn morph_arguments_are_checked() {
+        let declarations = "circle c1\nrect r1 size=(4, 2)\nline l1\ngraph f1 \"x\"\n";
+        let morph = |arguments: &str| error(&format!("{declarations}play Morph({arguments})"));
+
+        assert_eq!(
+            morph("c1"),
+            (5, 14, "Morph 需要 1 个参数，实际为 0 个".to_string())
+        );
+        assert_eq!(
+            morph("c1, r1, l1"),
+            (5, 22, "Morph 需要 1 个参数，实际为 2 个".to_string())
+        );
+        assert_eq!(
+            morph("c1, (1, 2)"),
+            (5, 16, "期望变形目标的对象名称".to_string())
+        );
+        assert_eq!(morph("c1, r9"), (5, 16, "对象 'r9' 未声明".to_string()));
+        assert_eq!(
+            morph("c1, f1"),
+            (5, 16, "变形目标必须是圆形、直线或矩形".to_string())
+        );
+
+        let file = parse_script(&format!("{declarations}play Morph(c1, r1) 2s")).unwrap();
+        let AnimationTypeData::Morph { target, style } = &file.animations[0].animation else {
+            panic!("期望变形动画");
+        };
+        let points: Vec<[f32; 2]> = target.iter().flatten().copied().collect();
+        assert!(points.iter().all(|[x, y]| x.abs() <= 2.0 && y.abs() <= 1.0));
+        assert!(points.contains(&[2.0, 1.0]));
+        assert_eq!(style.as_ref(), Some(&file.objects[1].style));
+        assert_eq!(file.animations[0].duration, 2.0);
+    }
+}